@@ -3,4 +3,23 @@ fn main() {
 
     #[cfg(target_os="macos")]
     println!("cargo:rustc-link-arg=-Wl,-rpath,@loader_path");
-}
\ No newline at end of file
+
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "c-header")]
+    generate_header();
+}
+
+/// Generates the C header of the functions in src/ffi.rs into OUT_DIR. The checked-in
+/// include/memory_game.h is a copy of it, the build doesn't write into the source tree.
+#[cfg(feature = "c-header")]
+fn generate_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let out = std::path::PathBuf::from(std::env::var("OUT_DIR").expect("cargo sets OUT_DIR"));
+    let config = cbindgen::Config::from_file("cbindgen.toml").expect("cbindgen.toml is broken");
+    match cbindgen::Builder::new().with_config(config).with_src("src/ffi.rs").generate() {
+        Ok(bindings) => { bindings.write_to_file(out.join("memory_game.h")); },
+        // a syntax error in ffi.rs is reported by the compiler, don't fail here
+        Err(e) => { println!("cargo:warning=Can't generate memory_game.h: {}", e); },
+    }
+}
@@ -0,0 +1,157 @@
+/* Generated from src/ffi.rs by cbindgen, don't edit. */
+
+#ifndef MEMORY_GAME_H
+#define MEMORY_GAME_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum MgCellState {
+  MG_CELL_STATE_FACE_DOWN = 0,
+  MG_CELL_STATE_FACE_UP = 1,
+  // the card has been taken as part of a pair
+  MG_CELL_STATE_REMOVED = 2,
+} MgCellState;
+
+// The result of a call
+typedef enum MgError {
+  MG_ERROR_OK = 0,
+  // the action isn't allowed in the current state, see mg_game_expected_action()
+  MG_ERROR_ILLEGAL_ACTION = 1,
+  // the coordinate is outside of the field
+  MG_ERROR_OUT_OF_BOUNDS = 2,
+  // there is no card at the coordinate any more
+  MG_ERROR_NO_CARD = 3,
+  // the card is already face up
+  MG_ERROR_ALREADY_OPEN = 4,
+  // the game has no players
+  MG_ERROR_NO_PLAYERS = 5,
+  // a pointer argument is NULL
+  MG_ERROR_NULL_POINTER = 6,
+  // an argument is invalid, e.g. a player name that isn't UTF-8 or an unknown player
+  MG_ERROR_INVALID_ARGUMENT = 7,
+} MgError;
+
+// What the current player has to do next, see ExpectedAction
+typedef enum MgExpected {
+  MG_EXPECTED_FLIP_FIRST_CARD = 0,
+  MG_EXPECTED_FLIP_SECOND_CARD = 1,
+  MG_EXPECTED_CHECK_RESULT = 2,
+  MG_EXPECTED_PASS_TURN = 3,
+  MG_EXPECTED_NEW_GAME = 4,
+} MgExpected;
+
+// A game. C code only gets pointers to it.
+typedef struct MgGame MgGame;
+
+// What can be seen of a card slot. card_type is -1 unless the card is face up.
+typedef struct MgCell {
+  enum MgCellState state;
+  int32_t card_type;
+} MgCell;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a game with a field of rows x cols cards and no players. Returns NULL if the field
+// has no cards, an odd number of cards or more than 64 rows or columns.
+struct MgGame *mg_game_new(size_t rows, size_t cols);
+
+// Frees a game. NULL is ignored.
+//
+// # Safety
+// The game must come from mg_game_new() and must not be used afterwards.
+void mg_game_free(struct MgGame *game);
+
+// Adds a player. The name is copied, it must be UTF-8.
+//
+// # Safety
+// The game must be valid and name a NUL terminated string.
+enum MgError mg_game_add_player(struct MgGame *game, const char *name);
+
+// Starts a new game. The cards are shuffled with the seed, so the same seed gives the same game.
+//
+// # Safety
+// The game must be valid.
+enum MgError mg_game_reset(struct MgGame *game, uint64_t seed);
+
+// Turns the card at row, col face up.
+//
+// # Safety
+// The game must be valid.
+enum MgError mg_game_flip(struct MgGame *game, size_t row, size_t col);
+
+// Checks the two open cards, or passes the turn on after that.
+//
+// # Safety
+// The game must be valid.
+enum MgError mg_game_confirm(struct MgGame *game);
+
+// Returns what the current player has to do next, MG_EXPECTED_NEW_GAME for NULL.
+//
+// # Safety
+// The game must be valid or NULL.
+enum MgExpected mg_game_expected_action(const struct MgGame *game);
+
+// Returns true if the last two cards checked were a pair.
+//
+// # Safety
+// The game must be valid or NULL.
+bool mg_game_last_guess_success(const struct MgGame *game);
+
+// Returns the number of rows of the field, 0 for NULL.
+//
+// # Safety
+// The game must be valid or NULL.
+size_t mg_game_rows(const struct MgGame *game);
+
+// Returns the number of columns of the field, 0 for NULL.
+//
+// # Safety
+// The game must be valid or NULL.
+size_t mg_game_cols(const struct MgGame *game);
+
+// Writes what can be seen of the card slot at row, col into cell.
+//
+// # Safety
+// The game must be valid and cell point to an MgCell.
+enum MgError mg_game_cell(const struct MgGame *game, size_t row, size_t col, struct MgCell *cell);
+
+// Returns the number of players, 0 for NULL.
+//
+// # Safety
+// The game must be valid or NULL.
+size_t mg_game_player_count(const struct MgGame *game);
+
+// Returns the index of the player whose turn it is, 0 for NULL.
+//
+// # Safety
+// The game must be valid or NULL.
+size_t mg_game_current_player(const struct MgGame *game);
+
+// Writes the points of the player under the rules of the game into score. They can be negative.
+// Only with the classic rules are they the number of pairs; before there were other rules this
+// function wrote the pairs as size_t, mg_game_pairs() does that now.
+//
+// # Safety
+// The game must be valid and score point to an int32_t.
+enum MgError mg_game_score(const struct MgGame *game, size_t player, int32_t *score);
+
+// Writes the number of pairs the player has found into pairs, whatever the rules.
+//
+// # Safety
+// The game must be valid and pairs point to a size_t.
+enum MgError mg_game_pairs(const struct MgGame *game, size_t player, size_t *pairs);
+
+// Returns a short English description of the error code. The string is static, don't free it.
+const char *mg_error_message(enum MgError error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MEMORY_GAME_H */
@@ -1,158 +1,461 @@
 
-use crate::model::game_model::{Coord, Game, GameState};
+use crate::model::game_model::{Action, Coord, ExpectedAction, Game, GameError, GameEvent};
+use crate::model::record::GameRecord;
 use crate::view::board_view;
+use crate::view::announcer::Announcer;
+use crate::view::layout::Layout;
+use crate::view::sound::{SoundEvent, SoundPack, SoundPlayer};
+use crate::view::accessibility::Accessibility;
+use crate::view::theme::ThemeSet;
+use crate::view::ui_state::{Menu, MenuItem, UiState};
+use crate::controller::input::{action_from_key, InputAction};
+use crate::controller::gamepad::{Gamepads, PadInput};
+use crate::i18n::Languages;
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 // use sdl2::libc::winsize;
 use sdl2::mouse::MouseButton;
-use sdl2::rect::Rect; //, sys::SDL_QuitEvent};
-use sdl2::pixels::Color;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 // use sdl2::video::WindowBuilder;
 
 pub struct Control {
     pub game : Game,
+    pub ui : UiState,
+    pub themes : ThemeSet,
+    pub languages : Languages,
+    /// additional directories to search for fonts
+    pub font_dirs : Vec<PathBuf>,
+    pub gamepads : Option<Gamepads>,
+    /// directory of the sound pack, the synthesized sounds are used if not set
+    pub sound_pack_dir : Option<PathBuf>,
+    sound : Option<SoundPlayer>,
+    pub accessibility : Accessibility,
+    /// tells screen readers what happens in the game
+    pub announcer : Announcer,
+    pub running : bool,
+    /// Selections (clicks, Enter, A button) made while an animation was running. They are
+    /// handled once the animation is over.
+    pending_selections : VecDeque<Option<Coord>>,
+    /// If set, the result of a turn is checked automatically and shown for this duration before
+    /// the next player gets the turn.
+    pub auto_turn_delay : Option<Duration>,
+    /// The time the result of the current turn was shown first
+    result_shown_at : Option<Instant>,
+    /// If set, every action is added to the record, which is saved to the file right away
+    pub recording : Option<(PathBuf, GameRecord)>,
 }
 
-fn calculate_card_coord_from_mouse_click(y : i32, x : i32, screen_left : i32, screen_top : i32, screen_height : u32, screen_width : u32) -> Option<Coord> {
-    let padding_left: i32  = 5 + screen_left;
-    let padding_top : i32 = 5 + screen_top;
-    let screen_height_i32: i32 = screen_height.try_into().unwrap();
-    let screen_width_i32: i32 = screen_width.try_into().unwrap();
-    let y_max : i32 = screen_height_i32 - 5 + screen_top;
-    let x_max : i32 = screen_width_i32 - 5 + screen_left;
-
-    if y > padding_top || y < y_max || x > padding_left || x < x_max {
-        let card_width_plus_padding : u32 = screen_width / 8;
-        let card_height_plus_padding : u32 = screen_height / 8;
-        let x_minus_padding : u32 = (x - padding_left).try_into().unwrap();
-        let y_minus_padding : u32 = (y - padding_top).try_into().unwrap();
-        let col : u32 = (x_minus_padding) / card_width_plus_padding;
-        let row : u32 = (y_minus_padding) / card_height_plus_padding;
-        let coord = Coord(row.try_into().unwrap(), col.try_into().unwrap());
-        return Some(coord);
-    }
-    None
-}
-
-
+/// Maximum number of selections kept while an animation is running. Further input is dropped.
+const MAX_PENDING_SELECTIONS : usize = 2;
 
 impl Control {
     pub fn new(height : usize, width : usize) -> Control {
         Control {
             game : Game::new(height, width),
+            ui : UiState::new(),
+            themes : ThemeSet::load(None),
+            languages : Languages::load(None),
+            font_dirs : Vec::new(),
+            gamepads : None,
+            sound_pack_dir : None,
+            sound : None,
+            accessibility : Accessibility::default(),
+            announcer : Announcer::none(),
+            running : true,
+            pending_selections : VecDeque::new(),
+            auto_turn_delay : None,
+            result_shown_at : None,
+            recording : None,
         }
     }
 
+    /// Starts a new game with the players added so far.
     pub fn reset(&mut self) {
-        self.game.reset();
+        self.ui.reset_cursors();
+        self.ui.animations.clear();
+        self.pending_selections.clear();
+        self.result_shown_at = None;
+        self.apply(Action::Reset);
     }
-    
-    fn handle_mouse_click(&mut self, y : i32, x : i32, screen_left : i32, screen_top : i32, screen_height : u32, screen_width : u32) {
-        let state = self.game.game_state();
-
-        if state == GameState::GameOver {
-            print!("Game over. Resetting ... ");
-            self.game.reset(); // state == StartSelectCards
-            let p = self.game.current_player();
-            println!("Player {}, select your first card", p.name);
+
+    /// Handles a selection right away or queues it if an animation is running.
+    fn select(&mut self, c : Option<Coord>) {
+        if self.ui.animations.is_busy(Instant::now()) {
+            if self.pending_selections.len() < MAX_PENDING_SELECTIONS {
+                self.pending_selections.push_back(c);
+            }
             return;
         }
+        self.handle_selection(c);
+    }
 
-        if state == GameState::StartSelectCards || state == GameState::StartGame {
-            let p = self.game.current_player();
-            let c = calculate_card_coord_from_mouse_click(y, x, screen_left, screen_top, screen_height, screen_width);
-            if c.is_none() {
-                println!("Player {}, select your first card", p.name);
-                return;
+    /// Handles the queued selections once the animations are over.
+    fn handle_pending_selections(&mut self) {
+        let now = Instant::now();
+        self.ui.animations.remove_finished(now);
+        if !self.ui.animations.is_busy(now) {
+            if let Some(c) = self.pending_selections.pop_front() {
+                self.handle_selection(c);
             }
-            let c = c.unwrap();
-            let card_opened = self.game.open_card(&c); // state is FirstCard if success
-            if card_opened {
-                println!("Card Opened at ({}, {})", c.0, c.1);
-                println!("Player {}, select your second card", p.name);
-                return;
+        }
+    }
+    
+    /// Handles a mouse click. The layout is the one the field was drawn with, so the click is
+    /// mapped to the card the user sees under the mouse.
+    fn handle_mouse_click(&mut self, y : i32, x : i32, layout : &Layout) {
+        if self.ui.menu.is_some() {
+            return;
+        }
+        let c = layout.coord_at(y, x);
+        if let Some(coord) = &c {
+            if self.game.coord_has_card(coord) {
+                self.ui.set_cursor(self.game.current_player_id, coord.clone());
             }
-            println!("No card opened.");
-            println!("Player {}, select your first card", p.name);
-            return ;
         }
+        self.ui.show_cursor = false;
+        self.select(c);
+    }
 
-        if state == GameState::FirstCard {
-            let p = self.game.current_player();
-            let c = calculate_card_coord_from_mouse_click(y, x, screen_left, screen_top, screen_height, screen_width);
-            if c.is_none() {
-                println!("Player {}, select your second card", p.name);
-                return;
+    /// Moves the cursor of the given player into the given direction. Slots without a card are skipped.
+    fn move_cursor(&mut self, player_id : usize, dy : i32, dx : i32) {
+        if player_id == self.game.current_player_id {
+            self.ui.show_cursor = true;
+        }
+        if let Some(next) = self.game.field.next_card_slot(&self.ui.cursor(player_id), dy, dx) {
+            self.ui.set_cursor(player_id, next);
+        }
+        self.announce_cursor(player_id);
+    }
+
+    /// Moves the cursors to the closest card if the card under a cursor has been removed.
+    fn snap_cursors_to_cards(&mut self) {
+        for player_id in 0..self.game.players.len() {
+            let cursor = self.ui.cursor(player_id);
+            if !self.game.coord_has_card(&cursor) {
+                if let Some(nearest) = self.game.field.nearest_card_slot(&cursor) {
+                    self.ui.set_cursor(player_id, nearest);
+                }
             }
-            let c = c.unwrap();
-            let card_opened = self.game.open_card(&c); // state is SecondCard if success
-            if card_opened {
-                println!("Card Opened at ({}, {})", c.0, c.1);
-                println!("Player {}, check the result", p.name);
+        }
+    }
+
+    fn play_sound(&mut self, event : SoundEvent) {
+        if let Some(sound) = self.sound.as_mut() {
+            sound.play(event);
+        }
+    }
+
+    /// Announces the text with the given key in the selected language.
+    fn announce(&mut self, key : &str, args : &[(&str, &dyn Display)]) {
+        if self.announcer.is_enabled() {
+            let text = self.languages.current().format(key, args);
+            self.announcer.announce(&text);
+        }
+    }
+
+    /// Announces the selected menu item, or that the menu was closed.
+    fn announce_menu(&mut self) {
+        let texts = self.languages.current();
+        let item = match &self.ui.menu {
+            Some(menu) => {
+                let sound = texts.text(if self.ui.volumes.muted { "menu.off" } else { "menu.on" });
+                texts.format(menu.selected_item().text_key(), &[
+                    ("theme", &self.themes.current().name),
+                    ("language", &texts.name()),
+                    ("state", &sound),
+                ])
+            },
+            None => {
+                self.announce("announce.menu_closed", &[]);
                 return;
-            } else {
-                println!("No card opened.");
-                println!("Player {}, select your second card", p.name);
-                return ;
-            }
+            },
+        };
+        self.announce("announce.menu", &[("item", &item)]);
+    }
+
+    /// Announces the card under the cursor of the given player.
+    fn announce_cursor(&mut self, player_id : usize) {
+        let cursor = self.ui.cursor(player_id);
+        let card = match self.game.card_at(cursor.0, cursor.1) {
+            Some(card) if self.game.is_clicked(&cursor) => { card.title.clone() },
+            _ => { self.languages.current().text("announce.face_down").to_string() },
+        };
+        self.announce("announce.cursor", &[("row", &(cursor.0 + 1)), ("col", &(cursor.1 + 1)), ("card", &card)]);
+    }
+
+    /// Prints the text with the given key in the selected language to the console.
+    fn print_text(&self, key : &str, args : &[(&str, &dyn Display)]) {
+        println!("{}", self.languages.current().format(key, args));
+    }
+
+    fn handle_menu_input(&mut self, action : InputAction) {
+        let menu = match self.ui.menu.as_mut() {
+            Some(menu) => menu,
+            None => return,
+        };
+        match action {
+            InputAction::Move(dy, _) if dy < 0 => { menu.select_previous(); },
+            InputAction::Move(dy, _) if dy > 0 => { menu.select_next(); },
+            InputAction::Move(_, _) => {},
+            InputAction::Menu | InputAction::Cancel => { self.ui.menu = None; },
+            InputAction::Select => {
+                let item = menu.selected_item();
+                match item {
+                    MenuItem::Resume => { self.ui.menu = None; },
+                    MenuItem::NewGame => { self.ui.menu = None; self.reset(); },
+                    // the menu stays open, so the user can see the theme and switch on
+                    MenuItem::Theme => { self.themes.next(); },
+                    MenuItem::Language => { self.languages.next(); },
+                    MenuItem::Sound => { self.ui.volumes.muted = !self.ui.volumes.muted; },
+                    MenuItem::Quit => { self.ui.menu = None; self.running = false; },
+                }
+            },
         }
+    }
 
-        if state == GameState::SecondCard {
-            let p = self.game.current_player();
-            let found_pair = self.game.check_guess_current_player(); // state is now ViewResult
-            if found_pair {
-                println!("Player {}, you found a pair, you now have cards", p.name);
-                self.game.print_cards_of_current_player();
-            } else {
-                println!("Player {}, bad luck, no pair found", p.name);
-            }
-            println!("Player {}, click to pass on to next player.", p.name);
+    /// Handles an action from the keyboard or a game controller. The player is the one the
+    /// input device belongs to. Every player can move their cursor, so they can pick their next
+    /// card while waiting, but only the current player may flip cards. Cancel takes back the last
+    /// queued selection, or hides the cursor.
+    fn handle_input(&mut self, player_id : usize, action : InputAction) {
+        if self.ui.menu.is_some() {
+            self.handle_menu_input(action);
+            self.announce_menu();
             return;
         }
+        if action == InputAction::Menu {
+            self.ui.menu = Some(Menu::new());
+            self.announce_menu();
+            return;
+        }
+        let has_turn = player_id == self.game.current_player_id;
+        match action {
+            InputAction::Move(dy, dx) => {
+                if !has_turn && !self.ui.waiting.contains(&player_id) {
+                    self.ui.waiting.push(player_id);
+                }
+                self.move_cursor(player_id, dy, dx);
+            },
+            InputAction::Select if has_turn => {
+                self.ui.show_cursor = true;
+                self.select(Some(self.ui.cursor(player_id)));
+            },
+            InputAction::Select => {},
+            InputAction::Cancel if has_turn => {
+                if self.pending_selections.pop_back().is_none() {
+                    self.ui.show_cursor = false;
+                }
+            },
+            InputAction::Cancel => { self.ui.waiting.retain(|p| *p != player_id); },
+            InputAction::Menu => {},
+        }
+    }
 
-        // if state == GameState::ViewResult {
-        //     let p = self.game.current_player();
-        //     let game_over = self.game.check_game_over(); // result is now either GameOver or NextUser
-        //     if game_over {
-        //         println!("Player {}, Game is over. Press any key to start new game.", p.name);
-        //     } else {
-        //         self.game.close_selected_cards();
-        //         self.game.next_player();
-        //         let p = self.game.current_player();
-        //         println!("Player {}, your turn!", p.name);
-        //     }
-        //     return ;
-        // }
-
-        if state == GameState::NextUser {
-            let p = self.game.current_player();
-            let game_over = self.game.check_game_over(); // result is now either GameOver or NextUser
-            if game_over {
-                println!("Player {}, Game is over. Press any key to start new game.", p.name);
-            } else {
-                self.game.close_selected_cards();
-                self.game.next_player();
-                let p = self.game.current_player();
-                println!("Player {}, your turn!", p.name);
+    fn handle_pad_input(&mut self, instance_id : u32, input : PadInput) {
+        let num_players = self.game.players.len();
+        let gamepads = match self.gamepads.as_mut() {
+            Some(g) => g,
+            None => return,
+        };
+        match input {
+            PadInput::AssignNextPlayer => {
+                if let Some(p) = gamepads.assign_next_player(instance_id, num_players) {
+                    self.print_text("prompt.pad_assigned", &[("player", &self.game.players[p].name)]);
+                }
+            },
+            PadInput::Action(action) => {
+                match gamepads.player_of(instance_id) {
+                    Some(player_id) => { self.handle_input(player_id, action); },
+                    // a controller without player can still operate the menu
+                    None => {
+                        if self.ui.menu.is_some() || action == InputAction::Menu {
+                            self.handle_input(self.game.current_player_id, action);
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    /// Drives the game state machine forward. The given coordinate is the selected card slot
+    /// (from a mouse click or the keyboard cursor). It is flipped while cards are to be selected,
+    /// otherwise the selection confirms the result or starts a new game.
+    fn handle_selection(&mut self, c : Option<Coord>) {
+        match self.game.expected_action() {
+            ExpectedAction::NewGame => {
+                self.print_text("prompt.resetting", &[]);
+                self.reset();
+            },
+            ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard => {
+                match c {
+                    Some(c) => { self.apply(Action::Flip(c)); },
+                    None => { self.print_prompt(); },
+                }
+            },
+            ExpectedAction::CheckResult | ExpectedAction::PassTurn => { self.apply(Action::Confirm); },
+        }
+    }
+
+    /// Applies the action to the game and shows what happened. Refused actions are reported.
+    fn apply(&mut self, action : Action) {
+        match self.game.apply(action.clone()) {
+            Ok(events) => {
+                self.record(action);
+                for event in events {
+                    self.handle_event(event);
+                }
+            },
+            Err(GameError::NoCard(_)) | Err(GameError::AlreadyOpen(_)) | Err(GameError::OutOfBounds(_)) => {
+                self.print_text("prompt.no_card", &[]);
+            },
+            Err(e) => { println!("{}", e); },
+        }
+        self.print_prompt();
+    }
+
+    /// Adds the action to the recording, if the game is recorded.
+    fn record(&mut self, action : Action) {
+        if let Some((path, record)) = self.recording.as_mut() {
+            record.actions.push(action);
+            if let Err(e) = record.save(path) {
+                println!("{}", e);
             }
-        
-            //self.game.next_player();
-            //let p = self.game.current_player();
-            //println!("Player {}, click to start your selection", p.name);
-            return ;
         }
     }
 
-    pub fn run(&mut self) {
+    /// Tells the current player what to do next.
+    fn print_prompt(&self) {
+        let key = match self.game.expected_action() {
+            ExpectedAction::FlipFirstCard => { "prompt.select_first" },
+            ExpectedAction::FlipSecondCard => { "prompt.select_second" },
+            ExpectedAction::CheckResult => { "prompt.check_result" },
+            ExpectedAction::PassTurn => { "prompt.pass_on" },
+            ExpectedAction::NewGame => { "prompt.game_over" },
+        };
+        if let Some(p) = self.game.players.get(self.game.current_player_id) {
+            self.print_text(key, &[("player", &p.name)]);
+        }
+    }
+
+    /// Shows what happened in the game: starts animations, plays sounds and writes announcements
+    /// and console messages.
+    fn handle_event(&mut self, event : GameEvent) {
+        let p = self.game.current_player();
+        match event {
+            GameEvent::GameStarted => {},
+            GameEvent::CardFlipped { coord, card_id, .. } => {
+                self.ui.animations.start_flip(coord.clone());
+                self.play_sound(SoundEvent::Flip);
+                let title = self.game.deck[card_id].title.clone();
+                self.announce("announce.opened", &[("player", &p.name), ("row", &(coord.0 + 1)), ("col", &(coord.1 + 1)), ("title", &title)]);
+                self.print_text("prompt.card_opened", &[("row", &coord.0), ("col", &coord.1)]);
+            },
+            GameEvent::PairFound { player_id, first, second } => {
+                self.result_shown_at = Some(Instant::now());
+                let title = self.game.deck[first.1].title.clone();
+                self.ui.animations.start_slide(first.0, title.clone(), player_id);
+                self.ui.animations.start_slide(second.0, self.game.deck[second.1].title.clone(), player_id);
+                self.play_sound(SoundEvent::Match);
+                let cards = self.languages.current().plural("cards", self.game.players[player_id].collected_cards.len());
+                self.announce("announce.pair", &[("player", &p.name), ("title", &title), ("cards", &cards)]);
+                self.print_text("prompt.found_pair", &[("player", &p.name), ("cards", &cards)]);
+                self.game.print_cards_of_current_player();
+            },
+            GameEvent::NoPair { first, second, .. } => {
+                self.result_shown_at = Some(Instant::now());
+                self.ui.animations.start_shake(vec![first.0, second.0]);
+                self.play_sound(SoundEvent::Mismatch);
+                let title1 = self.game.deck[first.1].title.clone();
+                let title2 = self.game.deck[second.1].title.clone();
+                self.announce("announce.no_pair", &[("first", &title1), ("second", &title2)]);
+                self.print_text("prompt.no_pair", &[("player", &p.name)]);
+            },
+            GameEvent::CardsShuffled => {
+                let cards : Vec<Coord> = self.game.field.card_slots().collect();
+                self.ui.animations.start_shake(cards);
+                self.announce("announce.shuffled", &[]);
+                self.print_text("announce.shuffled", &[]);
+            },
+            GameEvent::TurnChanged { .. } => {
+                self.result_shown_at = None;
+                self.snap_cursors_to_cards();
+                // a player who has already picked a card while waiting gets their cursor shown
+                let current = self.game.current_player_id;
+                if self.ui.waiting.contains(&current) {
+                    self.ui.waiting.retain(|p| *p != current);
+                    self.ui.show_cursor = true;
+                }
+                self.play_sound(SoundEvent::TurnChange);
+                self.announce("announce.turn", &[("player", &p.name)]);
+                self.print_text("prompt.your_turn", &[("player", &p.name)]);
+            },
+            GameEvent::GameOver => {
+                self.result_shown_at = None;
+                self.play_sound(SoundEvent::GameOver);
+                let texts = self.languages.current();
+                let scores : Vec<String> = self.game.players.iter().zip(self.game.scores())
+                    .map(|(player, score)| texts.format("players.score", &[
+                        ("player", &player.name),
+                        ("points", &texts.plural_signed("points", score)),
+                    ]))
+                    .collect();
+                self.announce("announce.game_over", &[("scores", &scores.join(", "))]);
+            },
+        }
+    }
+
+    /// In auto turn mode the game moves on by itself: the result is checked as soon as the
+    /// second card is open and the next player gets the turn once the result has been shown
+    /// for the configured time. Nothing happens while an animation is running.
+    fn auto_advance(&mut self) {
+        let delay = match self.auto_turn_delay {
+            Some(d) => d,
+            None => return,
+        };
+        let now = Instant::now();
+        if self.ui.menu.is_some() || self.ui.animations.is_busy(now) {
+            return;
+        }
+        match self.game.expected_action() {
+            ExpectedAction::CheckResult => { self.apply(Action::Confirm); },
+            ExpectedAction::PassTurn => {
+                let shown_since = *self.result_shown_at.get_or_insert(now);
+                if now.duration_since(shown_since) >= delay {
+                    self.apply(Action::Confirm);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Runs the game until the window is closed. Fails if SDL can't be set up.
+    pub fn run(&mut self) -> Result<(), String> {
         let window_height : u32 = 1000;
         let window_width : u32 = 1600;
-        let screen_height: u32 = 800;
-        let screen_width : u32 = 600;
-        let status_bar = Rect::new(0, (window_height-40).try_into().unwrap(), window_width, 40);
     
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
+        // Game controllers are optional, the game is still playable with mouse and keyboard.
+        // Already connected controllers are reported as ControllerDeviceAdded events.
+        self.gamepads = match sdl_context.game_controller() {
+            Ok(subsystem) => Some(Gamepads::new(subsystem)),
+            Err(e) => {
+                println!("Game controller support not available: {}", e);
+                None
+            },
+        };
+        // Without an audio device the game is played silently.
+        let sound_pack = match &self.sound_pack_dir {
+            Some(dir) => SoundPack::load(dir)?,
+            None => SoundPack::synthesized(),
+        };
+        self.sound = Some(SoundPlayer::new(&sdl_context, sound_pack, self.ui.volumes));
         let window = video_subsystem.window("Play Memory!", window_width, window_height)
+            .resizable()
             .build()
             .unwrap();
     
@@ -162,37 +465,73 @@ impl Control {
             .build()
             .unwrap();
     
-        let board_view = board_view::Renderer {
-            window_height,
+        let mut board_view = board_view::Renderer::new(
             window_width,
-            statusbar_area : status_bar,
-            screen_area : Rect::new(200, 100, screen_width, screen_height),
-            clear_color : Color::RGB(64, 192, 255),
-        };
-    
-        let screen_left = board_view.screen_area.x;
-        let screen_top = board_view.screen_area.y;
+            window_height,
+            self.game.field.height,
+            self.game.field.width,
+            self.themes.current().clone(),
+            self.languages.current().clone(),
+            self.font_dirs.clone())?;
+        board_view.accessibility = self.accessibility.clone();
 
-        let mut running = true;
         let mut event_queue = sdl_context.event_pump().unwrap();
     
-        while running {
+        while self.running {
             for event in event_queue.poll_iter() {
                 match event {
-                    Event::Quit { timestamp: _ } => { running = false; },
+                    Event::Quit { timestamp: _ } => { self.running = false; },
                     // Event::MouseMotion { timestamp: _, window_id: _, which: _, mousestate: _, x, y, xrel, yrel } => {
                     //     println!("Mouse x: {}, y: {} \t x-rel: {}, y-rel: {}", x, y, xrel, yrel);
                     // },
                     Event::MouseButtonDown { timestamp: _, window_id, which: _, mouse_btn, clicks: _, x, y } => {
                         if window_id == game_window_id && mouse_btn == MouseButton::Left {
-                            self.handle_mouse_click(y, x, screen_left, screen_top, screen_height, screen_width);
+                            self.handle_mouse_click(y, x, &board_view.layout);
+                        }
+                    }
+                    Event::Window { window_id, win_event: WindowEvent::SizeChanged(w, h), .. } if window_id == game_window_id => {
+                        board_view.resize(w.max(1) as u32, h.max(1) as u32);
+                    }
+                    Event::KeyDown { window_id, keycode: Some(keycode), .. } if window_id == game_window_id => {
+                        if let Some(action) = action_from_key(keycode) {
+                            self.handle_input(self.game.current_player_id, action);
+                        }
+                    }
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        let num_players = self.game.players.len();
+                        if let Some(gamepads) = self.gamepads.as_mut() {
+                            gamepads.open(which, num_players);
+                        }
+                    }
+                    Event::ControllerDeviceRemoved { which, .. } => {
+                        if let Some(gamepads) = self.gamepads.as_mut() {
+                            gamepads.close(which);
+                        }
+                    }
+                    Event::ControllerButtonDown { which, button, .. } => {
+                        if let Some(input) = Gamepads::input_from_button(button) {
+                            self.handle_pad_input(which, input);
+                        }
+                    }
+                    Event::ControllerAxisMotion { which, axis, value, .. } => {
+                        let input = self.gamepads.as_mut().and_then(|g| g.input_from_axis(which, axis, value));
+                        if let Some(input) = input {
+                            self.handle_pad_input(which, input);
                         }
                     }
                     _ => {}
                 }
             }
-            board_view.render(&mut canvas, &self.game);
+            self.handle_pending_selections();
+            self.auto_advance();
+            board_view.set_theme(self.themes.current())?;
+            board_view.set_language(self.languages.current());
+            if let Some(sound) = self.sound.as_mut() {
+                sound.set_volumes(self.ui.volumes);
+            }
+            board_view.render(&mut canvas, &self.game, &self.ui);
             canvas.present();
         }    
+        Ok(())
     }
-}
\ No newline at end of file
+}
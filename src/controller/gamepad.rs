@@ -0,0 +1,162 @@
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::GameControllerSubsystem;
+
+use crate::controller::input::InputAction;
+
+/// The analog stick has to be pushed beyond this value to count as a move. It must return below
+/// it before the next move is triggered, so holding the stick moves the cursor only once.
+const STICK_THRESHOLD : i16 = 16000;
+
+/// What a game controller asks for.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PadInput {
+    Action(InputAction),
+    /// The pad should be handed over to the next player (Back button).
+    AssignNextPlayer,
+}
+
+/// An opened game controller and the player it belongs to.
+pub struct Gamepad {
+    pub controller : GameController,
+    pub player : Option<usize>,
+    stick : (i32, i32),
+}
+
+/// Keeps track of all connected game controllers and which player uses which controller.
+/// Controllers are opened when SDL reports them (this also happens for controllers that are
+/// already plugged in at startup) and closed when they are unplugged.
+pub struct Gamepads {
+    subsystem : GameControllerSubsystem,
+    pub pads : Vec<Gamepad>,
+}
+
+impl Gamepads {
+    pub fn new(subsystem : GameControllerSubsystem) -> Self {
+        Gamepads {
+            subsystem,
+            pads : Vec::new(),
+        }
+    }
+
+    /// Opens the controller with the given joystick index and assigns it to the first player
+    /// that doesn't have a controller yet. Returns the instance id of the controller.
+    pub fn open(&mut self, joystick_index : u32, num_players : usize) -> Option<u32> {
+        if !self.subsystem.is_game_controller(joystick_index) {
+            return None;
+        }
+        let controller = match self.subsystem.open(joystick_index) {
+            Ok(c) => c,
+            Err(e) => {
+                println!("Could not open game controller {}: {}", joystick_index, e);
+                return None;
+            },
+        };
+        let instance_id = controller.instance_id();
+        if self.find(instance_id).is_some() {
+            // already known, SDL sometimes reports controllers twice
+            return Some(instance_id);
+        }
+        let player = (0..num_players).find(|p| self.pad_of_player(*p).is_none());
+        println!("Game controller \"{}\" connected, player: {:?}", controller.name(), player);
+        self.pads.push(Gamepad {
+            controller,
+            player,
+            stick : (0, 0),
+        });
+        Some(instance_id)
+    }
+
+    /// Forgets the controller with the given instance id. The player keeps playing with
+    /// keyboard and mouse or can get another controller.
+    pub fn close(&mut self, instance_id : u32) {
+        if let Some(idx) = self.find(instance_id) {
+            let pad = self.pads.remove(idx);
+            println!("Game controller \"{}\" disconnected", pad.controller.name());
+        }
+    }
+
+    fn find(&self, instance_id : u32) -> Option<usize> {
+        self.pads.iter().position(|p| p.controller.instance_id() == instance_id)
+    }
+
+    /// Returns the player the controller is assigned to.
+    pub fn player_of(&self, instance_id : u32) -> Option<usize> {
+        self.find(instance_id).and_then(|idx| self.pads[idx].player)
+    }
+
+    /// Returns the instance id of the controller assigned to the given player.
+    pub fn pad_of_player(&self, player : usize) -> Option<u32> {
+        self.pads.iter()
+            .find(|p| p.player == Some(player))
+            .map(|p| p.controller.instance_id())
+    }
+
+    /// Assigns the controller to the given player. A controller the player had before is
+    /// unassigned, so each player has at most one controller.
+    pub fn assign(&mut self, instance_id : u32, player : usize) {
+        for pad in self.pads.iter_mut() {
+            if pad.player == Some(player) {
+                pad.player = None;
+            }
+        }
+        if let Some(idx) = self.find(instance_id) {
+            self.pads[idx].player = Some(player);
+        }
+    }
+
+    /// Hands the controller over to the next player. Returns the new player.
+    pub fn assign_next_player(&mut self, instance_id : u32, num_players : usize) -> Option<usize> {
+        if num_players == 0 {
+            return None;
+        }
+        let next = match self.player_of(instance_id) {
+            Some(p) => (p + 1) % num_players,
+            None => 0,
+        };
+        self.assign(instance_id, next);
+        Some(next)
+    }
+
+    /// Translates a button press into the input it stands for.
+    pub fn input_from_button(button : Button) -> Option<PadInput> {
+        match button {
+            Button::DPadUp => { Some(PadInput::Action(InputAction::Move(-1, 0))) },
+            Button::DPadDown => { Some(PadInput::Action(InputAction::Move(1, 0))) },
+            Button::DPadLeft => { Some(PadInput::Action(InputAction::Move(0, -1))) },
+            Button::DPadRight => { Some(PadInput::Action(InputAction::Move(0, 1))) },
+            Button::A => { Some(PadInput::Action(InputAction::Select)) },
+            Button::B => { Some(PadInput::Action(InputAction::Cancel)) },
+            Button::Start => { Some(PadInput::Action(InputAction::Menu)) },
+            Button::Back => { Some(PadInput::AssignNextPlayer) },
+            _ => { None },
+        }
+    }
+
+    /// Translates a move of the left stick into a cursor move. A move is only reported when the
+    /// stick leaves the neutral zone (or changes direction).
+    pub fn input_from_axis(&mut self, instance_id : u32, axis : Axis, value : i16) -> Option<PadInput> {
+        let idx = self.find(instance_id)?;
+        let pad = &mut self.pads[idx];
+        let dir = if value > STICK_THRESHOLD {
+            1
+        } else if value < -STICK_THRESHOLD {
+            -1
+        } else {
+            0
+        };
+        let (dy, dx) = match axis {
+            Axis::LeftY => { (dir, 0) },
+            Axis::LeftX => { (0, dir) },
+            _ => { return None; },
+        };
+        let previous = pad.stick;
+        match axis {
+            Axis::LeftY => { pad.stick.0 = dy; },
+            _ => { pad.stick.1 = dx; },
+        }
+        if dir == 0 || pad.stick == previous {
+            return None;
+        }
+        Some(PadInput::Action(InputAction::Move(dy, dx)))
+    }
+}
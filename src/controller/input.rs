@@ -0,0 +1,28 @@
+use sdl2::keyboard::Keycode;
+
+/// The actions a user can trigger, independent of the input device (keyboard or game
+/// controller) they came from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputAction {
+    /// Move the cursor (or the menu selection) by (dy, dx)
+    Move(i32, i32),
+    /// Flip the card under the cursor, move on to the next turn or activate the menu item
+    Select,
+    /// Close the menu
+    Cancel,
+    /// Open or close the menu
+    Menu,
+}
+
+/// Translates a key press into an InputAction. Returns None for keys without a meaning.
+pub fn action_from_key(keycode : Keycode) -> Option<InputAction> {
+    match keycode {
+        Keycode::Up | Keycode::W => { Some(InputAction::Move(-1, 0)) },
+        Keycode::Down | Keycode::S => { Some(InputAction::Move(1, 0)) },
+        Keycode::Left | Keycode::A => { Some(InputAction::Move(0, -1)) },
+        Keycode::Right | Keycode::D => { Some(InputAction::Move(0, 1)) },
+        Keycode::Return | Keycode::KpEnter | Keycode::Space => { Some(InputAction::Select) },
+        Keycode::Escape => { Some(InputAction::Menu) },
+        _ => { None },
+    }
+}
@@ -1,2 +1,5 @@
-
-pub mod controller;
\ No newline at end of file
+pub mod controller;
+pub mod gamepad;
+pub mod input;
+pub mod net_controller;
+pub mod replay_controller;
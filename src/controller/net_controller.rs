@@ -0,0 +1,447 @@
+use crate::model::game_model::{CellView, Coord, ExpectedAction, GameState, PublicView};
+use crate::model::scoring::Rules;
+use crate::net::client::{self, Connection, MessageSender};
+use crate::net::protocol::{ClientMessage, RoomInfo, RoomSettings, ServerMessage, DEFAULT_PORT};
+use crate::view::board_view;
+use crate::view::accessibility::Accessibility;
+use crate::view::layout::Layout;
+use crate::view::theme::ThemeSet;
+use crate::view::ui_state::UiState;
+use crate::controller::input::{action_from_key, InputAction};
+use crate::i18n::{self, Languages};
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sdl2::mouse::MouseButton;
+use sdl2::event::{Event, WindowEvent};
+
+/// How long to wait for the server to let us into the room
+const ENTER_TIMEOUT : Duration = Duration::from_secs(10);
+
+/// The new connection and the seats, or why they couldn't be taken back
+type Reconnected = Result<(Connection, Vec<usize>), String>;
+
+/// What a selection (click, Enter) at this computer asks the server to do. While no game runs
+/// the host starts one and everybody else says whether they are ready. During the game only
+/// the players at this computer can play their turns, otherwise None is returned.
+pub fn message_for_selection(view : &PublicView, running : bool, local : &[usize], is_host : bool, ready : bool, c : Option<Coord>) -> Option<ClientMessage> {
+    if !running {
+        return Some(if is_host { ClientMessage::Start } else { ClientMessage::Ready { ready : !ready } });
+    }
+    if !local.contains(&view.current_player_id) {
+        return None;
+    }
+    match view.expected_action {
+        ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard => { c.map(|Coord(row, col)| ClientMessage::Flip { row, col }) },
+        ExpectedAction::CheckResult | ExpectedAction::PassTurn => { Some(ClientMessage::Confirm) },
+        ExpectedAction::NewGame => { None },
+    }
+}
+
+/// Plays a game of a room on a server for the players at this computer. The game itself runs on
+/// the server; the window shows the states it sends and turns the input of the local players
+/// into messages.
+pub struct NetControl {
+    pub ui : UiState,
+    pub themes : ThemeSet,
+    pub languages : Languages,
+    /// additional directories to search for fonts
+    pub font_dirs : Vec<PathBuf>,
+    pub accessibility : Accessibility,
+    pub running : bool,
+    /// the address of the server, to connect to again if the connection drops
+    server : String,
+    /// the token from the welcome to take the seats back with
+    token : Option<String>,
+    sender : MessageSender,
+    messages : Receiver<Result<ServerMessage, String>>,
+    /// set while the seats are being taken back on a new connection
+    reconnecting : Option<Receiver<Reconnected>>,
+    /// the players at this computer, as indices into view.players
+    seats : Vec<usize>,
+    room : RoomInfo,
+    view : PublicView,
+    ready : Vec<bool>,
+}
+
+impl NetControl {
+    /// Enters a room for the given players. If settings are given the room is created, otherwise
+    /// the players join the room with that name or code. Returns once the server has sent the
+    /// first state of the room.
+    pub fn connect(server : &str, room : String, players : Vec<String>, settings : Option<RoomSettings>) -> Result<NetControl, String> {
+        let mut connection = Connection::connect(server)?;
+        connection.set_timeout(Some(ENTER_TIMEOUT))?;
+        connection.send(&match settings {
+            Some(settings) => { ClientMessage::CreateRoom { players, room, settings } },
+            None => { ClientMessage::Join { players, room } },
+        })?;
+        loop {
+            match connection.receive()? {
+                ServerMessage::Welcome { room, code, seats, token } => {
+                    println!("Entered room {} (code {})", room, code);
+                    if let Some(token) = &token {
+                        println!("If the connection drops for good, come back with --resume={}", token);
+                    }
+                    return NetControl::enter(server, connection, seats, token);
+                },
+                ServerMessage::Error { message } => { return Err(message); },
+                ServerMessage::State { .. } | ServerMessage::Rooms { .. } => {},
+            }
+        }
+    }
+
+    /// Takes the seats of the players back with the token from an earlier welcome, e.g. after
+    /// the program was closed during a game.
+    pub fn resume(server : &str, token : String) -> Result<NetControl, String> {
+        let (connection, seats) = client::resume(server, &token, ENTER_TIMEOUT)?;
+        NetControl::enter(server, connection, seats, Some(token))
+    }
+
+    /// Waits for the first state of the room after the welcome.
+    fn enter(server : &str, mut connection : Connection, seats : Vec<usize>, token : Option<String>) -> Result<NetControl, String> {
+        loop {
+            match connection.receive()? {
+                ServerMessage::State { room, view, ready, .. } => {
+                    let (sender, messages) = connection.split();
+                    return Ok(NetControl {
+                        ui : UiState::new(),
+                        themes : ThemeSet::load(None),
+                        languages : Languages::load(None),
+                        font_dirs : Vec::new(),
+                        accessibility : Accessibility::default(),
+                        running : true,
+                        server : server.to_string(),
+                        token,
+                        sender,
+                        messages,
+                        reconnecting : None,
+                        seats,
+                        room,
+                        view,
+                        ready,
+                    });
+                },
+                ServerMessage::Error { message } => { return Err(message); },
+                ServerMessage::Welcome { .. } | ServerMessage::Rooms { .. } => {},
+            }
+        }
+    }
+
+    /// Whether a game is being played, otherwise the players get ready for the next one
+    fn is_running(&self) -> bool {
+        self.room.started && self.view.expected_action != ExpectedAction::NewGame
+    }
+
+    fn is_local_turn(&self) -> bool {
+        self.is_running() && self.seats.contains(&self.view.current_player_id)
+    }
+
+    /// The host is the client that created the room or got it passed on; it goes by the name of
+    /// its first player.
+    fn is_host(&self) -> bool {
+        let first = self.seats.first().and_then(|s| self.view.players.get(*s));
+        first.is_some_and(|p| self.room.host.as_ref() == Some(&p.name))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.seats.first().and_then(|s| self.ready.get(*s)) == Some(&true)
+    }
+
+    /// Sends the message to the server. Input while reconnecting is dropped.
+    fn send(&mut self, message : &ClientMessage) {
+        if self.reconnecting.is_some() {
+            return;
+        }
+        if let Err(e) = self.sender.send(message).or_else(|e| self.reconnect(e)) {
+            println!("{}", e);
+            self.running = false;
+        }
+    }
+
+    /// Starts to take the seats back on a new connection after the old one dropped, on a thread
+    /// so the window is still drawn meanwhile. The room keeps the seats for reconnect_seconds,
+    /// so it's tried that long. Fails with the error if there is no token.
+    fn reconnect(&mut self, error : String) -> Result<(), String> {
+        let Some(token) = self.token.clone() else { return Err(error) };
+        println!("{}, reconnecting", error);
+        let server = self.server.clone();
+        let patience = Duration::from_secs(self.room.settings.reconnect_seconds);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(client::resume(&server, &token, patience));
+        });
+        self.reconnecting = Some(rx);
+        Ok(())
+    }
+
+    /// Takes the messages that arrived from the server. Refused messages are reported on the
+    /// console. If the connection is lost, the seats are taken back on a new one; fails if that
+    /// doesn't work out.
+    fn receive(&mut self) -> Result<(), String> {
+        if let Some(reconnecting) = &self.reconnecting {
+            match reconnecting.try_recv() {
+                Ok(Ok((connection, seats))) => {
+                    println!("Reconnected");
+                    (self.sender, self.messages) = connection.split();
+                    self.seats = seats;
+                    self.reconnecting = None;
+                },
+                Ok(Err(e)) => { return Err(e); },
+                Err(TryRecvError::Empty) => { return Ok(()); },
+                Err(TryRecvError::Disconnected) => { return Err("Reconnecting failed".to_string()); },
+            }
+        }
+        loop {
+            match self.messages.try_recv() {
+                Ok(Ok(ServerMessage::State { room, view, ready, .. })) => {
+                    self.show_changes(&view);
+                    self.room = room;
+                    self.view = view;
+                    self.ready = ready;
+                },
+                Ok(Ok(ServerMessage::Error { message })) => { println!("{}", message); },
+                Ok(Ok(_)) => {},
+                Ok(Err(e)) => { return self.reconnect(e); },
+                Err(TryRecvError::Empty) => { return Ok(()); },
+                Err(TryRecvError::Disconnected) => { return self.reconnect("Connection lost".to_string()); },
+            }
+        }
+    }
+
+    /// Starts the animations for the difference between the shown view and the new one: cards
+    /// that were turned over flip, the cards of a found pair slide to the player and cards that
+    /// don't match shake.
+    fn show_changes(&mut self, new : &PublicView) {
+        let old = &self.view;
+        if (old.height, old.width) != (new.height, new.width) {
+            return;
+        }
+        let mut open = Vec::new();
+        for row in 0..new.height {
+            for col in 0..new.width {
+                let coord = Coord(row, col);
+                match (old.cell(&coord), new.cell(&coord)) {
+                    (Some(CellView::FaceDown), Some(CellView::FaceUp { .. })) => { self.ui.animations.start_flip(coord.clone()); },
+                    (Some(CellView::FaceUp { title, .. }), Some(CellView::Removed)) => {
+                        self.ui.animations.start_slide(coord.clone(), title.clone(), old.current_player_id);
+                    },
+                    _ => {},
+                }
+                if let Some(CellView::FaceUp { .. }) = new.cell(&coord) {
+                    open.push(coord);
+                }
+            }
+        }
+        if new.state == GameState::NextUser && old.state != GameState::NextUser && !new.last_guess_success {
+            self.ui.animations.start_shake(open);
+        }
+        for seat in self.seats.clone() {
+            if let Some(nearest) = new.nearest_card_cell(&self.ui.cursor(seat)) {
+                self.ui.set_cursor(seat, nearest);
+            }
+        }
+    }
+
+    fn select(&mut self, c : Option<Coord>) {
+        let message = message_for_selection(&self.view, self.is_running(), &self.seats, self.is_host(), self.is_ready(), c);
+        if let Some(message) = message {
+            self.send(&message);
+        }
+    }
+
+    /// Handles a mouse click. Cards can only be picked on the turns of the local players.
+    fn handle_mouse_click(&mut self, y : i32, x : i32, layout : &Layout) {
+        let c = layout.coord_at(y, x);
+        if let (Some(coord), true) = (&c, self.is_local_turn()) {
+            self.ui.set_cursor(self.view.current_player_id, coord.clone());
+        }
+        self.ui.show_cursor = false;
+        self.select(c);
+    }
+
+    /// Handles a key. The cursor belongs to the player whose turn it is, Escape leaves the room.
+    fn handle_input(&mut self, action : InputAction) {
+        let player_id = self.view.current_player_id;
+        match action {
+            InputAction::Move(dy, dx) if self.is_local_turn() => {
+                self.ui.show_cursor = true;
+                if let Some(next) = self.view.next_card_cell(&self.ui.cursor(player_id), dy, dx) {
+                    self.ui.set_cursor(player_id, next);
+                }
+            },
+            InputAction::Move(_, _) => {},
+            InputAction::Select => {
+                self.ui.show_cursor = true;
+                self.select(Some(self.ui.cursor(player_id)));
+            },
+            InputAction::Menu | InputAction::Cancel => {
+                self.send(&ClientMessage::Leave);
+                self.running = false;
+            },
+        }
+    }
+
+    /// What the players at this computer are asked to do while no game runs
+    fn lobby_prompt(&self) -> String {
+        let texts = self.languages.current();
+        if self.is_host() {
+            texts.text("net.start").to_string()
+        } else if self.is_ready() {
+            texts.format("net.ready", &[("host", &self.room.host.clone().unwrap_or_default())])
+        } else {
+            texts.text("net.get_ready").to_string()
+        }
+    }
+
+    /// Runs the game until the window is closed or the room is left. Fails if SDL can't be set
+    /// up or the connection is lost.
+    pub fn run(&mut self) -> Result<(), String> {
+        let window_height : u32 = 1000;
+        let window_width : u32 = 1600;
+
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let title = format!("Play Memory! - {} ({})", self.room.name, self.room.code);
+        let window = video_subsystem.window(&title, window_width, window_height)
+            .resizable()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let game_window_id = window.id();
+        let mut canvas = window.into_canvas()
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut board_view = board_view::Renderer::new(
+            window_width,
+            window_height,
+            self.view.height,
+            self.view.width,
+            self.themes.current().clone(),
+            self.languages.current().clone(),
+            self.font_dirs.clone())?;
+        board_view.accessibility = self.accessibility.clone();
+
+        let mut event_queue = sdl_context.event_pump()?;
+
+        while self.running {
+            for event in event_queue.poll_iter() {
+                match event {
+                    Event::Quit { .. } => {
+                        self.send(&ClientMessage::Leave);
+                        self.running = false;
+                    },
+                    Event::MouseButtonDown { window_id, mouse_btn : MouseButton::Left, x, y, .. } if window_id == game_window_id => {
+                        self.handle_mouse_click(y, x, &board_view.layout);
+                    }
+                    Event::Window { window_id, win_event: WindowEvent::SizeChanged(w, h), .. } if window_id == game_window_id => {
+                        board_view.resize(w.max(1) as u32, h.max(1) as u32);
+                    }
+                    Event::KeyDown { window_id, keycode: Some(keycode), .. } if window_id == game_window_id => {
+                        if let Some(action) = action_from_key(keycode) {
+                            self.handle_input(action);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            self.receive()?;
+            self.ui.animations.remove_finished(Instant::now());
+            board_view.set_theme(self.themes.current())?;
+            board_view.set_language(self.languages.current());
+            board_view.render_view(&mut canvas, &self.view, &self.ui, Some(&self.seats));
+            if self.reconnecting.is_some() {
+                board_view.render_message(&mut canvas, self.languages.current().text("net.reconnecting"));
+            } else if !self.is_running() {
+                board_view.render_message(&mut canvas, &self.lobby_prompt());
+            }
+            canvas.present();
+        }
+        Ok(())
+    }
+}
+
+/// Runs the "join" command, which plays in a room of a server for one or more players at this
+/// computer:
+///
+/// memory_game join room [--server=host:port] [--create] [--rows=N] [--cols=N] [--max-players=N] [--rules=name]
+///                  [--jokers=N] [--shuffle-cards=N] name...
+/// memory_game join --resume token [--server=host:port]
+///
+/// With --create the room is opened with the given size, rules and special cards, otherwise an existing room is joined by
+/// its name or code. With --resume the players take their seats back with the token printed when they entered the room.
+pub fn run(args : Vec<String>) -> Result<(), String> {
+    let mut server = format!("localhost:{}", DEFAULT_PORT);
+    let mut create = false;
+    let mut settings = RoomSettings::default();
+    let mut room = None;
+    let mut players = Vec::new();
+    let mut token = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let number = |value : &str| value.parse::<usize>().map_err(|_| format!("Option {} needs a number", arg));
+        match arg.split_once('=') {
+            Some(("--server", value)) => { server = value.to_string(); },
+            Some(("--rows", value)) => { settings.rows = number(value)?; },
+            Some(("--cols", value)) => { settings.cols = number(value)?; },
+            Some(("--max-players", value)) => { settings.max_players = number(value)?; },
+            Some(("--rules", value)) => { settings.rules = Rules::from_name(value)?; },
+            Some(("--jokers", value)) => { settings.specials.jokers = number(value)?; },
+            Some(("--shuffle-cards", value)) => { settings.specials.shuffles = number(value)?; },
+            Some(("--resume", value)) => { token = Some(value.to_string()); },
+            None if arg == "--resume" => { token = Some(args.next().ok_or("Option --resume needs the token")?); },
+            None if arg == "--create" => { create = true; },
+            None if !arg.starts_with("--") && room.is_none() => { room = Some(arg); },
+            None if !arg.starts_with("--") => { players.push(arg); },
+            _ => { return Err(format!("Unknown option {}", arg)); },
+        }
+    }
+    let mut control = match token {
+        Some(token) => { NetControl::resume(&server, token)? },
+        None => {
+            let room = room.ok_or("Which room? Give its name or code, \"memory_game rooms\" lists them")?;
+            if players.is_empty() {
+                return Err("Give the names of the players at this computer".to_string());
+            }
+            NetControl::connect(&server, room, players, Some(settings).filter(|_| create))?
+        },
+    };
+    if let Some(code) = i18n::language_from_env() {
+        control.languages.select(&code);
+    }
+    control.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::message_for_selection;
+    use crate::model::game_model::{Action, Coord, Game};
+    use crate::net::protocol::ClientMessage;
+
+    #[test]
+    fn test_selection_only_on_local_turns() {
+        let mut game = Game::new(2, 2);
+        for name in ["Anna", "Ben", "Carl"] {
+            game.add_player(name.to_string());
+        }
+        game.apply(Action::Reset).unwrap();
+        let view = game.public_view();
+        let local = [0, 1];
+        // before the game the host starts it and the others get ready
+        assert_eq!(message_for_selection(&view, false, &local, true, false, None), Some(ClientMessage::Start));
+        assert_eq!(message_for_selection(&view, false, &local, false, false, None), Some(ClientMessage::Ready { ready : true }));
+        assert_eq!(message_for_selection(&view, false, &local, false, true, None), Some(ClientMessage::Ready { ready : false }));
+
+        assert_eq!(message_for_selection(&view, true, &local, true, false, Some(Coord(1, 0))), Some(ClientMessage::Flip { row : 1, col : 0 }));
+        assert_eq!(message_for_selection(&view, true, &local, true, false, None), None);
+        // Carl plays at another computer
+        assert_eq!(message_for_selection(&view, true, &[2], false, false, Some(Coord(1, 0))), None);
+
+        game.apply(Action::Flip(Coord(0, 0))).unwrap();
+        game.apply(Action::Flip(Coord(0, 1))).unwrap();
+        let view = game.public_view();
+        assert_eq!(message_for_selection(&view, true, &local, true, false, None), Some(ClientMessage::Confirm));
+    }
+}
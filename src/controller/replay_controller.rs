@@ -0,0 +1,452 @@
+use crate::model::game_model::{Game, GameEvent};
+use crate::model::record::{GameRecord, Turn};
+use crate::view::board_view;
+use crate::view::accessibility::Accessibility;
+use crate::view::theme::ThemeSet;
+use crate::view::ui_state::UiState;
+use crate::i18n::{self, Catalog, Languages};
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+
+/// How many actions are played per second at speed x1
+const STEPS_PER_SECOND : f32 = 2.0;
+/// The speeds to choose from with + and -
+const SPEEDS : [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED : usize = 1;
+
+/// The things the keys do in the replay
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayInput {
+    PlayPause,
+    StepForward,
+    StepBack,
+    NextTurn,
+    PreviousTurn,
+    First,
+    Last,
+    Faster,
+    Slower,
+    /// a digit of the number of the turn to go to
+    Digit(u32),
+    /// go to the turn typed so far
+    GoToTurn,
+    Quit,
+}
+
+/// Translates a key press into a ReplayInput. Returns None for keys without a meaning.
+pub fn replay_input_from_key(keycode : Keycode) -> Option<ReplayInput> {
+    match keycode {
+        Keycode::Space => { Some(ReplayInput::PlayPause) },
+        Keycode::Right => { Some(ReplayInput::StepForward) },
+        Keycode::Left => { Some(ReplayInput::StepBack) },
+        Keycode::Down | Keycode::PageDown => { Some(ReplayInput::NextTurn) },
+        Keycode::Up | Keycode::PageUp => { Some(ReplayInput::PreviousTurn) },
+        Keycode::Home => { Some(ReplayInput::First) },
+        Keycode::End => { Some(ReplayInput::Last) },
+        Keycode::Plus | Keycode::KpPlus | Keycode::Equals => { Some(ReplayInput::Faster) },
+        Keycode::Minus | Keycode::KpMinus => { Some(ReplayInput::Slower) },
+        Keycode::Return | Keycode::KpEnter => { Some(ReplayInput::GoToTurn) },
+        Keycode::Escape => { Some(ReplayInput::Quit) },
+        _ => {
+            let digits = [Keycode::Num0, Keycode::Num1, Keycode::Num2, Keycode::Num3, Keycode::Num4,
+                          Keycode::Num5, Keycode::Num6, Keycode::Num7, Keycode::Num8, Keycode::Num9];
+            let keypad = [Keycode::Kp0, Keycode::Kp1, Keycode::Kp2, Keycode::Kp3, Keycode::Kp4,
+                          Keycode::Kp5, Keycode::Kp6, Keycode::Kp7, Keycode::Kp8, Keycode::Kp9];
+            digits.iter().position(|k| *k == keycode)
+                .or_else(|| keypad.iter().position(|k| *k == keycode))
+                .map(|d| ReplayInput::Digit(d as u32))
+        },
+    }
+}
+
+/// A recorded game and the point of it that is shown. The game is played forward from the
+/// current state; going back plays the record again from the start.
+pub struct Replay {
+    record : GameRecord,
+    pub turns : Vec<Turn>,
+    /// the number of actions of the record played so far
+    step : usize,
+    pub game : Game,
+}
+
+impl Replay {
+    /// Fails if the record can't be played.
+    pub fn new(record : GameRecord) -> Result<Replay, String> {
+        let turns = record.turns()?;
+        let game = record.replay(0)?;
+        Ok(Replay { record, turns, step : 0, game })
+    }
+
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.step == self.record.actions.len()
+    }
+
+    /// Shows the game after the given number of actions. Returns the events of the actions
+    /// played forward, none if the replay went back.
+    pub fn go_to(&mut self, step : usize) -> Vec<GameEvent> {
+        let step = step.min(self.record.actions.len());
+        if step < self.step {
+            self.game = self.record.replay(step).expect("the record was played when it was loaded");
+            self.step = step;
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        while self.step < step {
+            let action = self.record.actions[self.step].clone();
+            events.extend(self.game.apply(action).expect("the record was played when it was loaded"));
+            self.step += 1;
+        }
+        events
+    }
+
+    /// The index of the turn the last played action belongs to, None before the first turn
+    pub fn current_turn(&self) -> Option<usize> {
+        self.turns.iter().rposition(|t| t.start < self.step)
+    }
+
+    /// Goes to the start of the turn with the given index, or to the end after the last turn.
+    pub fn go_to_turn(&mut self, turn : usize) -> Vec<GameEvent> {
+        let step = self.turns.get(turn).map(|t| t.start).unwrap_or(self.record.actions.len());
+        self.go_to(step)
+    }
+
+    /// The index of the turn with the given number (from 1, like in the notation) in the given
+    /// game. A number after the last turn of the game gives the first turn of the next game.
+    pub fn turn_index(&self, game : usize, number : usize) -> usize {
+        self.turns.iter().position(|t| (t.game, t.number) >= (game, number)).unwrap_or(self.turns.len())
+    }
+
+    /// The index of the game the replay is in, 0 before the first turn
+    pub fn current_game(&self) -> usize {
+        self.current_turn().map_or(0, |t| self.turns[t].game)
+    }
+
+    pub fn games(&self) -> usize {
+        self.turns.last().map_or(1, |t| t.game + 1)
+    }
+}
+
+/// Shows a recorded game in the SDL window. It can be played at different speeds, stepped
+/// through action by action or turn by turn, and any turn can be jumped to.
+pub struct ReplayControl {
+    pub ui : UiState,
+    pub themes : ThemeSet,
+    pub languages : Languages,
+    /// additional directories to search for fonts
+    pub font_dirs : Vec<PathBuf>,
+    pub accessibility : Accessibility,
+    pub running : bool,
+    replay : Replay,
+    playing : bool,
+    /// index into SPEEDS
+    speed : usize,
+    last_step_at : Instant,
+    /// the digits of the turn number typed so far
+    typed : String,
+}
+
+impl ReplayControl {
+    pub fn new(record : GameRecord) -> Result<ReplayControl, String> {
+        Ok(ReplayControl {
+            ui : UiState::new(),
+            themes : ThemeSet::load(None),
+            languages : Languages::load(None),
+            font_dirs : Vec::new(),
+            accessibility : Accessibility::default(),
+            running : true,
+            replay : Replay::new(record)?,
+            playing : false,
+            speed : NORMAL_SPEED,
+            last_step_at : Instant::now(),
+            typed : String::new(),
+        })
+    }
+
+    /// Starts the animations for what happened, like in a game that is played.
+    fn show_events(&mut self, events : Vec<GameEvent>) {
+        for event in events {
+            match event {
+                GameEvent::CardFlipped { coord, .. } => { self.ui.animations.start_flip(coord); },
+                GameEvent::PairFound { player_id, first, second } => {
+                    for (coord, card_id) in [first, second] {
+                        let title = self.replay.game.deck[card_id].title.clone();
+                        self.ui.animations.start_slide(coord, title, player_id);
+                    }
+                },
+                GameEvent::NoPair { first, second, .. } => { self.ui.animations.start_shake(vec![first.0, second.0]); },
+                GameEvent::CardsShuffled => { self.ui.animations.start_shake(self.replay.game.field.card_slots().collect()); },
+                _ => {},
+            }
+        }
+    }
+
+    /// Jumps somewhere else in the game. Animations of the old position are dropped.
+    fn jump(&mut self, step : usize) {
+        self.ui.animations.clear();
+        self.replay.go_to(step);
+    }
+
+    /// Goes to the start of the turn with the given index.
+    fn jump_to_turn(&mut self, turn : usize) {
+        self.ui.animations.clear();
+        self.replay.go_to_turn(turn);
+    }
+
+    fn handle_input(&mut self, input : ReplayInput) {
+        if !matches!(input, ReplayInput::Digit(_) | ReplayInput::GoToTurn) {
+            self.typed.clear();
+        }
+        let step = self.replay.step();
+        match input {
+            ReplayInput::PlayPause => {
+                if self.replay.at_end() {
+                    self.jump(0);
+                }
+                self.playing = !self.playing;
+            },
+            ReplayInput::StepForward => {
+                self.playing = false;
+                self.ui.animations.clear();
+                let events = self.replay.go_to(step + 1);
+                self.show_events(events);
+            },
+            ReplayInput::StepBack => { self.playing = false; self.jump(step.saturating_sub(1)); },
+            ReplayInput::NextTurn => {
+                let next = self.replay.turns.iter().position(|t| t.start > step).unwrap_or(self.replay.turns.len());
+                self.jump_to_turn(next);
+            },
+            ReplayInput::PreviousTurn => {
+                let previous = self.replay.turns.iter().rposition(|t| t.start < step).unwrap_or(0);
+                self.jump_to_turn(previous);
+            },
+            ReplayInput::First => { self.jump(0); },
+            ReplayInput::Last => { self.jump(usize::MAX); },
+            ReplayInput::Faster => { self.speed = (self.speed + 1).min(SPEEDS.len() - 1); },
+            ReplayInput::Slower => { self.speed = self.speed.saturating_sub(1); },
+            ReplayInput::Digit(d) => { self.typed.push(char::from_digit(d, 10).unwrap_or('0')); },
+            ReplayInput::GoToTurn => {
+                // the number of a turn in the game shown, like in the move list
+                if let Ok(number) = self.typed.parse::<usize>() {
+                    self.playing = false;
+                    let index = self.replay.turn_index(self.replay.current_game(), number);
+                    self.jump_to_turn(index);
+                }
+                self.typed.clear();
+            },
+            ReplayInput::Quit => { self.running = false; },
+        }
+    }
+
+    /// Plays the next action when it is time for it and the animations of the last one are over.
+    fn advance(&mut self) {
+        let now = Instant::now();
+        self.ui.animations.remove_finished(now);
+        if !self.playing || self.ui.animations.is_busy(now) {
+            return;
+        }
+        let interval = Duration::from_secs_f32(1.0 / (STEPS_PER_SECOND * SPEEDS[self.speed]));
+        if now.duration_since(self.last_step_at) < interval {
+            return;
+        }
+        if self.replay.at_end() {
+            self.playing = false;
+            return;
+        }
+        self.last_step_at = now;
+        let events = self.replay.go_to(self.replay.step() + 1);
+        self.show_events(events);
+    }
+
+    /// The line above the move list: where the replay is, or the turn being typed
+    fn header(&self, texts : &Catalog) -> String {
+        if !self.typed.is_empty() {
+            return texts.format("replay.jump", &[("number", &self.typed)]);
+        }
+        let state = texts.text(if self.playing { "replay.playing" } else { "replay.paused" });
+        let game = self.replay.current_game();
+        let turns = self.replay.turns.iter().filter(|t| t.game == game).count();
+        let key = if self.replay.games() > 1 { "replay.game_status" } else { "replay.status" };
+        texts.format(key, &[
+            ("game", &(game + 1)),
+            ("games", &self.replay.games()),
+            ("turn", &self.replay.current_turn().map_or(0, |t| self.replay.turns[t].number)),
+            ("turns", &turns),
+            ("state", &state),
+            ("speed", &SPEEDS[self.speed]),
+        ])
+    }
+
+    /// Runs the replay until the window is closed. Fails if SDL can't be set up.
+    pub fn run(&mut self) -> Result<(), String> {
+        let window_height : u32 = 1000;
+        let window_width : u32 = 1600;
+
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let window = video_subsystem.window("Play Memory! - Replay", window_width, window_height)
+            .resizable()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let game_window_id = window.id();
+        let mut canvas = window.into_canvas()
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut board_view = board_view::Renderer::new(
+            window_width,
+            window_height,
+            self.replay.game.field.height,
+            self.replay.game.field.width,
+            self.themes.current().clone(),
+            self.languages.current().clone(),
+            self.font_dirs.clone())?;
+        board_view.accessibility = self.accessibility.clone();
+        let moves = move_list(&self.replay.turns, self.languages.current());
+
+        let mut event_queue = sdl_context.event_pump()?;
+
+        while self.running {
+            for event in event_queue.poll_iter() {
+                match event {
+                    Event::Quit { .. } => { self.running = false; },
+                    Event::Window { window_id, win_event: WindowEvent::SizeChanged(w, h), .. } if window_id == game_window_id => {
+                        board_view.resize(w.max(1) as u32, h.max(1) as u32);
+                    }
+                    Event::KeyDown { window_id, keycode: Some(keycode), .. } if window_id == game_window_id => {
+                        if let Some(input) = replay_input_from_key(keycode) {
+                            self.handle_input(input);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            self.advance();
+            board_view.set_theme(self.themes.current())?;
+            board_view.set_language(self.languages.current());
+            board_view.render(&mut canvas, &self.replay.game, &self.ui);
+            let header = self.header(self.languages.current());
+            board_view.render_move_list(&mut canvas, self.replay.game.players.len(), &header, &moves, self.replay.current_turn());
+            canvas.present();
+        }
+        Ok(())
+    }
+}
+
+/// The lines of the move list, one per turn, e.g. "3. Anna: 7 + 2 - no pair". The turns are
+/// numbered per game like in the notation, so records of several games tell the game as well.
+pub fn move_list(turns : &[Turn], texts : &Catalog) -> Vec<String> {
+    let several_games = turns.last().is_some_and(|t| t.game > 0);
+    let key = if several_games { "replay.game_turn" } else { "replay.turn" };
+    turns.iter().map(|turn| {
+        let cards : Vec<&str> = turn.flips.iter().map(|(_, title)| title.as_str()).collect();
+        let result = texts.text(match turn.pair {
+            Some(true) => { "replay.pair" },
+            Some(false) => { "replay.no_pair" },
+            None => { "replay.skipped" },
+        });
+        texts.format(key, &[
+            ("game", &(turn.game + 1)),
+            ("number", &turn.number),
+            ("player", &turn.player),
+            ("cards", &cards.join(" + ")),
+            ("result", &result),
+        ])
+    }).collect()
+}
+
+/// Runs the "replay" command, which shows a game recorded with --record:
+///
+/// memory_game replay file [--lang=code]
+pub fn run(args : Vec<String>) -> Result<(), String> {
+    let mut file = None;
+    let mut language = i18n::language_from_env();
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--lang", value)) => { language = Some(value.to_string()); },
+            None if !arg.starts_with("--") => { file = Some(arg); },
+            _ => { return Err(format!("Unknown option {}", arg)); },
+        }
+    }
+    let file = file.ok_or("Which game? Give the file it was recorded to")?;
+    let mut control = ReplayControl::new(GameRecord::load(Path::new(&file))?)?;
+    if let Some(code) = language {
+        control.languages.select(&code);
+    }
+    control.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{move_list, Replay};
+    use crate::i18n::Catalog;
+    use crate::model::game_model::{Action, Coord, ExpectedAction, GameEvent};
+    use crate::model::record::GameRecord;
+
+    /// Anna and Ben each turn over the first two cards that are still there, in a game of 2x4 cards.
+    fn record() -> GameRecord {
+        let mut record = GameRecord::new(11, 2, 4, vec!["Anna".to_string(), "Ben".to_string()]);
+        record.actions.push(Action::Reset);
+        for _ in 0..2 {
+            let view = record.replay(record.actions.len()).unwrap().public_view();
+            record.actions.extend(view.flippable[..2].iter().map(|c| Action::Flip(c.clone())));
+            record.actions.extend([Action::Confirm, Action::Confirm]);
+        }
+        record
+    }
+
+    #[test]
+    fn test_replay_steps() {
+        let mut replay = Replay::new(record()).unwrap();
+        assert_eq!(replay.turns.len(), 2);
+        assert_eq!(replay.current_turn(), None);
+        let events = replay.go_to(2);
+        assert!(matches!(events.as_slice(), [GameEvent::GameStarted, GameEvent::CardFlipped { .. }]));
+        assert_eq!(replay.current_turn(), Some(0));
+        replay.go_to_turn(1);
+        assert_eq!(replay.step(), 5);
+        assert_eq!(replay.game.current_player().name, "Ben");
+        assert_eq!(replay.current_turn(), Some(0));
+        // back to the first flip
+        assert!(replay.go_to(2).is_empty());
+        assert_eq!(replay.game.expected_action(), ExpectedAction::FlipSecondCard);
+        assert!(replay.game.is_clicked(&Coord(0, 0)));
+        replay.go_to(usize::MAX);
+        assert!(replay.at_end());
+        assert_eq!(replay.current_turn(), Some(1));
+    }
+
+    #[test]
+    fn test_move_list() {
+        let texts = Catalog::built_in().remove(0);
+        let replay = Replay::new(record()).unwrap();
+        let lines = move_list(&replay.turns, &texts);
+        assert_eq!(lines, vec!["1. Anna: 0 + 0 - pair", "2. Ben: 1 + 3 - no pair"]);
+
+        // the turns of the next game are numbered from 1 again, like in the notation
+        let mut record = record();
+        record.actions.push(Action::Reset);
+        let view = record.replay(record.actions.len()).unwrap().public_view();
+        record.actions.extend(view.flippable[..2].iter().map(|c| Action::Flip(c.clone())));
+        let mut replay = Replay::new(record).unwrap();
+        assert_eq!(move_list(&replay.turns, &texts), vec![
+            "Game 1, 1. Anna: 0 + 0 - pair",
+            "Game 1, 2. Ben: 1 + 3 - no pair",
+            "Game 2, 1. Anna: 1 + 3 - skipped",
+        ]);
+        replay.go_to(usize::MAX);
+        assert_eq!(replay.current_game(), 1);
+        assert_eq!(replay.games(), 2);
+        assert_eq!(replay.turn_index(1, 1), 2);
+        assert_eq!(replay.turn_index(0, 2), 1);
+        assert_eq!(replay.turn_index(0, 3), 2);
+        assert_eq!(replay.turn_index(1, 2), 3);
+    }
+}
@@ -0,0 +1,265 @@
+//! The protocol between the game and external bots ("engines"), in the spirit of the UCI
+//! protocol of chess engines.
+//!
+//! An engine is a program that reads messages on stdin and answers on stdout, one JSON object
+//! per line. Every message has a "type". The game sends:
+//!
+//! - `{"type":"hello","protocol":1}` once at the start, answered with
+//!   `{"type":"ready","name":"My bot"}`
+//! - `{"type":"new_game","seat":0,"view":{...}}` when a game starts, seat is the index of the
+//!   engine's player in view.players
+//! - `{"type":"update","view":{...}}` after every card that is flipped by any player, so the engine
+//!   can remember the open cards
+//! - `{"type":"move","view":{...}}` when it is the engine's turn, answered with
+//!   `{"type":"flip","row":2,"col":5}`, which must be one of view.flippable
+//! - `{"type":"game_over","view":{...}}` when the game is over
+//! - `{"type":"quit"}` before the engine is stopped
+//!
+//! The view is the PublicView of the game: face down cards are `{"state":"face_down"}`, open ones
+//! `{"state":"face_up","card_type":3,"title":"3"}`, taken ones `{"state":"removed"}`. An engine may
+//! also send `{"type":"log","text":"..."}` at any time, the text is printed by the game. Lines
+//! on stderr are passed through.
+//!
+//! An engine that doesn't answer in time, stops reading its input, sends something that isn't a
+//! message of the protocol or flips a card it may not flip makes the call fail. See `tests/engines/first_card.sh` for an
+//! engine in a few lines of shell.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::game_model::{Coord, PublicView};
+
+/// The version of the protocol, sent in the hello message
+pub const PROTOCOL_VERSION : u32 = 1;
+
+/// How long an engine may think if nothing else is set
+pub const DEFAULT_TIMEOUT : Duration = Duration::from_secs(5);
+
+/// Messages from the game to the engine
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToEngine {
+    Hello { protocol : u32 },
+    NewGame { seat : usize, view : PublicView },
+    Update { view : PublicView },
+    Move { view : PublicView },
+    GameOver { view : PublicView },
+    Quit,
+}
+
+/// Messages from the engine to the game
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FromEngine {
+    Ready { name : String },
+    Flip { row : usize, col : usize },
+    Log { text : String },
+}
+
+/// A running engine process
+pub struct Engine {
+    /// the name the engine gave itself
+    pub name : String,
+    /// how long to wait for an answer
+    pub timeout : Duration,
+    child : Child,
+    /// the lines for the engine, written by a thread so writing can time out as well
+    writer : Sender<String>,
+    /// whether the lines given to the writer could be written
+    written : Receiver<Result<(), String>>,
+    /// the lines the engine wrote, read by a thread so waiting can time out
+    lines : Receiver<String>,
+}
+
+impl Engine {
+    /// Starts the engine and waits for it to say it is ready. The command is split at spaces
+    /// into the program and its arguments.
+    pub fn start(command : &str, timeout : Duration) -> Result<Engine, String> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("The engine command is empty")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Can't start engine {}: {}", command, e))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => { if sender.send(line).is_err() { break; } },
+                    Err(_) => { break; },
+                }
+            }
+        });
+        let (writer, written) = write_lines(stdin);
+        let mut engine = Engine {
+            name : command.to_string(),
+            timeout,
+            child,
+            writer,
+            written,
+            lines,
+        };
+        engine.send(&ToEngine::Hello { protocol : PROTOCOL_VERSION })?;
+        match engine.receive()? {
+            FromEngine::Ready { name } => {
+                engine.name = name;
+                Ok(engine)
+            },
+            other => { Err(format!("Engine {} answered {:?} instead of ready", command, other)) },
+        }
+    }
+
+    /// Sends a message. An engine that doesn't take it within the timeout is stopped, since its
+    /// writer thread is stuck on the full pipe.
+    fn send(&mut self, message : &ToEngine) -> Result<(), String> {
+        let line = serde_json::to_string(message).expect("messages can be serialized");
+        if self.writer.send(line).is_err() {
+            return Err(format!("Engine {} has stopped", self.name));
+        }
+        match self.written.recv_timeout(self.timeout) {
+            Ok(result) => { result.map_err(|e| format!("Engine {} is gone: {}", self.name, e)) },
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = self.child.kill();
+                Err(format!("Engine {} didn't read its input within {} ms", self.name, self.timeout.as_millis()))
+            },
+            Err(RecvTimeoutError::Disconnected) => { Err(format!("Engine {} has stopped", self.name)) },
+        }
+    }
+
+    /// Waits for the next message. Log messages are printed and skipped.
+    fn receive(&mut self) -> Result<FromEngine, String> {
+        loop {
+            let line = match self.lines.recv_timeout(self.timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("Engine {} didn't answer within {} ms", self.name, self.timeout.as_millis()));
+                },
+                Err(RecvTimeoutError::Disconnected) => { return Err(format!("Engine {} has stopped", self.name)); },
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(FromEngine::Log { text }) => { println!("{}: {}", self.name, text); },
+                Ok(message) => { return Ok(message); },
+                Err(e) => { return Err(format!("Engine {} sent \"{}\": {}", self.name, line, e)); },
+            }
+        }
+    }
+
+    pub fn new_game(&mut self, seat : usize, view : &PublicView) -> Result<(), String> {
+        self.send(&ToEngine::NewGame { seat, view : view.clone() })
+    }
+
+    pub fn update(&mut self, view : &PublicView) -> Result<(), String> {
+        self.send(&ToEngine::Update { view : view.clone() })
+    }
+
+    /// Asks the engine which card to flip. Fails if the engine chooses a card that can't be flipped.
+    pub fn choose(&mut self, view : &PublicView) -> Result<Coord, String> {
+        self.send(&ToEngine::Move { view : view.clone() })?;
+        match self.receive()? {
+            FromEngine::Flip { row, col } if view.flippable.contains(&Coord(row, col)) => { Ok(Coord(row, col)) },
+            FromEngine::Flip { row, col } => { Err(format!("Engine {} can't flip the card at {}", self.name, Coord(row, col))) },
+            other => { Err(format!("Engine {} answered {:?} instead of a flip", self.name, other)) },
+        }
+    }
+
+    pub fn game_over(&mut self, view : &PublicView) -> Result<(), String> {
+        self.send(&ToEngine::GameOver { view : view.clone() })
+    }
+}
+
+/// Starts a thread that writes the lines sent to it to the engine and tells for each whether
+/// that worked. The thread ends at the first error or when the engine is dropped.
+fn write_lines(mut stdin : ChildStdin) -> (Sender<String>, Receiver<Result<(), String>>) {
+    let (writer, to_write) = mpsc::channel::<String>();
+    let (done, written) = mpsc::channel();
+    thread::spawn(move || {
+        for line in to_write {
+            let result = writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).map_err(|e| e.to_string());
+            let failed = result.is_err();
+            if done.send(result).is_err() || failed {
+                break;
+            }
+        }
+    });
+    (writer, written)
+}
+
+impl Drop for Engine {
+    /// Asks the engine to quit and kills it if it is still running after that.
+    fn drop(&mut self) {
+        let _ = self.send(&ToEngine::Quit);
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{Engine, FromEngine, ToEngine};
+    use crate::model::game_model::{Action, Game};
+    use std::time::Duration;
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(serde_json::to_string(&ToEngine::Hello { protocol : 1 }).unwrap(), r#"{"type":"hello","protocol":1}"#);
+        assert_eq!(serde_json::from_str::<FromEngine>(r#"{"type":"flip","row":2,"col":5}"#).unwrap(), FromEngine::Flip { row : 2, col : 5 });
+        assert!(serde_json::from_str::<FromEngine>(r#"{"type":"resign"}"#).is_err());
+    }
+
+    #[test]
+    fn test_engine_process() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Shell".to_string());
+        game.apply(Action::Reset).unwrap();
+        let mut engine = Engine::start("sh tests/engines/first_card.sh", Duration::from_secs(5)).unwrap();
+        assert_eq!(engine.name, "first card");
+        engine.new_game(0, &game.public_view()).unwrap();
+        let coord = engine.choose(&game.public_view()).unwrap();
+        assert_eq!(coord, game.public_view().flippable[0]);
+        game.apply(Action::Flip(coord)).unwrap();
+        engine.update(&game.public_view()).unwrap();
+        let coord = engine.choose(&game.public_view()).unwrap();
+        assert_eq!(coord, game.public_view().flippable[0]);
+    }
+
+    #[test]
+    fn test_engine_errors() {
+        assert!(Engine::start("./no/such/engine", Duration::from_secs(1)).is_err());
+        // answers nothing
+        let e = Engine::start("sleep 5", Duration::from_millis(100)).err().unwrap();
+        assert!(e.contains("didn't answer"), "{}", e);
+        // echoes the hello back, which is no message of an engine
+        let e = Engine::start("cat", Duration::from_secs(1)).err().unwrap();
+        assert!(e.contains("sent"), "{}", e);
+    }
+
+    #[test]
+    fn test_engine_not_reading() {
+        let mut game = Game::new(6, 6);
+        game.add_player("Deaf".to_string());
+        game.apply(Action::Reset).unwrap();
+        let mut engine = Engine::start("sh tests/engines/deaf.sh", Duration::from_millis(200)).unwrap();
+        // the updates fill the pipe, then the engine has to be given up instead of blocking
+        let e = (0..10_000).find_map(|_| engine.update(&game.public_view()).err()).unwrap();
+        assert!(e.contains("didn't read"), "{}", e);
+        assert!(engine.update(&game.public_view()).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! The game model for C and C++ programs.
+//!
+//! The header `include/memory_game.h` is generated from this file with the c-header feature. C
+//! programs link the library built with `--no-default-features`, otherwise it needs SDL2 and
+//! SDL2_ttf as well. A game is
+//! created with mg_game_new() and must be freed with mg_game_free(). All other functions take the
+//! game as first argument and report problems with an MgError code; mg_error_message() turns a
+//! code into English text.
+
+use std::ffi::{c_char, CStr};
+
+use crate::model::game_model::{Action, CellView, Coord, ExpectedAction, Game, GameError};
+
+/// The most rows or columns a field may have
+const MAX_SIDE : usize = 64;
+
+/// A game. C code only gets pointers to it.
+pub struct MgGame {
+    game : Game,
+}
+
+/// The result of a call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MgError {
+    Ok = 0,
+    /// the action isn't allowed in the current state, see mg_game_expected_action()
+    IllegalAction = 1,
+    /// the coordinate is outside of the field
+    OutOfBounds = 2,
+    /// there is no card at the coordinate any more
+    NoCard = 3,
+    /// the card is already face up
+    AlreadyOpen = 4,
+    /// the game has no players
+    NoPlayers = 5,
+    /// a pointer argument is NULL
+    NullPointer = 6,
+    /// an argument is invalid, e.g. a player name that isn't UTF-8 or an unknown player
+    InvalidArgument = 7,
+}
+
+impl From<GameError> for MgError {
+    fn from(e : GameError) -> Self {
+        match e {
+            GameError::IllegalAction { .. } => { MgError::IllegalAction },
+            GameError::OutOfBounds(_) => { MgError::OutOfBounds },
+            GameError::NoCard(_) => { MgError::NoCard },
+            GameError::AlreadyOpen(_) => { MgError::AlreadyOpen },
+            GameError::NoPlayers => { MgError::NoPlayers },
+        }
+    }
+}
+
+/// What the current player has to do next, see ExpectedAction
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MgExpected {
+    FlipFirstCard = 0,
+    FlipSecondCard = 1,
+    CheckResult = 2,
+    PassTurn = 3,
+    NewGame = 4,
+}
+
+impl From<ExpectedAction> for MgExpected {
+    fn from(e : ExpectedAction) -> Self {
+        match e {
+            ExpectedAction::FlipFirstCard => { MgExpected::FlipFirstCard },
+            ExpectedAction::FlipSecondCard => { MgExpected::FlipSecondCard },
+            ExpectedAction::CheckResult => { MgExpected::CheckResult },
+            ExpectedAction::PassTurn => { MgExpected::PassTurn },
+            ExpectedAction::NewGame => { MgExpected::NewGame },
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MgCellState {
+    FaceDown = 0,
+    FaceUp = 1,
+    /// the card has been taken as part of a pair
+    Removed = 2,
+}
+
+/// What can be seen of a card slot. card_type is -1 unless the card is face up.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MgCell {
+    pub state : MgCellState,
+    pub card_type : i32,
+}
+
+/// Creates a game with a field of rows x cols cards and no players. Returns NULL if the field
+/// has no cards, an odd number of cards or more than 64 rows or columns.
+#[no_mangle]
+pub extern "C" fn mg_game_new(rows : usize, cols : usize) -> *mut MgGame {
+    let fits = (1..=MAX_SIDE).contains(&rows) && (1..=MAX_SIDE).contains(&cols);
+    if !fits || !rows.checked_mul(cols).is_some_and(|cards| cards.is_multiple_of(2)) {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(MgGame { game : Game::new(rows, cols) }))
+}
+
+/// Frees a game. NULL is ignored.
+///
+/// # Safety
+/// The game must come from mg_game_new() and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_free(game : *mut MgGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Adds a player. The name is copied, it must be UTF-8.
+///
+/// # Safety
+/// The game must be valid and name a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_add_player(game : *mut MgGame, name : *const c_char) -> MgError {
+    let (Some(game), false) = (game.as_mut(), name.is_null()) else { return MgError::NullPointer };
+    match CStr::from_ptr(name).to_str() {
+        Ok(name) => {
+            game.game.add_player(name.to_string());
+            MgError::Ok
+        },
+        Err(_) => { MgError::InvalidArgument },
+    }
+}
+
+/// Starts a new game. The cards are shuffled with the seed, so the same seed gives the same game.
+///
+/// # Safety
+/// The game must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_reset(game : *mut MgGame, seed : u64) -> MgError {
+    let Some(game) = game.as_mut() else { return MgError::NullPointer };
+    game.game.set_seed(seed);
+    apply(game, Action::Reset)
+}
+
+/// Turns the card at row, col face up.
+///
+/// # Safety
+/// The game must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_flip(game : *mut MgGame, row : usize, col : usize) -> MgError {
+    let Some(game) = game.as_mut() else { return MgError::NullPointer };
+    apply(game, Action::Flip(Coord(row, col)))
+}
+
+/// Checks the two open cards, or passes the turn on after that.
+///
+/// # Safety
+/// The game must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_confirm(game : *mut MgGame) -> MgError {
+    let Some(game) = game.as_mut() else { return MgError::NullPointer };
+    apply(game, Action::Confirm)
+}
+
+fn apply(game : &mut MgGame, action : Action) -> MgError {
+    match game.game.apply(action) {
+        Ok(_) => { MgError::Ok },
+        Err(e) => { e.into() },
+    }
+}
+
+/// Returns what the current player has to do next, MG_EXPECTED_NEW_GAME for NULL.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_expected_action(game : *const MgGame) -> MgExpected {
+    match game.as_ref() {
+        Some(game) => { game.game.expected_action().into() },
+        None => { MgExpected::NewGame },
+    }
+}
+
+/// Returns true if the last two cards checked were a pair.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_last_guess_success(game : *const MgGame) -> bool {
+    game.as_ref().is_some_and(|game| game.game.last_guess_success())
+}
+
+/// Returns the number of rows of the field, 0 for NULL.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_rows(game : *const MgGame) -> usize {
+    game.as_ref().map_or(0, |game| game.game.field.height)
+}
+
+/// Returns the number of columns of the field, 0 for NULL.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_cols(game : *const MgGame) -> usize {
+    game.as_ref().map_or(0, |game| game.game.field.width)
+}
+
+/// Writes what can be seen of the card slot at row, col into cell.
+///
+/// # Safety
+/// The game must be valid and cell point to an MgCell.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_cell(game : *const MgGame, row : usize, col : usize, cell : *mut MgCell) -> MgError {
+    let (Some(game), Some(cell)) = (game.as_ref(), cell.as_mut()) else { return MgError::NullPointer };
+    *cell = match game.game.public_view().cell(&Coord(row, col)) {
+        Some(CellView::FaceDown) => { MgCell { state : MgCellState::FaceDown, card_type : -1 } },
+        Some(CellView::FaceUp { card_type, .. }) => { MgCell { state : MgCellState::FaceUp, card_type : *card_type as i32 } },
+        Some(CellView::Removed) => { MgCell { state : MgCellState::Removed, card_type : -1 } },
+        None => { return MgError::OutOfBounds; },
+    };
+    MgError::Ok
+}
+
+/// Returns the number of players, 0 for NULL.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_player_count(game : *const MgGame) -> usize {
+    game.as_ref().map_or(0, |game| game.game.players.len())
+}
+
+/// Returns the index of the player whose turn it is, 0 for NULL.
+///
+/// # Safety
+/// The game must be valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_current_player(game : *const MgGame) -> usize {
+    game.as_ref().map_or(0, |game| game.game.current_player_id)
+}
+
+/// Writes the points of the player under the rules of the game into score. They can be negative.
+/// Only with the classic rules are they the number of pairs; before there were other rules this
+/// function wrote the pairs as size_t, mg_game_pairs() does that now.
+///
+/// # Safety
+/// The game must be valid and score point to an int32_t.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_score(game : *const MgGame, player : usize, score : *mut i32) -> MgError {
+    let (Some(game), Some(score)) = (game.as_ref(), score.as_mut()) else { return MgError::NullPointer };
+    match game.game.scores().get(player) {
+        Some(s) => {
+            *score = *s;
+            MgError::Ok
+        },
+        None => { MgError::InvalidArgument },
+    }
+}
+
+/// Writes the number of pairs the player has found into pairs, whatever the rules.
+///
+/// # Safety
+/// The game must be valid and pairs point to a size_t.
+#[no_mangle]
+pub unsafe extern "C" fn mg_game_pairs(game : *const MgGame, player : usize, pairs : *mut usize) -> MgError {
+    let (Some(game), Some(pairs)) = (game.as_ref(), pairs.as_mut()) else { return MgError::NullPointer };
+    match game.game.players.get(player) {
+        Some(p) => {
+            *pairs = p.collected_cards.len() / 2;
+            MgError::Ok
+        },
+        None => { MgError::InvalidArgument },
+    }
+}
+
+/// Returns a short English description of the error code. The string is static, don't free it.
+#[no_mangle]
+pub extern "C" fn mg_error_message(error : MgError) -> *const c_char {
+    let text : &'static CStr = match error {
+        MgError::Ok => { c"no error" },
+        MgError::IllegalAction => { c"the action is not allowed now" },
+        MgError::OutOfBounds => { c"the coordinate is outside of the field" },
+        MgError::NoCard => { c"there is no card at the coordinate" },
+        MgError::AlreadyOpen => { c"the card is already open" },
+        MgError::NoPlayers => { c"there are no players" },
+        MgError::NullPointer => { c"a pointer argument is NULL" },
+        MgError::InvalidArgument => { c"invalid argument" },
+    };
+    text.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_game() {
+        unsafe {
+            assert!(mg_game_new(3, 3).is_null());
+            assert!(mg_game_new(usize::MAX, 2).is_null());
+            assert!(mg_game_new(MAX_SIDE + 1, 2).is_null());
+            assert!(mg_game_new(0, 2).is_null());
+            let big = mg_game_new(MAX_SIDE, MAX_SIDE);
+            assert!(!big.is_null());
+            mg_game_free(big);
+            let game = mg_game_new(2, 2);
+            assert_eq!(mg_game_reset(game, 1), MgError::NoPlayers);
+            assert_eq!(mg_game_add_player(game, c"Anna".as_ptr()), MgError::Ok);
+            assert_eq!(mg_game_reset(game, 1), MgError::Ok);
+            assert_eq!(mg_game_confirm(game), MgError::IllegalAction);
+            assert_eq!(mg_game_flip(game, 2, 0), MgError::OutOfBounds);
+            assert_eq!(mg_game_flip(game, 0, 0), MgError::Ok);
+            assert_eq!(mg_game_flip(game, 0, 0), MgError::AlreadyOpen);
+            let mut cell = MgCell { state : MgCellState::Removed, card_type : 0 };
+            assert_eq!(mg_game_cell(game, 0, 0, &mut cell), MgError::Ok);
+            assert_eq!(cell.state, MgCellState::FaceUp);
+            assert_eq!(mg_game_cell(game, 0, 1, &mut cell), MgError::Ok);
+            assert_eq!(cell, MgCell { state : MgCellState::FaceDown, card_type : -1 });
+            assert_eq!(mg_game_expected_action(game), MgExpected::FlipSecondCard);
+            let mut score = 9;
+            assert_eq!(mg_game_score(game, 0, &mut score), MgError::Ok);
+            assert_eq!(score, 0);
+            assert_eq!(mg_game_score(game, 1, &mut score), MgError::InvalidArgument);
+            assert_eq!(mg_game_score(game, 0, std::ptr::null_mut()), MgError::NullPointer);
+            let mut pairs = 9;
+            assert_eq!(mg_game_pairs(game, 0, &mut pairs), MgError::Ok);
+            assert_eq!(pairs, 0);
+            assert_eq!(mg_game_pairs(game, 1, &mut pairs), MgError::InvalidArgument);
+            assert_eq!(mg_game_add_player(game, std::ptr::null()), MgError::NullPointer);
+            mg_game_free(game);
+            mg_game_free(std::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+/// The language files that are built into the binary, as (language code, content).
+/// English is the reference: every other language must have the same keys.
+const BUILT_IN : [(&str, &str); 2] = [
+    ("en", include_str!("../lang/en.lang")),
+    ("de", include_str!("../lang/de.lang")),
+];
+
+/// The language that is used if nothing else is selected and that fills gaps in other languages
+pub const DEFAULT_LANGUAGE : &str = "en";
+
+/// The texts of one language.
+///
+/// Language files have one "key = value" pair per line, lines starting with '#' are comments.
+/// Values may contain placeholders like {player} which are replaced by format(). Texts that
+/// depend on a number come in plural forms, e.g. "cards.one" and "cards.other". The language
+/// decides which form is used for a number (see plural_form()). Example:
+///
+/// ```text
+/// language.name = English
+/// cards.one = {count} card
+/// cards.other = {count} cards
+/// prompt.your_turn = Player {player}, your turn!
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    /// language code, e.g. "en" or "de"
+    pub code : String,
+    messages : HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses the content of a language file. Errors name the line number.
+    pub fn parse(code : &str, text : &str) -> Result<Catalog, String> {
+        let mut messages = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) => { messages.insert(key.trim().to_string(), value.trim().to_string()); },
+                None => { return Err(format!("line {}: expected \"key = value\"", i + 1)); },
+            }
+        }
+        Ok(Catalog {
+            code : code.to_string(),
+            messages,
+        })
+    }
+
+    /// Loads a language file. The language code is the file name without extension, e.g. "fr.lang".
+    pub fn from_file(path : &Path) -> Result<Catalog, String> {
+        let code = path.file_stem().and_then(|s| s.to_str())
+            .ok_or_else(|| format!("{}: invalid file name", path.display()))?;
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Catalog::parse(code, &text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn built_in() -> Vec<Catalog> {
+        BUILT_IN.iter()
+            .map(|(code, text)| Catalog::parse(code, text).expect("built-in language file is broken"))
+            .collect()
+    }
+
+    /// The name of the language in the language itself, e.g. "Deutsch"
+    pub fn name(&self) -> &str {
+        self.text("language.name")
+    }
+
+    /// All keys of this language, sorted
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys : Vec<&str> = self.messages.keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns the keys of the reference catalog that are missing in this one.
+    pub fn missing_keys<'a>(&self, reference : &'a Catalog) -> Vec<&'a str> {
+        reference.keys().into_iter().filter(|k| !self.messages.contains_key(*k)).collect()
+    }
+
+    /// Adds the texts of the given catalog for all keys that are missing in this one.
+    fn fill_from(&mut self, other : &Catalog) {
+        for (key, value) in &other.messages {
+            self.messages.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Returns the text for the key. Unknown keys are returned as they are, so a missing text
+    /// shows up on the screen instead of crashing the game.
+    pub fn text<'a>(&'a self, key : &'a str) -> &'a str {
+        self.messages.get(key).map(|s| s.as_str()).unwrap_or(key)
+    }
+
+    /// Returns the text for the key with the placeholders replaced by the given values.
+    pub fn format(&self, key : &str, args : &[(&str, &dyn Display)]) -> String {
+        let mut text = self.text(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+
+    /// Returns the plural form of the key that fits the count, with {count} replaced.
+    pub fn plural(&self, key : &str, count : usize) -> String {
+        let form = format!("{}.{}", key, plural_form(&self.code, count));
+        self.format(&form, &[("count", &count)])
+    }
+
+    /// Like plural(), for counts that may be negative, e.g. points. The form is chosen by the
+    /// amount, so -1 gets the singular.
+    pub fn plural_signed(&self, key : &str, count : i32) -> String {
+        let form = format!("{}.{}", key, plural_form(&self.code, count.unsigned_abs() as usize));
+        self.format(&form, &[("count", &count)])
+    }
+}
+
+/// Returns the plural form a language uses for the given number: "one" or "other".
+/// All languages shipped so far only distinguish these two. French and some others use the
+/// singular for 0 as well.
+fn plural_form(code : &str, count : usize) -> &'static str {
+    let singular = match code {
+        "fr" | "pt" => { count <= 1 },
+        _ => { count == 1 },
+    };
+    if singular { "one" } else { "other" }
+}
+
+/// Guesses the language code from the environment (LC_ALL, LC_MESSAGES or LANG),
+/// e.g. "de" for "de_DE.UTF-8".
+pub fn language_from_env() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| value.split(['_', '.', '@']).next().unwrap_or("").to_lowercase())
+        .filter(|code| !code.is_empty() && code != "c" && code != "posix")
+}
+
+/// The available languages and which one is in use.
+pub struct Languages {
+    pub catalogs : Vec<Catalog>,
+    pub current : usize,
+}
+
+impl Languages {
+    /// The built-in languages plus all "*.lang" files found in the given directory. A file
+    /// replaces the built-in language with the same code. Texts missing in a language are
+    /// reported and taken from English.
+    pub fn load(dir : Option<&Path>) -> Self {
+        let mut catalogs = Catalog::built_in();
+        if let Some(dir) = dir {
+            let mut paths : Vec<_> = match fs::read_dir(dir) {
+                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+                Err(e) => {
+                    println!("Can't read language directory {}: {}", dir.display(), e);
+                    Vec::new()
+                },
+            };
+            paths.sort();
+            for path in paths.iter().filter(|p| p.extension().is_some_and(|ext| ext == "lang")) {
+                match Catalog::from_file(path) {
+                    Ok(catalog) => {
+                        catalogs.retain(|c| c.code != catalog.code);
+                        catalogs.push(catalog);
+                    },
+                    Err(e) => { println!("Skipping language {}", e); },
+                }
+            }
+        }
+        let reference = catalogs.iter().find(|c| c.code == DEFAULT_LANGUAGE).cloned()
+            .expect("the default language is built in");
+        for catalog in catalogs.iter_mut() {
+            let missing = catalog.missing_keys(&reference);
+            if !missing.is_empty() {
+                println!("Language {} has no texts for {}, using English instead", catalog.code, missing.join(", "));
+                catalog.fill_from(&reference);
+            }
+        }
+        let current = catalogs.iter().position(|c| c.code == DEFAULT_LANGUAGE).unwrap_or(0);
+        Languages {
+            catalogs,
+            current,
+        }
+    }
+
+    pub fn current(&self) -> &Catalog {
+        &self.catalogs[self.current]
+    }
+
+    /// Switches to the next language.
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.catalogs.len();
+    }
+
+    /// Switches to the language with the given code. Returns false if there is no such language.
+    pub fn select(&mut self, code : &str) -> bool {
+        match self.catalogs.iter().position(|c| c.code == code) {
+            Some(idx) => { self.current = idx; true },
+            None => { false },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, Languages, DEFAULT_LANGUAGE};
+    use std::path::Path;
+
+    /// Fails if a language file (built-in or shipped in lang/) is missing a text of the English one.
+    #[test]
+    fn test_all_languages_have_all_keys() {
+        let mut catalogs = Catalog::built_in();
+        for entry in std::fs::read_dir("lang").unwrap() {
+            catalogs.push(Catalog::from_file(&entry.unwrap().path()).unwrap());
+        }
+        let reference = catalogs.iter().find(|c| c.code == DEFAULT_LANGUAGE).unwrap().clone();
+        for catalog in &catalogs {
+            assert_eq!(catalog.missing_keys(&reference), Vec::<&str>::new(), "language {} is incomplete", catalog.code);
+            assert_eq!(reference.missing_keys(catalog), Vec::<&str>::new(), "language {} has unknown keys", catalog.code);
+        }
+    }
+
+    #[test]
+    fn test_format_and_plural() {
+        let en = Catalog::parse("en", "cards.one = {count} card\ncards.other = {count} cards\nhi = Hi {name}!").unwrap();
+        assert_eq!(en.plural("cards", 1), "1 card");
+        assert_eq!(en.plural("cards", 0), "0 cards");
+        assert_eq!(en.plural("cards", 2), "2 cards");
+        assert_eq!(en.plural_signed("cards", -1), "-1 card");
+        assert_eq!(en.plural_signed("cards", -3), "-3 cards");
+        assert_eq!(en.format("hi", &[("name", &"Anna")]), "Hi Anna!");
+        assert_eq!(en.text("no.such.key"), "no.such.key");
+        let fr = Catalog::parse("fr", "cards.one = {count} carte\ncards.other = {count} cartes").unwrap();
+        assert_eq!(fr.plural("cards", 0), "0 carte");
+        assert!(Catalog::parse("en", "no equals sign").is_err());
+    }
+
+    #[test]
+    fn test_languages() {
+        let mut languages = Languages::load(Some(Path::new("lang")));
+        assert_eq!(languages.current().code, "en");
+        assert!(languages.select("de"));
+        assert_eq!(languages.current().name(), "Deutsch");
+        assert_eq!(languages.current().plural("cards", 1), "1 Karte");
+        assert!(!languages.select("xx"));
+        languages.next();
+        assert_ne!(languages.current().code, "de");
+    }
+}
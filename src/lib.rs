@@ -0,0 +1,44 @@
+//! The memory game: players take turns flipping two cards and keep them if they are a pair.
+//!
+//! The game logic lives in [`model`] and has no dependencies on SDL. [`Game`] is driven by
+//! [`Game::apply`] with [`Action`]s and tells what happened with [`GameEvent`]s. Frontends and bots
+//! read the table through [`Game::public_view`], which hides face down cards.
+//!
+//! ```
+//! use memory_game::{Action, CellView, Coord, Game};
+//!
+//! let mut game = Game::new(2, 2);
+//! game.add_player("Anna".to_string());
+//! game.set_seed(7);
+//! game.apply(Action::Reset).unwrap();
+//! game.apply(Action::Flip(Coord(0, 0))).unwrap();
+//! let view = game.public_view();
+//! assert!(matches!(view.cell(&Coord(0, 0)), Some(CellView::FaceUp { .. })));
+//! assert!(game.apply(Action::Flip(Coord(5, 5))).is_err());
+//! ```
+//!
+//! Other modules:
+//!
+//! - [`engine`] is the protocol for bots written as separate programs
+//! - [`ffi`] makes the game usable from C and C++, see `include/memory_game.h`
+//! - [`net`] hosts games over TCP for players and spectators
+//! - [`simulator`] plays bot games for balancing and regression tests
+//! - [`i18n`] has the texts of the game in all languages
+//! - `view` and `controller` are the SDL frontend. They need the `sdl` feature, which is on by
+//!   default. Build with `default-features = false` to use the game logic without SDL.
+
+pub mod engine;
+pub mod ffi;
+pub mod i18n;
+pub mod model;
+pub mod net;
+pub mod simulator;
+
+#[cfg(feature = "sdl")]
+pub mod controller;
+#[cfg(feature = "sdl")]
+pub mod view;
+
+pub use model::game_model::{
+    Action, CellView, Coord, ExpectedAction, Game, GameError, GameEvent, GameState, PlayerView, PublicView,
+};
@@ -1,14 +1,21 @@
 
 use std::io;
 
-mod model;
-use model::game_model::{Game, Coord};
+use memory_game::model::game_model::Coord;
+use memory_game::controller::controller::Control;
+use memory_game::controller::net_controller;
+use memory_game::controller::replay_controller;
+use memory_game::model::record::GameRecord;
+use memory_game::view::accessibility::Accessibility;
+use memory_game::view::announcer::Announcer;
+use memory_game::view::sound::Volumes;
+use memory_game::view::theme::ThemeSet;
+use memory_game::i18n::{self, Languages};
+use memory_game::net;
+use memory_game::simulator;
 
-mod controller;
-use controller::controller::Control;
-
-mod view;
-// use view::board_view;
+mod settings;
+use settings::Settings;
 
 fn parse_and_check_bounds(s : &str, lower : usize, upper : usize) -> (bool, usize) {
     // let mut rslt: (bool, usize) = (false, 0);
@@ -56,13 +63,82 @@ fn query_one_coord(coord_counter : usize, upper : usize) -> Coord {
 }
 
 fn main() -> Result<(), String> {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    // "memory_game simulate ..." and "memory_game tournament ..." play bot games without opening a window,
+    // "memory_game serve ..." opens a lobby for network games, "memory_game rooms ..." lists its rooms
+    // and "memory_game watch ..." follows a game. "memory_game join ..." plays in a room of a server,
+    // "memory_game replay ..." shows a recorded game.
+    match args.first().map(|a| a.as_str()) {
+        Some("simulate") => { return simulator::run(args[1..].to_vec()); },
+        Some("tournament") => { return simulator::tournament::run(args[1..].to_vec()); },
+        Some("serve") => { return net::server::run(args[1..].to_vec()); },
+        Some("rooms") => { return net::client::rooms(args[1..].to_vec()); },
+        Some("watch") => { return net::client::watch(args[1..].to_vec()); },
+        Some("join") => { return net_controller::run(args[1..].to_vec()); },
+        Some("replay") => { return replay_controller::run(args[1..].to_vec()); },
+        _ => {},
+    }
     let height = 8;
     let width = 8;
+    // the player names and options are given on the command line, e.g. "memory_game Anna Ben"
+    let settings = Settings::from_args(args)?;
     let mut control = Control::new(height, width);
+    control.ui.animations.enabled = settings.animations;
+    control.auto_turn_delay = settings.auto_turn_delay;
+    control.themes = ThemeSet::load(Some(&settings.theme_dir));
+    if let Some(name) = &settings.theme {
+        if !control.themes.select(name) {
+            return Err(format!("Unknown theme {}", name));
+        }
+    }
+    control.languages = Languages::load(Some(&settings.lang_dir));
+    match &settings.language {
+        Some(code) => {
+            if !control.languages.select(code) {
+                return Err(format!("Unknown language {}", code));
+            }
+        },
+        // a language of the environment that isn't available just keeps English
+        None => {
+            if let Some(code) = i18n::language_from_env() {
+                control.languages.select(&code);
+            }
+        },
+    }
+    control.game.set_rules(settings.rules);
+    control.game.set_specials(settings.specials)?;
+    if let Some(path) = settings.record {
+        // the seed is part of the record, so the cards lie the same way when it is replayed
+        let seed = rand::random();
+        control.game.set_seed(seed);
+        let mut record = GameRecord::new(seed, height, width, settings.player_names.clone());
+        record.rules = settings.rules;
+        record.specials = settings.specials;
+        control.recording = Some((path, record));
+    }
+    for name in settings.player_names {
+        control.game.add_player(name);
+    }
+    control.font_dirs = settings.font_dirs;
+    control.ui.volumes = Volumes {
+        effects : settings.effects_volume as f32 / 100.0,
+        music : settings.music_volume as f32 / 100.0,
+        muted : settings.muted,
+    };
+    control.sound_pack_dir = settings.sound_pack;
+    control.accessibility = Accessibility {
+        high_contrast : settings.high_contrast,
+        text_scale : settings.text_scale,
+        focus_outline : settings.focus_outline,
+        shape_markers : settings.shape_markers,
+    };
+    control.announcer = match &settings.announce {
+        Some(path) if path.as_os_str() == "-" => { Announcer::stdout() },
+        Some(path) => { Announcer::to_file(path)? },
+        None => { Announcer::none() },
+    };
     control.reset();
-    let player_hugo = "Hugo".to_string();
-    control.game.add_player(player_hugo);
-    control.run();
+    control.run()?;
     
     Ok(())
 }
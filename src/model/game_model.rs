@@ -1,9 +1,13 @@
 
 //use std::io;
-use rand::thread_rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use super::scoring::{self, Rules, TurnResult};
+
 pub type CardId = usize;
 
 /// Info about the player.
@@ -33,6 +37,80 @@ pub struct Card {
     pub id : CardId,
     pub card_type : usize,
     pub title : String,
+    pub kind : CardKind,
+}
+
+impl Card {
+    /// Whether the two cards are a pair: of the same type, or one of them is a joker
+    pub fn matches(&self, other : &Card) -> bool {
+        self.card_type == other.card_type || self.kind == CardKind::Joker || other.kind == CardKind::Joker
+    }
+}
+
+/// What a card does besides being one of a pair. In JSON the kind is its name, e.g. "joker".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardKind {
+    #[default]
+    Normal,
+    /// matches any card
+    Joker,
+    /// when a pair with this card is found, the face down cards are shuffled
+    Shuffle,
+}
+
+/// The special cards of a deck, all other cards are ordinary pairs. The numbers count cards and
+/// have to be even, the jokers match each other as well. Written like in the notation, e.g.
+/// "standard + 2 jokers + 2 shuffles".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Specials {
+    pub jokers : usize,
+    pub shuffles : usize,
+}
+
+impl Specials {
+    /// Checks that the special cards fit into a deck of num_cards cards.
+    pub fn check(&self, num_cards : usize) -> Result<(), String> {
+        if !self.jokers.is_multiple_of(2) || !self.shuffles.is_multiple_of(2) {
+            return Err(format!("The special cards come in pairs, {} is no deck", self));
+        }
+        if self.jokers + self.shuffles > num_cards {
+            return Err(format!("{} special cards don't fit into a deck of {} cards", self.jokers + self.shuffles, num_cards));
+        }
+        Ok(())
+    }
+
+    /// Reads a deck like "standard + 2 jokers + 2 shuffles".
+    pub fn parse(text : &str) -> Result<Specials, String> {
+        let mut parts = text.split('+').map(|part| part.trim());
+        if parts.next() != Some("standard") {
+            return Err(format!("unknown deck \"{}\", expected standard with special cards like \"standard + 2 jokers\"", text));
+        }
+        let mut specials = Specials::default();
+        for part in parts {
+            let count = part.split_once(' ').and_then(|(count, kind)| Some((count.parse::<usize>().ok()?, kind)));
+            match count {
+                Some((count, "jokers" | "joker")) => { specials.jokers = count; },
+                Some((count, "shuffles" | "shuffle")) => { specials.shuffles = count; },
+                _ => { return Err(format!("unknown special cards \"{}\", expected e.g. \"2 jokers\" or \"2 shuffles\"", part)); },
+            }
+        }
+        Ok(specials)
+    }
+}
+
+impl fmt::Display for Specials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "standard")?;
+        if self.jokers > 0 {
+            write!(f, " + {} jokers", self.jokers)?;
+        }
+        if self.shuffles > 0 {
+            write!(f, " + {} shuffles", self.shuffles)?;
+        }
+        Ok(())
+    }
 }
 
 /// Represents a deck of cards
@@ -50,20 +128,36 @@ pub fn create_deck(num_pairs : usize) -> Deck {
             id : 2*i,
             card_type : i,
             title : i.to_string(),
+            kind : CardKind::Normal,
         });
         deck.push(Card {
             id : 2*i+1,
             card_type : i,
             title : i.to_string(),
+            kind : CardKind::Normal,
         });
     }
     deck
 }
 
+/// Creates a deck of num_cards cards with the given special cards at the end. The jokers and
+/// the shuffle cards have a card type of their own each, after the ordinary pairs.
+pub fn create_special_deck(num_cards : usize, specials : &Specials) -> Deck {
+    let num_pairs = (num_cards - specials.jokers - specials.shuffles) / 2;
+    let mut deck = create_deck(num_pairs);
+    for (kind, count, card_type, title) in [(CardKind::Joker, specials.jokers, num_pairs, "Joker"), (CardKind::Shuffle, specials.shuffles, num_pairs + 1, "Shuffle")] {
+        for _ in 0..count {
+            deck.push(Card { id : deck.len(), card_type, title : title.to_string(), kind });
+        }
+    }
+    deck
+}
+
 /// This creates a shuffle of the card deck (i.e. a vector witht he ids of the card deck in random order)
-pub fn shuffle_deck(deck : &Deck) -> Shuffle {
+/// The same random number generator state gives the same shuffle.
+pub fn shuffle_deck<R : Rng>(deck : &Deck, rng : &mut R) -> Shuffle {
     let mut deck_shuffle : Vec<usize> = (0..deck.len()).collect();
-    deck_shuffle.shuffle(&mut thread_rng());
+    deck_shuffle.shuffle(rng);
     deck_shuffle
 }
 
@@ -109,13 +203,13 @@ impl Field {
             }
         }
     }
-    pub fn place_deck(&mut self, deck : & Deck) {
+    pub fn place_deck<R : Rng>(&mut self, deck : & Deck, rng : &mut R) {
         let total_fields= self.width * self.height;
         let total_cards = deck.len();
         if total_fields != total_cards {
             panic!("Card number does not fit to field size");
         }
-        let shuffle = shuffle_deck(deck);
+        let shuffle = shuffle_deck(deck, rng);
         let mut row : usize = 0;
         let mut col : usize = 0;
         for card_id in shuffle {
@@ -127,41 +221,124 @@ impl Field {
             }
         }
     }
+
+    /// Rearranges the cards that are left on the field, the empty slots stay empty.
+    pub fn shuffle_cards<R : Rng>(&mut self, rng : &mut R) {
+        let slots : Vec<Coord> = self.card_slots().collect();
+        let mut cards : Vec<CardId> = slots.iter().filter_map(|c| self.field[c.0][c.1]).collect();
+        cards.shuffle(rng);
+        for (slot, card) in slots.iter().zip(cards) {
+            self.field[slot.0][slot.1] = Some(card);
+        }
+    }
+
+    /// The slots that still hold a card
+    pub fn card_slots(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.height).flat_map(move |row| (0..self.width).map(move |col| Coord(row, col)))
+            .filter(|c| self.field[c.0][c.1].is_some())
+    }
+
+    /// Finds the next slot holding a card when moving from `from` into the direction (dy, dx).
+    /// Empty slots are skipped. Cards on the same row (or column) are preferred, otherwise the card
+    /// closest to that line which still lies in the direction of the move is chosen.
+    /// Returns None if there is no card in that direction.
+    pub fn next_card_slot(&self, from : &Coord, dy : i32, dx : i32) -> Option<Coord> {
+        next_slot(self.card_slots(), from, dy, dx)
+    }
+
+    /// Finds the slot holding a card that is closest to `from` (which may be `from` itself).
+    /// Returns None if the field is empty.
+    pub fn nearest_card_slot(&self, from : &Coord) -> Option<Coord> {
+        nearest_slot(self.card_slots(), from)
+    }
+}
+
+/// The slot of `slots` to move to from `from` into the direction (dy, dx), see Field::next_card_slot()
+fn next_slot(slots : impl Iterator<Item = Coord>, from : &Coord, dy : i32, dx : i32) -> Option<Coord> {
+    let mut best : Option<(usize, usize, Coord)> = None;
+    for Coord(row, col) in slots {
+        let delta_y = row as i32 - from.0 as i32;
+        let delta_x = col as i32 - from.1 as i32;
+        // distance along the direction of the move and distance off the line of the move
+        let (along, off) = if dy != 0 {
+            (delta_y * dy.signum(), delta_x.unsigned_abs())
+        } else {
+            (delta_x * dx.signum(), delta_y.unsigned_abs())
+        };
+        if along <= 0 {
+            continue;
+        }
+        let key = (off as usize, along as usize);
+        let better = match &best {
+            Some((best_off, best_along, _)) => key < (*best_off, *best_along),
+            None => true,
+        };
+        if better {
+            best = Some((key.0, key.1, Coord(row, col)));
+        }
+    }
+    best.map(|(_, _, coord)| coord)
+}
+
+/// The slot of `slots` closest to `from`, see Field::nearest_card_slot()
+fn nearest_slot(slots : impl Iterator<Item = Coord>, from : &Coord) -> Option<Coord> {
+    let mut best : Option<(usize, Coord)> = None;
+    for Coord(row, col) in slots {
+        let dist = row.abs_diff(from.0) + col.abs_diff(from.1);
+        if best.as_ref().is_none_or(|(best_dist, _)| dist < *best_dist) {
+            best = Some((dist, Coord(row, col)));
+        }
+    }
+    best.map(|(_, coord)| coord)
 }
 
 /// Holds the core data of the game.
 /// 
-/// This is the expected sequence of function calls to make the Game iterate through it's states:
+/// The game is driven by apply() only, so every frontend (SDL, terminal, network, AI) plays it
+/// the same way. This is the expected sequence of actions and the states they lead to:
 /// 
 /// new() --> state = GameOver
 /// add_player()
 /// add_player()
 /// ...
-/// reset() --> state = StartGame
-/// open_card() --> state = FirstCard
-/// open_card() --> state = SecondCard
-/// check_guess_current_player() --> state = ViewResult
-/// check_gameover() --> state = NextUser
-/// next_player() --> state = StartSelectCaards
-/// open_card() -- state = FirstCard
+/// apply(Reset) --> state = StartGame
+/// apply(Flip(coord)) --> state = FirstCard
+/// apply(Flip(coord)) --> state = SecondCard
+/// apply(Confirm) --> cards are checked, state = NextUser
+/// apply(Confirm) --> state = StartSelectCards (next player) or GameOver
+/// apply(Flip(coord)) -- state = FirstCard
+/// apply(SkipTurn) --> the open card is turned face down, state = StartSelectCards (next player)
 /// ...
-/// check_gameover() -- state = GameOver
-/// reset() --> state = StartGame
+/// apply(Reset) --> state = StartGame
+///
+/// Actions that are not allowed in the current state are refused with a GameError and don't
+/// change anything.
+///
+/// The fields are only open to the view and controller of this crate. Other users of the library
+/// read the game through the query methods and public_view().
 pub struct Game {
-    pub state : GameState,
-    pub last_guess_success : bool,
-    pub rounds_counter : u32,
-    pub field : Field,
-    pub players : Vec<Player>,
-    pub deck : Deck,
-    pub current_player_id : usize,
-    pub num_clicked : usize,
-    pub clicked_card1 : Coord,
-    pub clicked_card2 : Coord,
+    pub(crate) state : GameState,
+    pub(crate) last_guess_success : bool,
+    pub(crate) rounds_counter : u32,
+    pub(crate) field : Field,
+    pub(crate) players : Vec<Player>,
+    pub(crate) deck : Deck,
+    pub(crate) current_player_id : usize,
+    pub(crate) num_clicked : usize,
+    pub(crate) clicked_card1 : Coord,
+    pub(crate) clicked_card2 : Coord,
+    /// how the turns are scored, see scores()
+    pub(crate) rules : Rules,
+    /// the turns of the current game
+    pub(crate) history : Vec<TurnResult>,
+    /// the special cards in the deck, see set_specials()
+    pub(crate) specials : Specials,
+    /// shuffles the deck on reset, see set_seed()
+    rng : StdRng,
 }
 
-#[derive(Clone)]
-#[derive(PartialEq)]
+#[derive(Clone, Debug)]
+#[derive(PartialEq, Serialize, Deserialize)]
 pub struct Coord (pub usize, pub usize);
 
 
@@ -171,7 +348,7 @@ impl fmt::Display for Coord {
     }
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum GameState {
     GameSetup,
     StartGame,
@@ -183,6 +360,148 @@ pub enum GameState {
     GameOver,
 }
 
+/// The things a player can do. They are handed to Game::apply().
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// turn the card at the coordinate face up
+    Flip(Coord),
+    /// move on: check the two open cards, or pass the turn on once the result has been seen
+    Confirm,
+    /// start a new game with the same players
+    Reset,
+    /// give up the turn before the second card, e.g. because the player has left. An open card is
+    /// turned face down again.
+    SkipTurn,
+}
+
+/// What happened in the game because of an action. Frontends use them to show what happened
+/// (animations, sounds, messages).
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// a new game has been started
+    GameStarted,
+    CardFlipped { player_id : usize, coord : Coord, card_id : CardId },
+    /// the two open cards are a pair and went to the player
+    PairFound { player_id : usize, first : (Coord, CardId), second : (Coord, CardId) },
+    /// the two open cards are no pair
+    NoPair { player_id : usize, first : (Coord, CardId), second : (Coord, CardId) },
+    /// a pair with a shuffle card was found and the face down cards have been rearranged
+    CardsShuffled,
+    /// the open cards are turned face down and the next player has the turn
+    TurnChanged { player_id : usize },
+    GameOver,
+}
+
+/// What the current player is expected to do next
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpectedAction {
+    /// flip the first card of the turn
+    FlipFirstCard,
+    /// flip the second card of the turn
+    FlipSecondCard,
+    /// confirm to see if the two open cards are a pair
+    CheckResult,
+    /// confirm to pass the turn on once the result has been seen
+    PassTurn,
+    /// the game is over, reset to start a new one
+    NewGame,
+}
+
+/// What everybody at the table can see of a card slot. Face down cards don't reveal anything.
+/// In JSON the state is a field, e.g. {"state":"face_up","card_type":3,"title":"3"}.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum CellView {
+    FaceDown,
+    FaceUp {
+        card_type : usize,
+        title : String,
+        #[serde(default)]
+        kind : CardKind,
+    },
+    /// the card has been taken as part of a pair
+    Removed,
+}
+
+/// What everybody at the table can see of a player
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub name : String,
+    /// the types of the pairs the player found
+    pub pairs : Vec<usize>,
+    /// the points of the player under the rules of the game
+    #[serde(default)]
+    pub score : i32,
+}
+
+/// The visible state of the game. Bots and remote clients get this instead of the Game, so they
+/// can't cheat by reading the deck or the field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicView {
+    pub height : usize,
+    pub width : usize,
+    /// the cells row by row, `cells[row][col]`
+    pub cells : Vec<Vec<CellView>>,
+    pub state : GameState,
+    pub round : u32,
+    pub current_player_id : usize,
+    pub players : Vec<PlayerView>,
+    pub expected_action : ExpectedAction,
+    pub flippable : Vec<Coord>,
+    pub last_guess_success : bool,
+    #[serde(default)]
+    pub rules : Rules,
+}
+
+impl PublicView {
+    pub fn cell(&self, coord : &Coord) -> Option<&CellView> {
+        self.cells.get(coord.0).and_then(|row| row.get(coord.1))
+    }
+
+    /// The cells that still hold a card
+    fn card_cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.height).flat_map(move |row| (0..self.width).map(move |col| Coord(row, col)))
+            .filter(|c| !matches!(self.cell(c), None | Some(CellView::Removed)))
+    }
+
+    /// Like Field::next_card_slot(), for clients that only see the view
+    pub fn next_card_cell(&self, from : &Coord, dy : i32, dx : i32) -> Option<Coord> {
+        next_slot(self.card_cells(), from, dy, dx)
+    }
+
+    /// Like Field::nearest_card_slot(), for clients that only see the view
+    pub fn nearest_card_cell(&self, from : &Coord) -> Option<Coord> {
+        nearest_slot(self.card_cells(), from)
+    }
+}
+
+/// Why an action was refused
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    /// the action can't be done in the current state, e.g. a flip while two cards are open
+    IllegalAction { action : Action, state : GameState },
+    /// the coordinate is outside of the field
+    OutOfBounds(Coord),
+    /// there is no card at the coordinate (any more)
+    NoCard(Coord),
+    /// the card is already face up
+    AlreadyOpen(Coord),
+    /// a game can't be started without players
+    NoPlayers,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::IllegalAction { action, state } => { write!(f, "{:?} is not allowed in state {:?}", action, state) },
+            GameError::OutOfBounds(c) => { write!(f, "{} is outside of the field", c) },
+            GameError::NoCard(c) => { write!(f, "there is no card at {}", c) },
+            GameError::AlreadyOpen(c) => { write!(f, "the card at {} is already open", c) },
+            GameError::NoPlayers => { write!(f, "there are no players") },
+        }
+    }
+}
+
 impl Game {
     pub fn new(height : usize, width : usize) -> Self {
         Self {
@@ -196,6 +515,10 @@ impl Game {
             num_clicked : 0,
             clicked_card1 : Coord(0, 0),
             clicked_card2 : Coord(0, 0),
+            rules : Rules::Classic,
+            history : Vec::new(),
+            specials : Specials::default(),
+            rng : StdRng::from_entropy(),
         }
     }
 
@@ -211,6 +534,40 @@ impl Game {
         self.rounds_counter
     }
 
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Sets how the turns are scored. The scores are computed from the turns, so this may even
+    /// be changed during a game.
+    pub fn set_rules(&mut self, rules : Rules) {
+        self.rules = rules;
+    }
+
+    pub fn specials(&self) -> Specials {
+        self.specials
+    }
+
+    /// Puts special cards into the deck instead of some of the ordinary pairs. Takes effect
+    /// with the next new game.
+    pub fn set_specials(&mut self, specials : Specials) -> Result<(), String> {
+        let num_cards = self.field.height * self.field.width;
+        specials.check(num_cards)?;
+        self.specials = specials;
+        self.deck = create_special_deck(num_cards, &specials);
+        Ok(())
+    }
+
+    /// The turns of the current game so far
+    pub fn history(&self) -> &[TurnResult] {
+        &self.history
+    }
+
+    /// The points of the players in the order of their seats, under the rules of the game
+    pub fn scores(&self) -> Vec<i32> {
+        scoring::scores(self.rules, &self.deck, &self.history, self.players.len())
+    }
+
     pub fn add_player(&mut self, name : String) {
         let p = Player::new(name);
         self.players.push(p);
@@ -220,15 +577,183 @@ impl Game {
         self.players[self.current_player_id].clone()
     }
 
-    pub fn reset(&mut self) {
+    /// The single entry point of the state machine. Checks that the action is allowed in the
+    /// current state and carries it out. Returns what happened, or why the action was refused,
+    /// in which case the game is unchanged.
+    pub fn apply(&mut self, action : Action) -> Result<Vec<GameEvent>, GameError> {
+        let state = self.game_state();
+        let illegal = |action : Action| Err(GameError::IllegalAction { action, state : state.clone() });
+        match action {
+            Action::Reset => {
+                if self.players.is_empty() {
+                    return Err(GameError::NoPlayers);
+                }
+                self.reset();
+                Ok(vec![GameEvent::GameStarted])
+            },
+            Action::Flip(coord) => {
+                if !matches!(state, GameState::StartGame | GameState::StartSelectCards | GameState::FirstCard) {
+                    return illegal(Action::Flip(coord));
+                }
+                if coord.0 >= self.field.height || coord.1 >= self.field.width {
+                    return Err(GameError::OutOfBounds(coord));
+                }
+                let card_id = match self.field.card_idx(coord.0, coord.1) {
+                    Some(id) => id,
+                    None => { return Err(GameError::NoCard(coord)); },
+                };
+                if self.is_clicked(&coord) {
+                    return Err(GameError::AlreadyOpen(coord));
+                }
+                if self.num_clicked == 0 {
+                    self.history.push(TurnResult { player : self.current_player_id, cards : Vec::new(), pair : None });
+                }
+                if let Some(turn) = self.history.last_mut() {
+                    turn.cards.push(card_id);
+                }
+                self.open_card(&coord);
+                Ok(vec![GameEvent::CardFlipped { player_id : self.current_player_id, coord, card_id }])
+            },
+            Action::Confirm => {
+                match state {
+                    GameState::SecondCard => {
+                        let player_id = self.current_player_id;
+                        let c1 = self.clicked_card1.clone();
+                        let c2 = self.clicked_card2.clone();
+                        // the cards are gone from the field if they are a pair
+                        let first = (c1.clone(), self.field.field[c1.0][c1.1].unwrap());
+                        let second = (c2.clone(), self.field.field[c2.0][c2.1].unwrap());
+                        if self.check_guess_current_player() {
+                            let shuffle = [first.1, second.1].iter().any(|id| self.deck[*id].kind == CardKind::Shuffle);
+                            let mut events = vec![GameEvent::PairFound { player_id, first, second }];
+                            if shuffle {
+                                self.field.shuffle_cards(&mut self.rng);
+                                events.push(GameEvent::CardsShuffled);
+                            }
+                            Ok(events)
+                        } else {
+                            Ok(vec![GameEvent::NoPair { player_id, first, second }])
+                        }
+                    },
+                    GameState::NextUser => {
+                        self.close_selected_cards();
+                        if self.check_game_over() {
+                            return Ok(vec![GameEvent::GameOver]);
+                        }
+                        self.next_player();
+                        Ok(vec![GameEvent::TurnChanged { player_id : self.current_player_id }])
+                    },
+                    _ => { illegal(Action::Confirm) },
+                }
+            },
+            Action::SkipTurn => {
+                if !matches!(state, GameState::StartGame | GameState::StartSelectCards | GameState::FirstCard) {
+                    return illegal(Action::SkipTurn);
+                }
+                self.close_selected_cards();
+                self.next_player();
+                Ok(vec![GameEvent::TurnChanged { player_id : self.current_player_id }])
+            },
+        }
+    }
+
+    /// Returns what the current player is expected to do next.
+    pub fn expected_action(&self) -> ExpectedAction {
+        match self.state {
+            GameState::StartGame | GameState::StartSelectCards => { ExpectedAction::FlipFirstCard },
+            GameState::FirstCard => { ExpectedAction::FlipSecondCard },
+            GameState::SecondCard => { ExpectedAction::CheckResult },
+            GameState::NextUser => { ExpectedAction::PassTurn },
+            GameState::GameSetup | GameState::GameOver => { ExpectedAction::NewGame },
+        }
+    }
+
+    /// Returns the coordinates of all cards that can be flipped right now, row by row. The list
+    /// is empty if no card may be flipped in the current state.
+    pub fn flippable_coords(&self) -> Vec<Coord> {
+        if !matches!(self.expected_action(), ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard) {
+            return Vec::new();
+        }
+        let mut coords = Vec::new();
+        for row in 0..self.field.height {
+            for col in 0..self.field.width {
+                let coord = Coord(row, col);
+                if self.field.field[row][col].is_some() && !self.is_clicked(&coord) {
+                    coords.push(coord);
+                }
+            }
+        }
+        coords
+    }
+
+    /// Returns true if apply() would accept the action.
+    pub fn is_legal(&self, action : &Action) -> bool {
+        match action {
+            Action::Reset => { !self.players.is_empty() },
+            Action::Flip(coord) => { self.flippable_coords().contains(coord) },
+            Action::Confirm => { matches!(self.expected_action(), ExpectedAction::CheckResult | ExpectedAction::PassTurn) },
+            Action::SkipTurn => { matches!(self.expected_action(), ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard) },
+        }
+    }
+
+    /// Returns the visible state of the game: open cards show their type, face down cards don't.
+    pub fn public_view(&self) -> PublicView {
+        let cells = (0..self.field.height).map(|row| {
+            (0..self.field.width).map(|col| {
+                match self.field.field[row][col] {
+                    None => { CellView::Removed },
+                    Some(card_id) if self.is_clicked(&Coord(row, col)) => {
+                        let card = &self.deck[card_id];
+                        CellView::FaceUp { card_type : card.card_type, title : card.title.clone(), kind : card.kind }
+                    },
+                    Some(_) => { CellView::FaceDown },
+                }
+            }).collect()
+        }).collect();
+        let scores = self.scores();
+        let players = self.players.iter().zip(scores).map(|(p, score)| PlayerView {
+            name : p.name.clone(),
+            // the cards of a pair are collected one after the other, a joker counts as the card
+            // it was paired with
+            pairs : p.collected_cards.chunks(2).map(|pair| {
+                let card = pair.iter().map(|id| &self.deck[*id]).find(|card| card.kind != CardKind::Joker);
+                card.unwrap_or(&self.deck[pair[0]]).card_type
+            }).collect(),
+            score,
+        }).collect();
+        PublicView {
+            height : self.field.height,
+            width : self.field.width,
+            cells,
+            state : self.game_state(),
+            round : self.rounds_counter,
+            current_player_id : self.current_player_id,
+            players,
+            expected_action : self.expected_action(),
+            flippable : self.flippable_coords(),
+            last_guess_success : self.last_guess_success,
+            rules : self.rules,
+        }
+    }
+
+    /// Seeds the random number generator that shuffles the cards. Games reset after this are
+    /// the same for the same seed, which makes simulations and tests repeatable.
+    pub fn set_seed(&mut self, seed : u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn reset(&mut self) {
         self.rounds_counter = 1;
         self.state = GameState::StartGame;
         self.field.clear_field();
-        self.field.place_deck(&self.deck);
+        self.field.place_deck(&self.deck, &mut self.rng);
         for player in &mut self.players {
             player.collected_cards.clear();
         }
         self.current_player_id = 0;
+        self.num_clicked = 0;
+        self.last_guess_success = false;
+        self.history.clear();
     }
 
     pub fn card_at(&self, y : usize, x : usize) -> Option<&Card> {
@@ -259,11 +784,11 @@ impl Game {
         result
     }
 
-    pub fn close_selected_cards(&mut self) {
+    fn close_selected_cards(&mut self) {
         self.num_clicked = 0;
     }
 
-    pub fn open_card(&mut self, coord : &Coord) -> bool { 
+    fn open_card(&mut self, coord : &Coord) -> bool { 
         if ! self.coord_has_card(coord) {
             return false;
         }
@@ -283,16 +808,20 @@ impl Game {
         false
     }
 
-    pub fn check_guess_current_player(&mut self) -> bool {
+    fn check_guess_current_player(&mut self) -> bool {
         // copying the parameters is just borrow checker bs
         // could probably be avoided with some lifetime crap ...
         let p = self.current_player_id;
         let c1 = self.clicked_card1.clone();
         let c2 = self.clicked_card2.clone();
-        self.check_guess(p, &c1, &c2)
+        let pair = self.check_guess(p, &c1, &c2);
+        if let Some(turn) = self.history.last_mut() {
+            turn.pair = Some(pair);
+        }
+        pair
     }
 
-    pub fn check_guess(& mut self, player : usize, coord1 : &Coord, coord2 : &Coord) -> bool {
+    fn check_guess(& mut self, player : usize, coord1 : &Coord, coord2 : &Coord) -> bool {
         if self.field.field[coord1.0][coord1.1] == None {
             println!("coord1 card already taken");
             return false;
@@ -310,7 +839,7 @@ impl Game {
         let card_id2 = self.field.field[coord2.0][coord2.1].unwrap();
         let col_cards = &mut self.players[player].collected_cards;
 
-        if self.deck[card_id1].card_type == self.deck[card_id2].card_type {
+        if self.deck[card_id1].matches(&self.deck[card_id2]) {
             col_cards.push(card_id1);
             col_cards.push(card_id2);
 
@@ -323,24 +852,24 @@ impl Game {
         false
     }
 
-    pub fn check_game_over(&mut self) -> bool {
+    fn check_game_over(&mut self) -> bool {
         if self.state == GameState::GameOver {
             return true;
         }
-        for row in 0..self.field.height {
-            for col in 0..self.field.width {
-                if self.field.field[row][col] != None {
-                    self.state = GameState::NextUser;
-                    // self.state = GameState::StartSelectCards;
-                    return false;
-                }
-            }
+        // the game is over when no pair is left, with jokers a few single cards may remain
+        let cards : Vec<CardId> = self.field.card_slots().filter_map(|c| self.field.field[c.0][c.1]).collect();
+        let pair_left = cards.iter().enumerate()
+            .any(|(i, a)| cards[i + 1..].iter().any(|b| self.deck[*a].matches(&self.deck[*b])));
+        if pair_left {
+            self.state = GameState::NextUser;
+            // self.state = GameState::StartSelectCards;
+            return false;
         }
         self.state = GameState::GameOver;
         true
     }
 
-    pub fn next_player(&mut self) {
+    fn next_player(&mut self) {
         self.state = GameState::StartSelectCards;
         self.current_player_id += 1;
         if self.current_player_id >= self.players.len() {
@@ -392,7 +921,10 @@ impl Game {
 
 #[cfg(test)]
 mod tests {
-    use super::{create_deck, shuffle_deck};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::{create_deck, shuffle_deck, Action, CardKind, CellView, Coord, ExpectedAction, Field, Game, GameError, GameEvent, GameState, Specials};
+    use crate::model::scoring::Rules;
     #[test]
     fn test_create_deck() {
         let deck = create_deck(32);
@@ -409,9 +941,269 @@ mod tests {
     #[test]
     fn test_shuffle_deck() {
         let deck = create_deck(32);
-        let shuffle = shuffle_deck(&deck);
+        let shuffle = shuffle_deck(&deck, &mut StdRng::seed_from_u64(7));
         assert_eq!(shuffle.len(), 64);
+        assert_eq!(shuffle, shuffle_deck(&deck, &mut StdRng::seed_from_u64(7)));
         // we should now test that all numbers between 0 and 31 are in it twice, maybe also check randomness in some way
         // but too much work
     }
+    #[test]
+    fn test_next_card_slot_skips_empty_slots() {
+        let mut field = Field::new(3, 4);
+        field.place_deck(&create_deck(6), &mut StdRng::seed_from_u64(1));
+        field.field[1][2] = None;
+        // same row is preferred, the empty slot is skipped
+        assert_eq!(field.next_card_slot(&Coord(1, 1), 0, 1), Some(Coord(1, 3)));
+        // no card left in that direction on the row -> closest card off the row
+        field.field[1][3] = None;
+        assert_eq!(field.next_card_slot(&Coord(1, 1), 0, 1), Some(Coord(0, 2)));
+        // nothing above the top row
+        assert_eq!(field.next_card_slot(&Coord(0, 1), -1, 0), None);
+        assert_eq!(field.next_card_slot(&Coord(0, 1), 1, 0), Some(Coord(1, 1)));
+    }
+    #[test]
+    fn test_nearest_card_slot() {
+        let mut field = Field::new(2, 2);
+        field.place_deck(&create_deck(2), &mut StdRng::seed_from_u64(1));
+        assert_eq!(field.nearest_card_slot(&Coord(1, 1)), Some(Coord(1, 1)));
+        field.field[1][1] = None;
+        field.field[0][1] = None;
+        assert_eq!(field.nearest_card_slot(&Coord(1, 1)), Some(Coord(1, 0)));
+        field.clear_field();
+        assert_eq!(field.nearest_card_slot(&Coord(1, 1)), None);
+    }
+
+    /// Returns the coordinates of two cards of the same type and, if there are any, of two cards
+    /// that are no pair.
+    fn pair_and_no_pair(game : &Game) -> ((Coord, Coord), Option<(Coord, Coord)>) {
+        let mut coords = Vec::new();
+        for row in 0..game.field.height {
+            for col in 0..game.field.width {
+                if game.card_at(row, col).is_some() {
+                    coords.push(Coord(row, col));
+                }
+            }
+        }
+        let card_type = |c : &Coord| game.card_at(c.0, c.1).unwrap().card_type;
+        let first = coords[0].clone();
+        let partner = coords.iter().skip(1).find(|c| card_type(c) == card_type(&first)).unwrap().clone();
+        let other = coords.iter().find(|c| card_type(c) != card_type(&first)).cloned();
+        ((first.clone(), partner), other.map(|o| (first, o)))
+    }
+
+    #[test]
+    fn test_apply_turns() {
+        let mut game = Game::new(2, 2);
+        assert_eq!(game.apply(Action::Reset), Err(GameError::NoPlayers));
+        game.add_player("Anna".to_string());
+        game.add_player("Ben".to_string());
+        assert_eq!(game.apply(Action::Reset), Ok(vec![GameEvent::GameStarted]));
+
+        // Anna misses
+        let (c1, c2) = pair_and_no_pair(&game).1.unwrap();
+        let events = game.apply(Action::Flip(c1.clone())).unwrap();
+        assert!(matches!(&events[..], [GameEvent::CardFlipped { player_id : 0, coord, .. }] if *coord == c1));
+        game.apply(Action::Flip(c2.clone())).unwrap();
+        assert_eq!(game.game_state(), GameState::SecondCard);
+        assert!(matches!(&game.apply(Action::Confirm).unwrap()[..], [GameEvent::NoPair { player_id : 0, .. }]));
+        assert_eq!(game.apply(Action::Confirm), Ok(vec![GameEvent::TurnChanged { player_id : 1 }]));
+        assert!(!game.is_clicked(&c1));
+
+        // Ben finds a pair, then Anna finds the last one and the game is over
+        for player_id in [1, 0] {
+            let ((c1, c2), _) = pair_and_no_pair(&game);
+            game.apply(Action::Flip(c1)).unwrap();
+            game.apply(Action::Flip(c2)).unwrap();
+            let events = game.apply(Action::Confirm).unwrap();
+            assert!(matches!(&events[..], [GameEvent::PairFound { player_id : p, .. }] if *p == player_id));
+            let next = if player_id == 1 { GameEvent::TurnChanged { player_id : 0 } } else { GameEvent::GameOver };
+            assert_eq!(game.apply(Action::Confirm), Ok(vec![next]));
+        }
+        assert_eq!(game.game_state(), GameState::GameOver);
+        assert_eq!(game.players[0].collected_cards.len(), 2);
+        assert_eq!(game.players[1].collected_cards.len(), 2);
+
+        // the scores follow from the turns, Anna missed once
+        assert_eq!(game.history().iter().map(|t| (t.player, t.pair)).collect::<Vec<_>>(), vec![(0, Some(false)), (1, Some(true)), (0, Some(true))]);
+        assert_eq!(game.scores(), vec![1, 1]);
+        game.set_rules(Rules::MismatchPenalty);
+        assert_eq!(game.public_view().players.iter().map(|p| p.score).collect::<Vec<_>>(), vec![0, 1]);
+        game.apply(Action::Reset).unwrap();
+        assert_eq!(game.scores(), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_jokers() {
+        let mut game = Game::new(2, 3);
+        game.add_player("Anna".to_string());
+        assert!(game.set_specials(Specials { jokers : 3, shuffles : 0 }).is_err());
+        game.set_specials(Specials { jokers : 2, shuffles : 0 }).unwrap();
+        assert_eq!(game.deck.iter().filter(|c| c.kind == CardKind::Joker).count(), 2);
+        game.set_seed(5);
+        game.apply(Action::Reset).unwrap();
+        // each joker takes a card of another pair, the two cards left over are no pair
+        for card_id in [0, 2] {
+            let coords = game.flippable_coords();
+            let joker = coords.iter().find(|c| game.card_at(c.0, c.1).unwrap().kind == CardKind::Joker).unwrap().clone();
+            let other = coords.iter().find(|c| game.card_at(c.0, c.1).unwrap().id == card_id).unwrap().clone();
+            game.apply(Action::Flip(joker)).unwrap();
+            game.apply(Action::Flip(other)).unwrap();
+            assert!(matches!(&game.apply(Action::Confirm).unwrap()[..], [GameEvent::PairFound { .. }]));
+            game.apply(Action::Confirm).unwrap();
+        }
+        assert_eq!(game.game_state(), GameState::GameOver);
+        assert_eq!(game.field.card_slots().count(), 2);
+        // the jokers were turned over first, the pairs show the other cards
+        let types : Vec<usize> = [0, 2].iter().map(|id| game.deck[*id].card_type).collect();
+        assert_eq!(game.public_view().players[0].pairs, types);
+    }
+
+    #[test]
+    fn test_shuffle_cards() {
+        let mut game = Game::new(2, 3);
+        game.add_player("Anna".to_string());
+        game.set_specials(Specials { jokers : 0, shuffles : 2 }).unwrap();
+        game.set_seed(5);
+        game.apply(Action::Reset).unwrap();
+        let shuffles : Vec<Coord> = game.flippable_coords().into_iter()
+            .filter(|c| game.card_at(c.0, c.1).unwrap().kind == CardKind::Shuffle)
+            .collect();
+        game.apply(Action::Flip(shuffles[0].clone())).unwrap();
+        game.apply(Action::Flip(shuffles[1].clone())).unwrap();
+        let events = game.apply(Action::Confirm).unwrap();
+        assert!(matches!(&events[..], [GameEvent::PairFound { .. }, GameEvent::CardsShuffled]));
+        // the other cards are still there, in the same slots
+        let mut slots : Vec<Coord> = game.field.card_slots().collect();
+        assert!(slots.iter().all(|c| !shuffles.contains(c)));
+        slots.sort_by_key(|c| game.field.field[c.0][c.1]);
+        assert_eq!(slots.iter().map(|c| game.field.field[c.0][c.1].unwrap()).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_specials_names() {
+        let specials = Specials { jokers : 2, shuffles : 4 };
+        assert_eq!(specials.to_string(), "standard + 2 jokers + 4 shuffles");
+        assert_eq!(Specials::parse("standard + 2 jokers + 4 shuffles"), Ok(specials));
+        assert_eq!(Specials::parse("standard"), Ok(Specials::default()));
+        assert!(Specials::parse("standard + jokers").is_err());
+        assert!(Specials { jokers : 2, shuffles : 0 }.check(2).is_ok());
+        assert!(Specials { jokers : 4, shuffles : 0 }.check(2).is_err());
+    }
+
+    #[test]
+    fn test_apply_refuses_illegal_actions() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Anna".to_string());
+        // a new game starts in GameOver
+        assert!(matches!(game.apply(Action::Flip(Coord(0, 0))), Err(GameError::IllegalAction { .. })));
+        assert!(matches!(game.apply(Action::Confirm), Err(GameError::IllegalAction { .. })));
+        game.apply(Action::Reset).unwrap();
+        assert!(matches!(game.apply(Action::Confirm), Err(GameError::IllegalAction { .. })));
+        assert_eq!(game.apply(Action::Flip(Coord(2, 0))), Err(GameError::OutOfBounds(Coord(2, 0))));
+        game.apply(Action::Flip(Coord(0, 0))).unwrap();
+        assert_eq!(game.apply(Action::Flip(Coord(0, 0))), Err(GameError::AlreadyOpen(Coord(0, 0))));
+        assert_eq!(game.game_state(), GameState::FirstCard);
+        game.apply(Action::Flip(Coord(0, 1))).unwrap();
+        assert!(matches!(game.apply(Action::Flip(Coord(1, 1))), Err(GameError::IllegalAction { .. })));
+        assert_eq!(game.game_state(), GameState::SecondCard);
+        assert!(matches!(game.apply(Action::SkipTurn), Err(GameError::IllegalAction { .. })));
+    }
+
+    #[test]
+    fn test_skip_turn() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Anna".to_string());
+        game.add_player("Ben".to_string());
+        game.apply(Action::Reset).unwrap();
+        assert!(game.is_legal(&Action::SkipTurn));
+        assert_eq!(game.apply(Action::SkipTurn), Ok(vec![GameEvent::TurnChanged { player_id : 1 }]));
+        game.apply(Action::Flip(Coord(0, 0))).unwrap();
+        assert_eq!(game.apply(Action::SkipTurn), Ok(vec![GameEvent::TurnChanged { player_id : 0 }]));
+        assert_eq!(game.round(), 2);
+        assert!(!game.is_clicked(&Coord(0, 0)));
+        assert_eq!(game.flippable_coords().len(), 4);
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Anna".to_string());
+        assert_eq!(game.expected_action(), ExpectedAction::NewGame);
+        assert!(game.flippable_coords().is_empty());
+        game.apply(Action::Reset).unwrap();
+        assert_eq!(game.expected_action(), ExpectedAction::FlipFirstCard);
+        assert_eq!(game.flippable_coords().len(), 4);
+        assert!(!game.is_legal(&Action::Confirm));
+
+        let (c1, c2) = pair_and_no_pair(&game).1.unwrap();
+        game.apply(Action::Flip(c1.clone())).unwrap();
+        assert_eq!(game.expected_action(), ExpectedAction::FlipSecondCard);
+        assert!(!game.flippable_coords().contains(&c1));
+        assert!(!game.is_legal(&Action::Flip(c1.clone())));
+        assert!(game.is_legal(&Action::Flip(c2.clone())));
+
+        game.apply(Action::Flip(c2.clone())).unwrap();
+        assert_eq!(game.expected_action(), ExpectedAction::CheckResult);
+        assert!(game.flippable_coords().is_empty());
+        assert!(game.is_legal(&Action::Confirm));
+        game.apply(Action::Confirm).unwrap();
+        assert_eq!(game.expected_action(), ExpectedAction::PassTurn);
+    }
+
+    #[test]
+    fn test_public_view_hides_face_down_cards() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Anna".to_string());
+        game.apply(Action::Reset).unwrap();
+        let view = game.public_view();
+        assert!(view.cells.iter().flatten().all(|c| *c == CellView::FaceDown));
+
+        let ((c1, c2), _) = pair_and_no_pair(&game);
+        let card_type = game.card_at(c1.0, c1.1).unwrap().card_type;
+        game.apply(Action::Flip(c1.clone())).unwrap();
+        let view = game.public_view();
+        assert!(matches!(view.cell(&c1), Some(CellView::FaceUp { card_type : t, .. }) if *t == card_type));
+        assert_eq!(view.cells.iter().flatten().filter(|c| **c == CellView::FaceDown).count(), 3);
+        assert_eq!(view.flippable.len(), 3);
+
+        game.apply(Action::Flip(c2.clone())).unwrap();
+        game.apply(Action::Confirm).unwrap();
+        let view = game.public_view();
+        assert_eq!(view.cell(&c1), Some(&CellView::Removed));
+        assert_eq!(view.cell(&c2), Some(&CellView::Removed));
+        assert_eq!(view.players[0].pairs, vec![card_type]);
+        assert!(view.last_guess_success);
+    }
+
+    #[test]
+    fn test_view_moves_like_the_field() {
+        let mut game = Game::new(3, 4);
+        game.add_player("Anna".to_string());
+        game.apply(Action::Reset).unwrap();
+        game.field.field[1][2] = None;
+        game.field.field[0][0] = None;
+        let view = game.public_view();
+        for row in 0..3 {
+            for col in 0..4 {
+                let from = Coord(row, col);
+                for (dy, dx) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    assert_eq!(view.next_card_cell(&from, dy, dx), game.field.next_card_slot(&from, dy, dx));
+                }
+                assert_eq!(view.nearest_card_cell(&from), game.field.nearest_card_slot(&from));
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeded_games_are_repeatable() {
+        let layout = |seed : u64| {
+            let mut game = Game::new(4, 4);
+            game.add_player("Anna".to_string());
+            game.set_seed(seed);
+            game.apply(Action::Reset).unwrap();
+            game.field.field.clone()
+        };
+        assert_eq!(layout(42), layout(42));
+        assert_ne!(layout(42), layout(43));
+    }
 }
@@ -1,2 +1,5 @@
 
 pub mod game_model;
+pub mod notation;
+pub mod record;
+pub mod scoring;
@@ -0,0 +1,432 @@
+//! A compact text notation for recorded games, easy to read and to diff. A game looks like this:
+//!
+//! ```text
+//! seed: 7
+//! size: 4x4
+//! deck: standard
+//! players: Anna, Ben
+//! rules: classic
+//!
+//! 1. Anna a1 c3 -
+//! 2. Ben b4 d2 x
+//! 3. Anna d1 skip
+//! new game
+//! 1. Anna a2 c2 x *
+//! ```
+//!
+//! The header names the seed, the size of the field (rows x columns), the deck (standard, with
+//! special cards e.g. `standard + 2 jokers + 2 shuffles`), the players in the order of their
+//! seats and the rules of scoring (see scoring::Rules). Then every turn has a line with its
+//! number, the player and the cards turned over: the column as a letter and the row as a number,
+//! so `b4` is the second card of the fourth row. `x` means the cards were a pair, `-` that they
+//! weren't; the turn is then passed on, unless `*` follows because the game stopped before.
+//! `skip` gives the turn up. `new game` starts the next game with the same players, the turns
+//! are counted from 1 again. Lines starting with `#` are comments.
+
+use super::game_model::{Action, Coord, ExpectedAction, Game, GameError, GameEvent, GameState, Specials};
+use super::record::{self, GameRecord, MAX_SIDE};
+use super::scoring::Rules;
+
+/// The name of a card slot, e.g. "b4" for Coord(3, 1)
+pub fn coord_name(coord : &Coord) -> String {
+    format!("{}{}", (b'a' + coord.1 as u8) as char, coord.0 + 1)
+}
+
+/// Reads a card slot like "b4". Returns None if the text isn't one.
+pub fn parse_coord(text : &str) -> Option<Coord> {
+    let mut chars = text.chars();
+    let col = chars.next().filter(|c| c.is_ascii_lowercase())? as usize - 'a' as usize;
+    let row : usize = chars.as_str().parse().ok().filter(|row| *row > 0)?;
+    Some(Coord(row - 1, col))
+}
+
+/// Writes a record in the notation. Fails for records the notation can't hold: fields with more
+/// than 26 rows or columns, player names with commas, and records that don't start with a new game.
+pub fn write_record(record : &GameRecord) -> Result<String, String> {
+    if record.rows > MAX_SIDE || record.cols > MAX_SIDE {
+        return Err(format!("The notation has room for {} rows and columns, the field is {}x{}", MAX_SIDE, record.rows, record.cols));
+    }
+    if record.players.is_empty() {
+        return Err("The record has no players".to_string());
+    }
+    if let Some(name) = record.players.iter().find(|name| name.contains(',') || name.trim() != name.as_str() || name.is_empty()) {
+        return Err(format!("The player name \"{}\" can't be written, names are separated by commas", name));
+    }
+    let mut text = format!("seed: {}\nsize: {}x{}\ndeck: {}\nplayers: {}\nrules: {}\n\n",
+        record.seed, record.rows, record.cols, record.specials, record.players.join(", "), record.rules);
+    if record.actions.first() != Some(&Action::Reset) {
+        return Err("The record doesn't start with a new game".to_string());
+    }
+    let mut game = record.replay(0)?;
+    let mut line = String::new();
+    let mut turn = 0;
+    let mut actions = record.actions.iter().enumerate().peekable();
+    while let Some((i, action)) = actions.next() {
+        let expected = game.expected_action();
+        let player = game.current_player().name;
+        let events = game.apply(action.clone()).map_err(|e| format!("Action {} of the record can't be played: {}", i + 1, e))?;
+        match (action, expected) {
+            (Action::Reset, _) => {
+                if i > 0 {
+                    end_line(&mut text, &mut line);
+                    text.push_str("new game\n");
+                }
+                turn = 0;
+            },
+            (Action::Flip(_) | Action::SkipTurn, ExpectedAction::FlipFirstCard) => {
+                end_line(&mut text, &mut line);
+                turn += 1;
+                line = format!("{}. {}", turn, player);
+                line.push_str(&move_text(action));
+            },
+            (Action::Flip(_) | Action::SkipTurn, _) => { line.push_str(&move_text(action)); },
+            (Action::Confirm, ExpectedAction::CheckResult) => {
+                let pair = events.iter().any(|e| matches!(e, GameEvent::PairFound { .. }));
+                line.push_str(if pair { " x" } else { " -" });
+                if actions.peek().map(|(_, next)| *next) == Some(&Action::Confirm) {
+                    let (j, pass) = actions.next().expect("peeked");
+                    game.apply(pass.clone()).map_err(|e| format!("Action {} of the record can't be played: {}", j + 1, e))?;
+                } else {
+                    line.push_str(" *");
+                }
+            },
+            (Action::Confirm, _) => { return Err(format!("Action {} of the record passes on a turn that was already passed on", i + 1)); },
+        }
+    }
+    end_line(&mut text, &mut line);
+    Ok(text)
+}
+
+fn move_text(action : &Action) -> String {
+    match action {
+        Action::Flip(coord) => { format!(" {}", coord_name(coord)) },
+        _ => { " skip".to_string() },
+    }
+}
+
+fn end_line(text : &mut String, line : &mut String) {
+    if !line.is_empty() {
+        text.push_str(line);
+        text.push('\n');
+        line.clear();
+    }
+}
+
+/// Reads a game in the notation. The game is played while it is read, so the errors name the
+/// line and tell what is wrong, e.g. "line 9: Ben has the turn, not Anna".
+pub fn parse_record(text : &str) -> Result<GameRecord, String> {
+    let mut header = Header::default();
+    let mut started : Option<(GameRecord, Game)> = None;
+    let mut turn = 0;
+    let mut last_line = 1;
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        last_line = number;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if started.is_none() && !line.starts_with(|c : char| c.is_ascii_digit()) && line != "new game" {
+            header.read(line).map_err(|e| format!("line {}: {}", number, e))?;
+            continue;
+        }
+        let (record, game) = match started.as_mut() {
+            Some(started) => { started },
+            None => {
+                // the first game starts with the first turn
+                let mut record = header.record().map_err(|e| format!("line {}: {}", number, e))?;
+                let mut game = record.replay(0).map_err(|e| format!("line {}: {}", number, e))?;
+                game.apply(Action::Reset).map_err(|e| format!("line {}: {}", number, e))?;
+                record.actions.push(Action::Reset);
+                started.insert((record, game))
+            },
+        };
+        if line == "new game" {
+            play(record, game, Action::Reset, line, number)?;
+            turn = 0;
+            continue;
+        }
+        let Some((turn_number, rest)) = line.split_once(". ") else {
+            return Err(format!("line {}: expected a turn like \"3. Anna b4 f2 x\"", number));
+        };
+        turn += 1;
+        if turn_number.parse::<usize>() != Ok(turn) {
+            return Err(format!("line {}: expected turn {}, found {}", number, turn, turn_number));
+        }
+        match game.expected_action() {
+            ExpectedAction::FlipFirstCard => {},
+            ExpectedAction::NewGame => { return Err(format!("line {}: the game is over, expected \"new game\"", number)); },
+            _ => { return Err(format!("line {}: the turn before isn't over", number)); },
+        }
+        let player = game.current_player().name;
+        let moves = match rest.strip_prefix(player.as_str()) {
+            Some(moves) if moves.is_empty() || moves.starts_with(' ') => { moves },
+            _ => { return Err(format!("line {}: {} has the turn, not {}", number, player, rest.split_whitespace().next().unwrap_or(""))); },
+        };
+        let mut checked = false;
+        let mut stopped = false;
+        let mut skipped = false;
+        for token in moves.split_whitespace() {
+            if stopped {
+                return Err(format!("line {}: nothing may follow \"*\"", number));
+            }
+            if skipped {
+                return Err(format!("line {}: nothing may follow \"skip\", the next player has the turn", number));
+            }
+            match token {
+                "x" | "-" => {
+                    if checked {
+                        return Err(format!("line {}: the cards were already checked", number));
+                    }
+                    let events = play(record, game, Action::Confirm, token, number)?;
+                    let pair = events.iter().any(|e| matches!(e, GameEvent::PairFound { .. }));
+                    if pair != (token == "x") {
+                        let (what, marker) = if pair { ("a pair", "x") } else { ("no pair", "-") };
+                        return Err(format!("line {}: the cards are {}, expected {}", number, what, marker));
+                    }
+                    checked = true;
+                },
+                "*" if checked => { stopped = true; },
+                "*" => { return Err(format!("line {}: \"*\" can only follow x or -", number)); },
+                "skip" => {
+                    play(record, game, Action::SkipTurn, token, number)?;
+                    skipped = true;
+                },
+                _ => {
+                    let coord = parse_coord(token).ok_or_else(|| format!("line {}: unknown move \"{}\"", number, token))?;
+                    play(record, game, Action::Flip(coord), token, number)?;
+                },
+            }
+        }
+        if checked && !stopped {
+            play(record, game, Action::Confirm, "x", number)?;
+        }
+    }
+    match started {
+        Some((record, _)) => { Ok(record) },
+        None => {
+            let mut record = header.record().map_err(|e| format!("line {}: {}", last_line, e))?;
+            record.actions.push(Action::Reset);
+            Ok(record)
+        },
+    }
+}
+
+/// Applies a move of the notation to the game and records it.
+fn play(record : &mut GameRecord, game : &mut Game, action : Action, token : &str, line : usize) -> Result<Vec<GameEvent>, String> {
+    let events = game.apply(action.clone()).map_err(|e| format!("line {}: {}", line, describe(&e, token, game)))?;
+    record.actions.push(action);
+    Ok(events)
+}
+
+/// Says why a move of the notation was refused, with the slot as it was written.
+fn describe(error : &GameError, token : &str, game : &Game) -> String {
+    match error {
+        GameError::OutOfBounds(_) => { format!("{} is outside of the {}x{} field", token, game.field.height, game.field.width) },
+        GameError::NoCard(_) => { format!("there is no card at {} any more", token) },
+        GameError::AlreadyOpen(_) => { format!("{} is already open", token) },
+        GameError::IllegalAction { action : Action::Confirm, .. } => { format!("{} needs two open cards", token) },
+        GameError::IllegalAction { state : GameState::NextUser, .. } => { format!("{} is not possible now, the turn has to be passed on first", token) },
+        GameError::IllegalAction { .. } => { format!("{} is not possible now, the cards have to be checked first", token) },
+        GameError::NoPlayers => { "there are no players".to_string() },
+    }
+}
+
+/// The header lines read so far
+#[derive(Default)]
+struct Header {
+    seed : Option<u64>,
+    size : Option<(usize, usize)>,
+    players : Option<Vec<String>>,
+    rules : Rules,
+    specials : Specials,
+}
+
+impl Header {
+    fn read(&mut self, line : &str) -> Result<(), String> {
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(format!("expected \"key: value\" in the header, found \"{}\"", line));
+        };
+        let value = value.trim();
+        match key.trim() {
+            "seed" => { self.seed = Some(value.parse().map_err(|_| format!("the seed must be a number, found \"{}\"", value))?); },
+            "size" => {
+                let size = value.split_once('x').and_then(|(rows, cols)| Some((rows.trim().parse().ok()?, cols.trim().parse().ok()?)));
+                match size {
+                    Some((rows, cols)) if record::check_size(rows, cols).is_ok() => {
+                        self.size = Some((rows, cols));
+                    },
+                    _ => {
+                        return Err(format!("the size must be like 4x6 (rows x columns, at most {} each, an even number of cards), found \"{}\"", MAX_SIDE, value));
+                    },
+                }
+            },
+            "players" => {
+                let players : Vec<String> = value.split(',').map(|name| name.trim().to_string()).collect();
+                if players.iter().any(|name| name.is_empty()) {
+                    return Err(format!("the players must be names separated by commas, found \"{}\"", value));
+                }
+                self.players = Some(players);
+            },
+            "deck" => { self.specials = Specials::parse(value)?; },
+            "rules" => {
+                self.rules = Rules::from_name(value).map_err(|_| {
+                    let names : Vec<&str> = Rules::ALL.iter().map(|rules| rules.name()).collect();
+                    format!("unknown rules \"{}\", expected one of {}", value, names.join(", "))
+                })?;
+            },
+            other => { return Err(format!("unknown header \"{}\"", other)); },
+        }
+        Ok(())
+    }
+
+    fn record(&self) -> Result<GameRecord, String> {
+        let seed = self.seed.ok_or("the header has no seed")?;
+        let (rows, cols) = self.size.ok_or("the header has no size")?;
+        let players = self.players.clone().ok_or("the header has no players")?;
+        self.specials.check(rows * cols).map_err(|e| e.to_lowercase())?;
+        let mut record = GameRecord::new(seed, rows, cols, players);
+        record.rules = self.rules;
+        record.specials = self.specials;
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coord_name, parse_coord, parse_record, write_record};
+    use crate::model::game_model::{Action, Coord, Game, Specials};
+    use crate::model::record::GameRecord;
+    use crate::model::scoring::Rules;
+
+    #[test]
+    fn test_coords() {
+        assert_eq!(coord_name(&Coord(3, 1)), "b4");
+        assert_eq!(parse_coord("b4"), Some(Coord(3, 1)));
+        assert_eq!(parse_coord("p16"), Some(Coord(15, 15)));
+        assert_eq!(parse_coord("a0"), None);
+        assert_eq!(parse_coord("B4"), None);
+        assert_eq!(parse_coord("b"), None);
+    }
+
+    /// A record of two games: the first is played to the end, the second stops after a check.
+    /// Anna gives one turn up and Ben skips another after the first card.
+    fn two_games() -> GameRecord {
+        let mut record = GameRecord::new(42, 2, 4, vec!["Anna".to_string(), "Ben Bo".to_string()]);
+        let mut game = Game::new(2, 4);
+        game.add_player("Anna".to_string());
+        game.add_player("Ben Bo".to_string());
+        game.set_seed(42);
+        let mut play = |game : &mut Game, action : Action| {
+            game.apply(action.clone()).unwrap();
+            record.actions.push(action);
+        };
+        play(&mut game, Action::Reset);
+        play(&mut game, Action::SkipTurn);
+        let first = game.flippable_coords()[0].clone();
+        play(&mut game, Action::Flip(first));
+        play(&mut game, Action::SkipTurn);
+        // the first two cards, then a pair, until the field is empty
+        while game.expected_action() != crate::model::game_model::ExpectedAction::NewGame {
+            let cards = game.flippable_coords();
+            let pair = cards.iter().skip(1)
+                .find(|c| game.card_at(c.0, c.1).unwrap().card_type == game.card_at(cards[0].0, cards[0].1).unwrap().card_type)
+                .unwrap().clone();
+            let second = if game.round().is_multiple_of(2) { pair } else { cards[1].clone() };
+            play(&mut game, Action::Flip(cards[0].clone()));
+            play(&mut game, Action::Flip(second));
+            play(&mut game, Action::Confirm);
+            play(&mut game, Action::Confirm);
+        }
+        play(&mut game, Action::Reset);
+        let cards = game.flippable_coords();
+        play(&mut game, Action::Flip(cards[0].clone()));
+        play(&mut game, Action::Reset);
+        let cards = game.flippable_coords();
+        play(&mut game, Action::Flip(cards[0].clone()));
+        play(&mut game, Action::Flip(cards[1].clone()));
+        play(&mut game, Action::Confirm);
+        record
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let record = two_games();
+        let text = write_record(&record).unwrap();
+        assert!(text.starts_with("seed: 42\nsize: 2x4\ndeck: standard\nplayers: Anna, Ben Bo\nrules: classic\n\n1. Anna skip\n2. Ben Bo "));
+        assert_eq!(text.matches("new game").count(), 2);
+        assert!(text.trim_end().ends_with(" *"));
+        assert_eq!(parse_record(&text).unwrap(), record);
+
+        let mut started = GameRecord::new(1, 2, 2, vec!["Anna".to_string()]);
+        assert!(write_record(&started).is_err());
+        started.actions.push(Action::Reset);
+        assert_eq!(parse_record(&write_record(&started).unwrap()).unwrap(), started);
+        started.rules = Rules::StreakBonus;
+        let text = write_record(&started).unwrap();
+        assert!(text.contains("\nrules: streak-bonus\n"));
+        assert_eq!(parse_record(&text).unwrap(), started);
+        started.specials = Specials { jokers : 2, shuffles : 0 };
+        let text = write_record(&started).unwrap();
+        assert!(text.contains("\ndeck: standard + 2 jokers\n"));
+        assert_eq!(parse_record(&text).unwrap(), started);
+    }
+
+    #[test]
+    fn test_written_by_hand() {
+        let record = two_games();
+        let text = write_record(&record).unwrap();
+        // comments, blank lines and extra spaces don't matter
+        let by_hand = format!("# a game\n{}", text.replace("\n1. Anna", "\n\n   1. Anna ").replace("seed:", "seed :"));
+        assert_eq!(parse_record(&by_hand).unwrap(), record);
+    }
+
+    #[test]
+    fn test_errors() {
+        let header = "seed: 42\nsize: 2x4\ndeck: standard\nplayers: Anna, Ben\nrules: classic\n";
+        let error = |turns : &str| parse_record(&format!("{}{}", header, turns)).unwrap_err();
+        assert_eq!(error("1. Ben a1 b1 -\n"), "line 6: Anna has the turn, not Ben");
+        assert_eq!(error("1. Anna a1 b9 -\n"), "line 6: b9 is outside of the 2x4 field");
+        assert_eq!(error("1. Anna a1 a1 -\n"), "line 6: a1 is already open");
+        assert_eq!(error("1. Anna a1 -\n"), "line 6: - needs two open cards");
+        assert_eq!(error("1. Anna a1 b1 -\n3. Ben c1 d1 -\n"), "line 7: expected turn 2, found 3");
+        assert_eq!(error("1. Anna a1 b1 c1\n"), "line 6: c1 is not possible now, the cards have to be checked first");
+        assert_eq!(error("1. Anna a1 b1 - *\n2. Ben c1\n"), "line 7: the turn before isn't over");
+        assert_eq!(error("new game\n1. Anna a1 b1 -\nnew game\n2. Anna a1 b1 -\n"), "line 9: expected turn 1, found 2");
+        assert_eq!(error("1. Anna a1 b1 - x\n"), "line 6: the cards were already checked");
+        assert_eq!(error("1. Anna a1 b1 - c1\n"), "line 6: c1 is not possible now, the turn has to be passed on first");
+        assert_eq!(error("1. Anna skip a1 b1 -\n"), "line 6: nothing may follow \"skip\", the next player has the turn");
+        assert_eq!(error("1. Anna a1 skip b1\n"), "line 6: nothing may follow \"skip\", the next player has the turn");
+        assert_eq!(error("1. Anna a1 zz\n"), "line 6: unknown move \"zz\"");
+        assert_eq!(error("1 Anna a1\n"), "line 6: expected a turn like \"3. Anna b4 f2 x\"");
+
+        let mut game = GameRecord::new(42, 2, 4, vec!["Anna".to_string(), "Ben".to_string()]).replay(0).unwrap();
+        game.apply(Action::Reset).unwrap();
+        let first = game.card_at(0, 0).unwrap().card_type;
+        let pair = game.flippable_coords().into_iter().skip(1).find(|c| game.card_at(c.0, c.1).unwrap().card_type == first).unwrap();
+        let turn = format!("1. Anna a1 {} -\n", coord_name(&pair));
+        assert_eq!(error(&turn), "line 6: the cards are a pair, expected x");
+
+        assert_eq!(parse_record("seed: 1\nsize: 3x3\n").unwrap_err(), "line 2: the size must be like 4x6 (rows x columns, at most 26 each, an even number of cards), found \"3x3\"");
+        assert!(parse_record("seed: 1\nsize: 100000x100000\n").unwrap_err().starts_with("line 2: the size must be like 4x6"));
+        assert!(parse_record("seed: 1\nsize: 28x2\n").is_err());
+        assert_eq!(parse_record("seed: 1\ncolour: red\n").unwrap_err(), "line 2: unknown header \"colour\"");
+        assert_eq!(parse_record("seed: 1\ndeck: jokers\n").unwrap_err(), "line 2: unknown deck \"jokers\", expected standard with special cards like \"standard + 2 jokers\"");
+        assert_eq!(parse_record("seed: 1\ndeck: standard + 2 queens\n").unwrap_err(), "line 2: unknown special cards \"2 queens\", expected e.g. \"2 jokers\" or \"2 shuffles\"");
+        assert_eq!(parse_record("seed: 1\nsize: 2x2\nplayers: Anna\ndeck: standard + 6 jokers\n").unwrap_err(), "line 4: 6 special cards don't fit into a deck of 4 cards");
+        assert_eq!(parse_record("seed: 1\nrules: fair\n").unwrap_err(), "line 2: unknown rules \"fair\", expected one of classic, mismatch-penalty, known-card-penalty, streak-bonus");
+        assert_eq!(parse_record("seed: one\n").unwrap_err(), "line 1: the seed must be a number, found \"one\"");
+        assert_eq!(parse_record("seed: 1\nsize: 2x2\n\n1. Anna a1\n").unwrap_err(), "line 4: the header has no players");
+        assert_eq!(parse_record("seed: 1\nplayers: Anna\n").unwrap_err(), "line 2: the header has no size");
+    }
+
+    #[test]
+    fn test_unwritable_records() {
+        let commas = GameRecord::new(1, 2, 2, vec!["Anna, Ben".to_string()]);
+        assert!(write_record(&commas).is_err());
+        let wide = GameRecord::new(1, 1, 28, vec!["Anna".to_string()]);
+        assert!(write_record(&wide).is_err());
+        let high = GameRecord::new(1, 28, 1, vec!["Anna".to_string()]);
+        assert!(write_record(&high).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::game_model::{Action, Coord, ExpectedAction, Game, GameEvent, Specials};
+use super::notation;
+use super::scoring::Rules;
+
+/// The most rows or columns of a recorded field. The notation has letters for 26 columns, rows
+/// are limited the same way, so a record can't ask for a huge field.
+pub const MAX_SIDE : usize = 26;
+
+/// Checks that a field of rows x cols cards can be recorded: 1 to MAX_SIDE rows and columns and
+/// an even number of cards.
+pub fn check_size(rows : usize, cols : usize) -> Result<(), String> {
+    if !(1..=MAX_SIDE).contains(&rows) || !(1..=MAX_SIDE).contains(&cols) || !(rows * cols).is_multiple_of(2) {
+        return Err(format!("A field needs 1 to {} rows and columns and an even number of cards, not {}x{}", MAX_SIDE, rows, cols));
+    }
+    Ok(())
+}
+
+/// A recorded game: how it was set up and every action that was applied to it. Together with
+/// the seed this is enough to play the game again exactly as it went, see replay().
+///
+/// Records are saved in the text notation of the notation module, or as JSON, e.g.
+/// {"seed":7,"rows":2,"cols":2,"players":["Anna"],"actions":["Reset",{"Flip":[0,1]},"Confirm"]}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub seed : u64,
+    pub rows : usize,
+    pub cols : usize,
+    pub players : Vec<String>,
+    /// how the turns were scored, classic in records from before there were other rules
+    #[serde(default)]
+    pub rules : Rules,
+    /// the special cards in the deck
+    #[serde(default)]
+    pub specials : Specials,
+    /// the actions in the order they were applied, starting with the Reset of the first game
+    pub actions : Vec<Action>,
+}
+
+/// A turn of a recorded game, as shown in the move list of the replay
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    /// the index of the first action of the turn in GameRecord::actions
+    pub start : usize,
+    /// the index of the game the turn belongs to, a record goes on after each new game
+    pub game : usize,
+    /// the number of the turn in its game, counted from 1 like in the notation
+    pub number : usize,
+    pub player : String,
+    /// the cards the player turned over, with their titles
+    pub flips : Vec<(Coord, String)>,
+    /// whether the cards were a pair, None if the turn was skipped before the check
+    pub pair : Option<bool>,
+}
+
+impl GameRecord {
+    pub fn new(seed : u64, rows : usize, cols : usize, players : Vec<String>) -> Self {
+        GameRecord { seed, rows, cols, players, rules : Rules::Classic, specials : Specials::default(), actions : Vec::new() }
+    }
+
+    /// The game as it was before the first action. Fails if the field or the special cards
+    /// can't be set up, e.g. for a record from a broken file.
+    fn setup(&self) -> Result<Game, String> {
+        check_size(self.rows, self.cols)?;
+        let mut game = Game::new(self.rows, self.cols);
+        for name in &self.players {
+            game.add_player(name.clone());
+        }
+        game.set_seed(self.seed);
+        game.set_rules(self.rules);
+        game.set_specials(self.specials)?;
+        Ok(game)
+    }
+
+    /// Plays the first steps actions of the record and returns the game as it was then. Fails
+    /// if an action is refused, i.e. the record doesn't belong to this version of the game, or
+    /// if the special cards don't fit the field.
+    pub fn replay(&self, steps : usize) -> Result<Game, String> {
+        let mut game = self.setup()?;
+        for (i, action) in self.actions.iter().take(steps).enumerate() {
+            game.apply(action.clone()).map_err(|e| format!("Action {} of the record can't be played: {}", i + 1, e))?;
+        }
+        Ok(game)
+    }
+
+    /// Plays the whole record and splits it into turns. A turn starts with the first card of a
+    /// player and ends when the next player gets the turn.
+    pub fn turns(&self) -> Result<Vec<Turn>, String> {
+        let mut game = self.setup()?;
+        let mut turns : Vec<Turn> = Vec::new();
+        let mut game_index = 0;
+        let mut number = 0;
+        for (i, action) in self.actions.iter().enumerate() {
+            if *action == Action::Reset {
+                if i > 0 {
+                    game_index += 1;
+                }
+                number = 0;
+            } else if game.expected_action() == ExpectedAction::FlipFirstCard {
+                number += 1;
+                let player = game.current_player().name.clone();
+                turns.push(Turn { start : i, game : game_index, number, player, flips : Vec::new(), pair : None });
+            }
+            let events = game.apply(action.clone()).map_err(|e| format!("Action {} of the record can't be played: {}", i + 1, e))?;
+            let Some(turn) = turns.last_mut() else { continue };
+            for event in events {
+                match event {
+                    GameEvent::CardFlipped { coord, card_id, .. } => { turn.flips.push((coord, game.deck[card_id].title.clone())); },
+                    GameEvent::PairFound { .. } => { turn.pair = Some(true); },
+                    GameEvent::NoPair { .. } => { turn.pair = Some(false); },
+                    _ => {},
+                }
+            }
+        }
+        Ok(turns)
+    }
+
+    /// Saves the record as JSON if the file name ends with .json, else in the notation of the
+    /// notation module.
+    pub fn save(&self, path : &Path) -> Result<(), String> {
+        let text = if is_json(path) {
+            serde_json::to_string(self).expect("records can be serialized")
+        } else {
+            notation::write_record(self)?
+        };
+        fs::write(path, text).map_err(|e| format!("Can't write {}: {}", path.display(), e))
+    }
+
+    /// Loads a record saved by save(). Records with a field that can't be set up are refused.
+    pub fn load(path : &Path) -> Result<GameRecord, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+        if is_json(path) {
+            let record : GameRecord = serde_json::from_str(&text).map_err(|e| format!("{} is no game record: {}", path.display(), e))?;
+            record.setup().map_err(|e| format!("{} is no game record: {}", path.display(), e))?;
+            Ok(record)
+        } else {
+            notation::parse_record(&text).map_err(|e| format!("{}, {}", path.display(), e))
+        }
+    }
+}
+
+fn is_json(path : &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameRecord, MAX_SIDE};
+    use crate::model::game_model::{Action, Coord, ExpectedAction, Game};
+
+    /// Plays a game of 2x2 cards for Anna and Ben: Anna misses, then Ben and Anna find a pair each.
+    fn recorded_game() -> GameRecord {
+        let mut record = GameRecord::new(3, 2, 2, vec!["Anna".to_string(), "Ben".to_string()]);
+        let mut game = Game::new(2, 2);
+        game.add_player("Anna".to_string());
+        game.add_player("Ben".to_string());
+        game.set_seed(3);
+        let mut play = |game : &mut Game, action : Action| {
+            game.apply(action.clone()).unwrap();
+            record.actions.push(action);
+        };
+        play(&mut game, Action::Reset);
+        let first = game.card_at(0, 0).unwrap().card_type;
+        let all = [Coord(0, 0), Coord(0, 1), Coord(1, 0), Coord(1, 1)];
+        let pair_of_first = all.iter().skip(1).find(|c| game.card_at(c.0, c.1).unwrap().card_type == first).unwrap().clone();
+        let other : Vec<Coord> = all.iter().skip(1).filter(|c| **c != pair_of_first).cloned().collect();
+        for action in [Action::Flip(Coord(0, 0)), Action::Flip(other[0].clone()), Action::Confirm, Action::Confirm,
+                       Action::Flip(Coord(0, 0)), Action::Flip(pair_of_first), Action::Confirm, Action::Confirm,
+                       Action::Flip(other[0].clone()), Action::Flip(other[1].clone()), Action::Confirm, Action::Confirm] {
+            play(&mut game, action);
+        }
+        assert_eq!(game.expected_action(), ExpectedAction::NewGame);
+        record
+    }
+
+    #[test]
+    fn test_replay() {
+        let record = recorded_game();
+        let game = record.replay(3).unwrap();
+        assert_eq!(game.expected_action(), ExpectedAction::CheckResult);
+        assert_eq!(record.replay(record.actions.len()).unwrap().expected_action(), ExpectedAction::NewGame);
+
+        let turns = record.turns().unwrap();
+        let summary : Vec<(usize, &str, usize, Option<bool>)> = turns.iter()
+            .map(|t| (t.start, t.player.as_str(), t.flips.len(), t.pair))
+            .collect();
+        assert_eq!(summary, vec![(1, "Anna", 2, Some(false)), (5, "Ben", 2, Some(true)), (9, "Anna", 2, Some(true))]);
+        let numbers : Vec<(usize, usize)> = turns.iter().map(|t| (t.game, t.number)).collect();
+        assert_eq!(numbers, vec![(0, 1), (0, 2), (0, 3)]);
+
+        let mut broken = record.clone();
+        broken.actions.insert(1, Action::Confirm);
+        assert!(broken.replay(2).err().unwrap().contains("Action 2"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let record = recorded_game();
+        let path = std::env::temp_dir().join(format!("memory_record_{}.json", std::process::id()));
+        record.save(&path).unwrap();
+        assert_eq!(GameRecord::load(&path).unwrap(), record);
+        std::fs::write(&path, "nonsense").unwrap();
+        assert!(GameRecord::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let path = path.with_extension("game");
+        record.save(&path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("\n1. Anna "));
+        assert_eq!(GameRecord::load(&path).unwrap(), record);
+        std::fs::write(&path, "seed: 3\nsize: 2x2\nplayers: Anna\n\n1. Ben a1\n").unwrap();
+        assert!(GameRecord::load(&path).err().unwrap().ends_with(", line 5: Anna has the turn, not Ben"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_broken_sizes() {
+        let path = std::env::temp_dir().join(format!("memory_broken_{}.json", std::process::id()));
+        for (rows, cols) in [(3, 3), (0, 2), (MAX_SIDE + 1, 2), (usize::MAX, 2)] {
+            let mut record = recorded_game();
+            (record.rows, record.cols) = (rows, cols);
+            assert!(record.replay(0).is_err());
+            assert!(record.turns().is_err());
+            std::fs::write(&path, serde_json::to_string(&record).unwrap()).unwrap();
+            assert!(GameRecord::load(&path).err().unwrap().contains("rows and columns"), "{}x{}", rows, cols);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::game_model::{CardId, CardKind, Deck};
+
+/// How the turns of a game are scored. Every pair is worth a point, the rules other than
+/// classic add penalties or bonuses on top. In JSON the rules are their name, e.g. "streak-bonus".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rules {
+    /// a point per pair, nothing else
+    #[default]
+    Classic,
+    /// a point less for every turn without a pair
+    MismatchPenalty,
+    /// a point less for a turn without a pair in which a card was turned over that had been seen
+    /// before together with its partner, i.e. the pair could have been taken
+    KnownCardPenalty,
+    /// a pair in the turn after one of the player's own pairs is worth a point more for every
+    /// pair of the streak before it
+    StreakBonus,
+}
+
+impl Rules {
+    pub const ALL : [Rules; 4] = [Rules::Classic, Rules::MismatchPenalty, Rules::KnownCardPenalty, Rules::StreakBonus];
+
+    /// The name used on the command line and in records, e.g. "mismatch-penalty"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rules::Classic => { "classic" },
+            Rules::MismatchPenalty => { "mismatch-penalty" },
+            Rules::KnownCardPenalty => { "known-card-penalty" },
+            Rules::StreakBonus => { "streak-bonus" },
+        }
+    }
+
+    pub fn from_name(name : &str) -> Result<Rules, String> {
+        Rules::ALL.iter().copied().find(|rules| rules.name() == name).ok_or_else(|| {
+            let names : Vec<&str> = Rules::ALL.iter().map(|rules| rules.name()).collect();
+            format!("Unknown rules \"{}\", choose one of {}", name, names.join(", "))
+        })
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A turn of the current game as far as the scores are concerned
+#[derive(Clone, Debug, PartialEq)]
+pub struct TurnResult {
+    pub player : usize,
+    /// the cards turned over, in order
+    pub cards : Vec<CardId>,
+    /// whether the cards were a pair, None if the turn was skipped before the check
+    pub pair : Option<bool>,
+}
+
+/// Computes the scores of all players from the turns of the game.
+pub fn scores(rules : Rules, deck : &Deck, history : &[TurnResult], num_players : usize) -> Vec<i32> {
+    let mut scores = vec![0; num_players];
+    // how many pairs each player found in their last turns without a miss in between
+    let mut streaks = vec![0; num_players];
+    // the cards on the field that have been face up before the current turn
+    let mut seen : HashSet<CardId> = HashSet::new();
+    for turn in history {
+        let Some(score) = scores.get_mut(turn.player) else { continue };
+        match turn.pair {
+            Some(true) => {
+                *score += 1;
+                if rules == Rules::StreakBonus {
+                    *score += streaks[turn.player];
+                }
+                streaks[turn.player] += 1;
+                for card in &turn.cards {
+                    seen.remove(card);
+                }
+                // nobody knows where the cards are after a shuffle
+                if turn.cards.iter().any(|card| deck[*card].kind == CardKind::Shuffle) {
+                    seen.clear();
+                }
+                continue;
+            },
+            Some(false) => {
+                let known = turn.cards.iter().any(|card| seen.contains(card) && has_partner(deck, &seen, *card));
+                if rules == Rules::MismatchPenalty || (rules == Rules::KnownCardPenalty && known) {
+                    *score -= 1;
+                }
+                streaks[turn.player] = 0;
+            },
+            None => { streaks[turn.player] = 0; },
+        }
+        seen.extend(turn.cards.iter().copied());
+    }
+    scores
+}
+
+/// Whether another one of the cards matches the card
+fn has_partner(deck : &Deck, cards : &HashSet<CardId>, card : CardId) -> bool {
+    cards.iter().any(|other| *other != card && deck[*other].matches(&deck[card]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scores, Rules, TurnResult};
+    use crate::model::game_model::{create_deck, create_special_deck, Specials};
+
+    fn turn(player : usize, cards : &[usize], pair : Option<bool>) -> TurnResult {
+        TurnResult { player, cards : cards.to_vec(), pair }
+    }
+
+    #[test]
+    fn test_scores() {
+        // cards 2i and 2i+1 are a pair
+        let deck = create_deck(4);
+        let history = [
+            turn(0, &[0, 2], Some(false)),
+            turn(1, &[1, 4], Some(false)),
+            // both cards of 0 have been seen, Anna turns it over and misses the pair
+            turn(0, &[0, 5], Some(false)),
+            turn(1, &[4, 5], Some(true)),
+            turn(0, &[6, 7], Some(true)),
+            turn(1, &[3], None),
+            turn(0, &[0, 1], Some(true)),
+            turn(1, &[2, 3], Some(true)),
+        ];
+        assert_eq!(scores(Rules::Classic, &deck, &history, 2), vec![2, 2]);
+        assert_eq!(scores(Rules::MismatchPenalty, &deck, &history, 2), vec![0, 1]);
+        assert_eq!(scores(Rules::KnownCardPenalty, &deck, &history, 2), vec![1, 2]);
+        // the skipped turn ends the streak of Ben
+        assert_eq!(scores(Rules::StreakBonus, &deck, &history, 2), vec![3, 2]);
+        assert_eq!(scores(Rules::StreakBonus, &deck, &[], 3), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_special_cards() {
+        let history = [
+            turn(0, &[0, 2], Some(false)),
+            turn(1, &[1, 4], Some(false)),
+            turn(0, &[6, 7], Some(true)),
+            turn(1, &[0, 3], Some(false)),
+        ];
+        // Ben turns over 0 although both cards of 0 have been seen
+        assert_eq!(scores(Rules::KnownCardPenalty, &create_deck(4), &history, 2), vec![1, -1]);
+        // but not if Anna's pair of shuffle cards has rearranged them
+        let deck = create_special_deck(8, &Specials { jokers : 0, shuffles : 2 });
+        assert_eq!(scores(Rules::KnownCardPenalty, &deck, &history, 2), vec![1, 0]);
+
+        // a seen joker matches any card
+        let deck = create_special_deck(8, &Specials { jokers : 2, shuffles : 0 });
+        let history = [turn(0, &[0, 6], Some(true)), turn(1, &[2, 7], Some(true)), turn(0, &[3, 4], Some(false)), turn(1, &[7, 5], Some(false))];
+        assert_eq!(scores(Rules::KnownCardPenalty, &deck, &history, 2), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_names() {
+        for rules in Rules::ALL {
+            assert_eq!(Rules::from_name(rules.name()), Ok(rules));
+        }
+        assert_eq!(serde_json::to_string(&Rules::KnownCardPenalty).unwrap(), "\"known-card-penalty\"");
+        assert!(Rules::from_name("fair").unwrap_err().contains("classic, mismatch-penalty"));
+    }
+}
@@ -0,0 +1,217 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::protocol::{ClientMessage, RoomInfo, ServerMessage, DEFAULT_PORT};
+use crate::model::game_model::{CellView, PublicView};
+
+/// A connection to a game server
+pub struct Connection {
+    reader : BufReader<TcpStream>,
+    writer : TcpStream,
+}
+
+impl Connection {
+    pub fn connect<A : ToSocketAddrs>(addr : A) -> Result<Connection, String> {
+        let stream = TcpStream::connect(addr).map_err(|e| format!("Can't connect to the server: {}", e))?;
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+        Ok(Connection {
+            reader : BufReader::new(stream),
+            writer,
+        })
+    }
+
+    /// Makes receive() fail if nothing arrives for this long. None waits forever.
+    pub fn set_timeout(&mut self, timeout : Option<Duration>) -> Result<(), String> {
+        self.reader.get_ref().set_read_timeout(timeout).map_err(|e| e.to_string())
+    }
+
+    pub fn send(&mut self, message : &ClientMessage) -> Result<(), String> {
+        write_message(&mut self.writer, message)
+    }
+
+    /// Waits for the next message of the server.
+    pub fn receive(&mut self) -> Result<ServerMessage, String> {
+        read_message(&mut self.reader)
+    }
+
+    /// Receives the messages of the server on a thread of its own, so a window can be drawn while
+    /// waiting. The channel ends with an error once the connection is lost.
+    pub fn split(mut self) -> (MessageSender, Receiver<Result<ServerMessage, String>>) {
+        let _ = self.set_timeout(None);
+        let (tx, rx) = mpsc::channel();
+        let mut reader = self.reader;
+        thread::spawn(move || loop {
+            let message = read_message(&mut reader);
+            let lost = message.is_err();
+            if tx.send(message).is_err() || lost {
+                return;
+            }
+        });
+        (MessageSender { writer : self.writer }, rx)
+    }
+}
+
+/// How long to wait for the welcome after asking to resume
+const RESUME_TIMEOUT : Duration = Duration::from_secs(5);
+/// How long to wait before connecting again after a failed try
+const RESUME_RETRY : Duration = Duration::from_secs(1);
+
+/// Takes the seats back after the connection dropped: connects again and sends the token from
+/// the welcome, again and again until the server lets us in or patience runs out. Returns the
+/// new connection and the seats once the welcome has arrived; the state of the room follows.
+/// Fails right away if the server refuses the token, e.g. because the seats were given up.
+pub fn resume(server : &str, token : &str, patience : Duration) -> Result<(Connection, Vec<usize>), String> {
+    let deadline = Instant::now() + patience;
+    loop {
+        let error = match Connection::connect(server) {
+            Ok(mut connection) => {
+                let sent = connection.set_timeout(Some(RESUME_TIMEOUT))
+                    .and_then(|_| connection.send(&ClientMessage::Resume { token : token.to_string() }));
+                match sent.and_then(|_| wait_for_welcome(&mut connection)) {
+                    Ok(Ok(seats)) => { return Ok((connection, seats)); },
+                    Ok(Err(refused)) => { return Err(refused); },
+                    Err(e) => { e },
+                }
+            },
+            Err(e) => { e },
+        };
+        if Instant::now() + RESUME_RETRY > deadline {
+            return Err(format!("{}, giving up", error));
+        }
+        thread::sleep(RESUME_RETRY);
+    }
+}
+
+/// Reads up to the welcome and returns its seats, or the error the server answered with
+fn wait_for_welcome(connection : &mut Connection) -> Result<Result<Vec<usize>, String>, String> {
+    loop {
+        match connection.receive()? {
+            ServerMessage::Welcome { seats, .. } => { return Ok(Ok(seats)); },
+            ServerMessage::Error { message } => { return Ok(Err(message)); },
+            ServerMessage::State { .. } | ServerMessage::Rooms { .. } => {},
+        }
+    }
+}
+
+/// The sending half of a split connection
+pub struct MessageSender {
+    writer : TcpStream,
+}
+
+impl MessageSender {
+    pub fn send(&mut self, message : &ClientMessage) -> Result<(), String> {
+        write_message(&mut self.writer, message)
+    }
+}
+
+fn read_message(reader : &mut BufReader<TcpStream>) -> Result<ServerMessage, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => { Err("The server closed the connection".to_string()) },
+        Ok(_) => { serde_json::from_str(&line).map_err(|e| format!("The server sent \"{}\": {}", line.trim(), e)) },
+        Err(e) => { Err(format!("Connection lost: {}", e)) },
+    }
+}
+
+fn write_message(writer : &mut TcpStream, message : &ClientMessage) -> Result<(), String> {
+    let line = serde_json::to_string(message).expect("messages can be serialized");
+    writeln!(writer, "{}", line).map_err(|e| format!("Connection lost: {}", e))
+}
+
+/// Shows the state of a room as text: the field with "##" for cards face down and the title
+/// for open ones, then the players and spectators.
+pub fn format_state(room : &RoomInfo, view : &PublicView, ready : &[bool], away : &[bool], spectators : &[String]) -> String {
+    let mut text = format!("{} ({})\n", room.name, room.code);
+    if room.started {
+        for row in &view.cells {
+            let cells : Vec<String> = row.iter().map(|cell| match cell {
+                CellView::FaceDown => { "##".to_string() },
+                CellView::FaceUp { title, .. } => { format!("{:>2}", title) },
+                CellView::Removed => { "  ".to_string() },
+            }).collect();
+            text.push_str(&cells.join(" "));
+            text.push('\n');
+        }
+        text.push_str(&format!("Round {}\n", view.round));
+    } else {
+        text.push_str("Waiting for the game to start\n");
+    }
+    for (i, player) in view.players.iter().enumerate() {
+        let turn = if room.started && i == view.current_player_id { " <-" } else { "" };
+        let host = if room.host.as_ref() == Some(&player.name) { " (host)" } else { "" };
+        let ready = if ready.get(i) == Some(&true) { " ready" } else { "" };
+        let away = if away.get(i) == Some(&true) { " (away)" } else { "" };
+        text.push_str(&format!("{}{}: {} pairs, {} points{}{}{}\n", player.name, host, player.pairs.len(), player.score, ready, away, turn));
+    }
+    if !spectators.is_empty() {
+        text.push_str(&format!("Watching: {}\n", spectators.join(", ")));
+    }
+    text
+}
+
+/// Shows the rooms of a lobby as text, one per line.
+pub fn format_rooms(rooms : &[RoomInfo]) -> String {
+    if rooms.is_empty() {
+        return "No open rooms\n".to_string();
+    }
+    let mut text = String::new();
+    for room in rooms {
+        let s = &room.settings;
+        let status = if room.started { "playing" } else { "waiting" };
+        text.push_str(&format!("{} {}  {}x{}  {}/{} players  {}: {}\n",
+            room.code, room.name, s.rows, s.cols, room.players.len(), s.max_players, status, room.players.join(", ")));
+    }
+    text
+}
+
+/// Runs the "rooms" command, which lists the rooms of a server:
+///
+/// memory_game rooms [--server=host:port]
+pub fn rooms(args : Vec<String>) -> Result<(), String> {
+    let mut server = format!("localhost:{}", DEFAULT_PORT);
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--server", value)) => { server = value.to_string(); },
+            _ => { return Err(format!("Unknown option {}", arg)); },
+        }
+    }
+    let mut connection = Connection::connect(server.as_str())?;
+    connection.send(&ClientMessage::ListRooms)?;
+    match connection.receive()? {
+        ServerMessage::Rooms { rooms } => { print!("{}", format_rooms(&rooms)); },
+        other => { return Err(format!("The server answered {:?}", other)); },
+    }
+    Ok(())
+}
+
+/// Runs the "watch" command: enters a room as a spectator and prints the game after every change.
+///
+/// memory_game watch room [--server=host:port] [--name=name]
+pub fn watch(args : Vec<String>) -> Result<(), String> {
+    let mut server = format!("localhost:{}", DEFAULT_PORT);
+    let mut name = "Spectator".to_string();
+    let mut room = None;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--server", value)) => { server = value.to_string(); },
+            Some(("--name", value)) => { name = value.to_string(); },
+            None if !arg.starts_with("--") => { room = Some(arg); },
+            _ => { return Err(format!("Unknown option {}", arg)); },
+        }
+    }
+    let room = room.ok_or("Which room? Give its name or code, \"memory_game rooms\" lists them")?;
+    let mut connection = Connection::connect(server.as_str())?;
+    connection.send(&ClientMessage::Watch { name, room })?;
+    loop {
+        match connection.receive()? {
+            ServerMessage::State { room, view, ready, away, spectators } => {
+                println!("{}", format_state(&room, &view, &ready, &away, &spectators));
+            },
+            ServerMessage::Error { message } => { return Err(message); },
+            ServerMessage::Welcome { .. } | ServerMessage::Rooms { .. } => {},
+        }
+    }
+}
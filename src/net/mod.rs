@@ -0,0 +1,8 @@
+//! Playing over the network. A server hosts a lobby of rooms, each with its own game. Clients
+//! connect over TCP and exchange JSON messages, one per line (see protocol). Clients only ever get
+//! the public view of a game, so nobody can learn face down cards from the network traffic.
+
+pub mod client;
+pub mod protocol;
+mod room;
+pub mod server;
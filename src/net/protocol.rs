@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::game_model::{PublicView, Specials};
+use crate::model::scoring::Rules;
+
+/// The port the server listens on if none is given
+pub const DEFAULT_PORT : u16 = 7878;
+
+/// The longest line a client may send, in bytes. The server hangs up on clients that send
+/// longer ones.
+pub const MAX_LINE : usize = 64 * 1024;
+
+/// What happens to the turns of a player whose connection dropped, while the seat is kept for
+/// them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Absence {
+    /// the game waits for the player to come back
+    Pause,
+    /// the turns of the player are skipped
+    Skip,
+}
+
+/// The settings of a room, chosen by whoever creates it. Fields left out of the JSON get the
+/// default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomSettings {
+    pub rows : usize,
+    pub cols : usize,
+    pub max_players : usize,
+    /// whether others may watch the game
+    pub spectators : bool,
+    /// how long the seat of a player whose connection dropped during a game is kept, in seconds.
+    /// After that the player has left and their turns are skipped.
+    pub reconnect_seconds : u64,
+    pub absence : Absence,
+    /// how the turns are scored
+    pub rules : Rules,
+    /// the special cards in the deck
+    pub specials : Specials,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        RoomSettings {
+            rows : 6,
+            cols : 6,
+            max_players : 4,
+            spectators : true,
+            reconnect_seconds : 60,
+            absence : Absence::Pause,
+            rules : Rules::Classic,
+            specials : Specials::default(),
+        }
+    }
+}
+
+/// What the lobby tells about a room
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub name : String,
+    /// a short code to join the room with, e.g. "KQXT"
+    pub code : String,
+    pub settings : RoomSettings,
+    pub players : Vec<String>,
+    /// the player who may start the game
+    pub host : Option<String>,
+    pub started : bool,
+}
+
+/// Messages from a client to the server. In JSON the kind of message is the field "type", e.g.
+/// {"type":"flip","row":2,"col":5}.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// ask for the rooms of the lobby, possible at any time
+    ListRooms,
+    /// open a new room and take the first seats in it as host, one for each player at this
+    /// computer
+    CreateRoom {
+        players : Vec<String>,
+        room : String,
+        #[serde(default)]
+        settings : RoomSettings,
+    },
+    /// take a seat in a room for each player at this computer. The room is given by its name or
+    /// code. Only possible before the game has started.
+    Join { players : Vec<String>, room : String },
+    /// watch the game of a room without playing, possible at any time
+    Watch { name : String, room : String },
+    /// tell the host whether the players of this client are ready to play
+    Ready { ready : bool },
+    /// start a new game, only the host can do this once all players are ready
+    Start,
+    /// flip a card, only the client of the player whose turn it is
+    Flip { row : usize, col : usize },
+    /// check the open cards or pass the turn on, only the client of the player whose turn it is
+    Confirm,
+    /// take the seats back after the connection dropped, with the token from the welcome
+    Resume { token : String },
+    /// go back to the lobby. Leaving a running game gives the seats up for good.
+    Leave,
+}
+
+/// Messages from the server to a client
+// they are sent right away, so the size of the state doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// the rooms of the lobby, the answer to list_rooms and leave
+    Rooms { rooms : Vec<RoomInfo> },
+    /// the answer to create_room, join, watch or resume. seats are the indices of the players of
+    /// the client in view.players, in the order they were given, and empty for spectators.
+    /// Players get a token to resume with if their connection drops.
+    Welcome { room : String, code : String, seats : Vec<usize>, token : Option<String> },
+    /// the whole visible state of the room, sent after joining and after every change. ready and
+    /// away have an entry for every player; away players have lost their connection or left.
+    State { room : RoomInfo, view : PublicView, ready : Vec<bool>, away : Vec<bool>, spectators : Vec<String> },
+    /// the last message of the client was refused
+    Error { message : String },
+}
@@ -0,0 +1,382 @@
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::distributions::{Alphanumeric, DistString};
+
+use super::protocol::{Absence, ClientMessage, RoomInfo, RoomSettings, ServerMessage};
+use crate::model::game_model::{Action, Coord, ExpectedAction, Game};
+
+/// The length of the tokens players resume with
+const TOKEN_LENGTH : usize = 16;
+
+/// The messages on their way to a client. A thread of its own writes them to the connection, so
+/// sending only queues them and never waits for a slow client while the lobby is locked. If
+/// the client can't be written to, the thread closes the connection, so the client leaves the
+/// room like any client that disconnects.
+#[derive(Clone)]
+pub struct Outbox {
+    /// the lines to write, None closes the connection
+    queue : Sender<Option<String>>,
+    stream : Arc<TcpStream>,
+}
+
+impl Outbox {
+    pub fn new(stream : &TcpStream) -> Result<Outbox, String> {
+        let failed = |e : std::io::Error| format!("Connection failed: {}", e);
+        let mut writer = stream.try_clone().map_err(failed)?;
+        let (queue, lines) = mpsc::channel::<Option<String>>();
+        // ends once the connection is closed or all outboxes of the client are gone
+        thread::spawn(move || {
+            for line in lines {
+                if line.is_none_or(|line| writeln!(writer, "{}", line).is_err()) {
+                    let _ = writer.shutdown(Shutdown::Both);
+                    return;
+                }
+            }
+        });
+        Ok(Outbox { queue, stream : Arc::new(stream.try_clone().map_err(failed)?) })
+    }
+
+    pub fn send(&self, message : &ServerMessage) {
+        self.send_line(to_line(message));
+    }
+
+    fn send_line(&self, line : String) {
+        // the writer is gone once the connection failed, the reader notices that as well
+        let _ = self.queue.send(Some(line));
+    }
+
+    /// Sends the message and closes the connection once it has been written.
+    pub fn send_last(&self, message : &ServerMessage) {
+        self.send(message);
+        let _ = self.queue.send(None);
+    }
+
+    /// Closes the connection, the messages still waiting are lost.
+    pub fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+fn to_line(message : &ServerMessage) -> String {
+    serde_json::to_string(message).expect("messages can be serialized")
+}
+
+/// A connection in a room: the players at one computer, or a spectator
+struct Client {
+    id : usize,
+    /// the name of the spectator or the first player
+    name : String,
+    /// the indices of the players this client plays for, empty for spectators
+    seats : Vec<usize>,
+    ready : bool,
+    /// the secret the players resume with
+    token : String,
+    /// when the connection of the player dropped, None while connected
+    dropped : Option<Instant>,
+    out : Outbox,
+}
+
+/// A game and everybody who plays or watches it
+pub struct Room {
+    pub name : String,
+    pub code : String,
+    settings : RoomSettings,
+    game : Game,
+    started : bool,
+    clients : Vec<Client>,
+    /// the id of the client who may start the game
+    host : Option<usize>,
+}
+
+impl Room {
+    /// Opens a room with the settings, which the server has checked. Fails if the special cards
+    /// don't fit the field after all.
+    pub fn new(name : String, code : String, settings : RoomSettings) -> Result<Self, String> {
+        let mut game = Game::new(settings.rows, settings.cols);
+        game.set_rules(settings.rules);
+        game.set_specials(settings.specials)?;
+        Ok(Room {
+            name,
+            code,
+            game,
+            settings,
+            started : false,
+            clients : Vec::new(),
+            host : None,
+        })
+    }
+
+    /// Whether the name or code given by a client means this room. Codes may be typed in lower case.
+    pub fn is_called(&self, name_or_code : &str) -> bool {
+        self.name == name_or_code || self.code.eq_ignore_ascii_case(name_or_code)
+    }
+
+    /// True once everybody has left. Players whose connection dropped keep the room open.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    pub fn has_token(&self, token : &str) -> bool {
+        self.clients.iter().any(|c| !c.seats.is_empty() && c.token == token)
+    }
+
+    fn players(&self) -> impl Iterator<Item = &Client> {
+        self.clients.iter().filter(|c| !c.seats.is_empty())
+    }
+
+    /// Whether the turns of the player at the seat are played by the room: nobody sits there any
+    /// more, or the connection dropped and the room skips absent players.
+    fn is_absent(&self, seat : usize) -> bool {
+        match self.clients.iter().find(|c| c.seats.contains(&seat)) {
+            Some(client) => { client.dropped.is_some() && self.settings.absence == Absence::Skip },
+            None => { true },
+        }
+    }
+
+    pub fn info(&self) -> RoomInfo {
+        let mut seats : Vec<usize> = self.players().flat_map(|c| c.seats.iter().copied()).collect();
+        seats.sort();
+        RoomInfo {
+            name : self.name.clone(),
+            code : self.code.clone(),
+            settings : self.settings.clone(),
+            players : seats.iter().filter_map(|s| self.game.players.get(*s)).map(|p| p.name.clone()).collect(),
+            host : self.clients.iter().find(|c| Some(c.id) == self.host).map(|c| c.name.clone()),
+            started : self.started,
+        }
+    }
+
+    /// The state as everybody sees it. Only the public view is sent, face down cards stay secret.
+    fn state(&self) -> ServerMessage {
+        let mut ready = vec![false; self.game.players.len()];
+        let mut away = vec![true; self.game.players.len()];
+        for client in self.players() {
+            for seat in &client.seats {
+                ready[*seat] = client.ready;
+                away[*seat] = client.dropped.is_some();
+            }
+        }
+        ServerMessage::State {
+            room : self.info(),
+            view : self.game.public_view(),
+            ready,
+            away,
+            spectators : self.clients.iter().filter(|c| c.seats.is_empty()).map(|c| c.name.clone()).collect(),
+        }
+    }
+
+    /// Sends the welcome to a client that has just come in.
+    pub fn welcome(&self, id : usize) {
+        if let Some(client) = self.clients.iter().find(|c| c.id == id) {
+            client.out.send(&ServerMessage::Welcome {
+                room : self.name.clone(),
+                code : self.code.clone(),
+                seats : client.seats.clone(),
+                token : Some(client.token.clone()).filter(|_| !client.seats.is_empty()),
+            });
+        }
+    }
+
+    /// Queues the state for everybody.
+    pub fn broadcast(&self) {
+        let state = to_line(&self.state());
+        for client in self.clients.iter().filter(|c| c.dropped.is_none()) {
+            client.out.send_line(state.clone());
+        }
+    }
+
+    /// Whether the name is taken by a player or spectator of the room
+    fn is_taken(&self, name : &str) -> bool {
+        self.game.players.iter().any(|p| p.name == name) || self.clients.iter().any(|c| c.name == name)
+    }
+
+    /// Gives a client a seat for each of its players, the first client becomes host.
+    pub fn join(&mut self, id : usize, names : Vec<String>, out : Outbox) -> Result<(), String> {
+        if self.started {
+            return Err("The game has already started, you can only watch it".to_string());
+        }
+        if names.is_empty() {
+            return Err("Join with at least one player".to_string());
+        }
+        for (i, name) in names.iter().enumerate() {
+            if self.is_taken(name) || names[..i].contains(name) {
+                return Err(format!("There is already somebody called {} in the room", name));
+            }
+        }
+        if self.game.players.len() + names.len() > self.settings.max_players {
+            return Err(format!("The room {} is full", self.name));
+        }
+        let first = self.game.players.len();
+        for name in &names {
+            self.game.add_player(name.clone());
+        }
+        let token = Alphanumeric.sample_string(&mut rand::thread_rng(), TOKEN_LENGTH);
+        let name = names[0].clone();
+        self.clients.push(Client { id, name, seats : (first..first + names.len()).collect(), ready : false, token, dropped : None, out });
+        if self.host.is_none() {
+            self.host = Some(id);
+        }
+        Ok(())
+    }
+
+    pub fn watch(&mut self, id : usize, name : String, out : Outbox) -> Result<(), String> {
+        if !self.settings.spectators {
+            return Err(format!("The room {} doesn't allow spectators", self.name));
+        }
+        if self.is_taken(&name) {
+            return Err(format!("There is already somebody called {} in the room", name));
+        }
+        self.clients.push(Client { id, name, seats : Vec::new(), ready : false, token : String::new(), dropped : None, out });
+        Ok(())
+    }
+
+    /// Gives the seats with the token to a new connection. If the old connection is still open,
+    /// it is closed.
+    pub fn resume(&mut self, token : &str, id : usize, out : Outbox) -> Result<(), String> {
+        let client = self.clients.iter_mut().find(|c| !c.seats.is_empty() && c.token == token)
+            .ok_or("Unknown token")?;
+        if client.dropped.is_none() {
+            client.out.close();
+        }
+        if self.host == Some(client.id) {
+            self.host = Some(id);
+        }
+        client.id = id;
+        client.out = out;
+        client.dropped = None;
+        self.skip_absent_turns();
+        Ok(())
+    }
+
+    /// Carries out the message of a client in the room.
+    pub fn handle(&mut self, id : usize, message : ClientMessage) -> Result<(), String> {
+        let Some(client) = self.clients.iter_mut().find(|c| c.id == id) else { return Err("Unknown client".to_string()) };
+        if client.seats.is_empty() {
+            return Err("Spectators can't play".to_string());
+        }
+        let has_turn = client.seats.contains(&self.game.current_player_id);
+        let running = self.started && self.game.expected_action() != ExpectedAction::NewGame;
+        let action = match message {
+            ClientMessage::Ready { ready } => {
+                client.ready = ready;
+                return Ok(());
+            },
+            ClientMessage::Start => {
+                if self.host != Some(id) {
+                    return Err("Only the host can start the game".to_string());
+                }
+                if running {
+                    return Err("The game is still running".to_string());
+                }
+                let waiting : Vec<&str> = self.players().filter(|c| c.id != id && !c.ready).map(|c| c.name.as_str()).collect();
+                if !waiting.is_empty() {
+                    return Err(format!("Waiting for {} to be ready", waiting.join(", ")));
+                }
+                // players who left the last game don't play the next one
+                for seat in (0..self.game.players.len()).rev() {
+                    if !self.clients.iter().any(|c| c.seats.contains(&seat)) {
+                        self.free_seat(seat);
+                    }
+                }
+                Action::Reset
+            },
+            ClientMessage::Flip { .. } | ClientMessage::Confirm if !running => {
+                return Err("The game isn't running".to_string());
+            },
+            ClientMessage::Flip { .. } | ClientMessage::Confirm if !has_turn => {
+                return Err("It's not your turn".to_string());
+            },
+            ClientMessage::Flip { row, col } => { Action::Flip(Coord(row, col)) },
+            ClientMessage::Confirm => { Action::Confirm },
+            _ => { return Err("You are already in a room, leave it first".to_string()); },
+        };
+        self.game.apply(action.clone()).map_err(|e| e.to_string())?;
+        if action == Action::Reset {
+            // everybody says again whether they want a rematch
+            self.started = true;
+            for client in &mut self.clients {
+                client.ready = false;
+            }
+        }
+        self.skip_absent_turns();
+        Ok(())
+    }
+
+    /// Plays the turns of absent players: their turns are skipped, open cards are checked and
+    /// passed on. Stops when nobody is there at all.
+    fn skip_absent_turns(&mut self) {
+        while self.started && (0..self.game.players.len()).any(|seat| !self.is_absent(seat)) {
+            if !self.is_absent(self.game.current_player_id) {
+                return;
+            }
+            let action = match self.game.expected_action() {
+                ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard => { Action::SkipTurn },
+                ExpectedAction::CheckResult | ExpectedAction::PassTurn => { Action::Confirm },
+                ExpectedAction::NewGame => { return; },
+            };
+            if self.game.apply(action).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Takes a seat out of the game, the players after it move up.
+    fn free_seat(&mut self, seat : usize) {
+        self.game.players.remove(seat);
+        for client in &mut self.clients {
+            for s in client.seats.iter_mut().filter(|s| **s > seat) {
+                *s -= 1;
+            }
+        }
+    }
+
+    /// Called when the connection of a client is lost. During a game players keep their seat
+    /// for the reconnect time of the room, everybody else leaves.
+    pub fn disconnect(&mut self, id : usize) {
+        match self.clients.iter_mut().find(|c| c.id == id) {
+            Some(client) if self.started && !client.seats.is_empty() => {
+                client.dropped = Some(Instant::now());
+                self.skip_absent_turns();
+            },
+            Some(_) => { self.leave(id); },
+            None => {},
+        }
+    }
+
+    /// Lets the players whose reconnect time is over leave. Returns true if somebody left.
+    pub fn expire(&mut self, now : Instant) -> bool {
+        let grace = Duration::from_secs(self.settings.reconnect_seconds);
+        let expired : Vec<usize> = self.clients.iter()
+            .filter(|c| c.dropped.is_some_and(|dropped| now.duration_since(dropped) >= grace))
+            .map(|c| c.id)
+            .collect();
+        for id in &expired {
+            self.leave(*id);
+        }
+        !expired.is_empty()
+    }
+
+    /// Removes a client. Before the game has started its seats are freed; once it runs the seats
+    /// stay so the scores keep their players, and their turns are skipped. The host passes to the
+    /// next client with players.
+    pub fn leave(&mut self, id : usize) {
+        let Some(index) = self.clients.iter().position(|c| c.id == id) else { return };
+        let client = self.clients.remove(index);
+        if !self.started {
+            for seat in client.seats.iter().rev() {
+                self.free_seat(*seat);
+            }
+        }
+        if self.host == Some(id) {
+            self.host = self.players().filter(|c| c.dropped.is_none()).min_by_key(|c| c.seats[0])
+                .or_else(|| self.players().min_by_key(|c| c.seats[0]))
+                .map(|c| c.id);
+        }
+        self.skip_absent_turns();
+    }
+}
@@ -0,0 +1,302 @@
+use std::io::{BufRead, BufReader, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::protocol::{ClientMessage, RoomSettings, ServerMessage, DEFAULT_PORT, MAX_LINE};
+use super::room::{Outbox, Room};
+
+/// A client that doesn't take a message for this long is dropped, so it can't block the others
+const WRITE_TIMEOUT : Duration = Duration::from_secs(5);
+
+/// How often the server looks for players whose reconnect time is over
+const TICK : Duration = Duration::from_millis(100);
+
+/// The most rows or columns a room may have
+const MAX_SIDE : usize = 16;
+
+/// The letters of room codes, without I and O which look like digits
+const CODE_LETTERS : &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const CODE_LENGTH : usize = 4;
+
+/// Checks the settings of a new room.
+fn check_settings(settings : &RoomSettings) -> Result<(), String> {
+    let RoomSettings { rows, cols, max_players, .. } = *settings;
+    if rows == 0 || cols == 0 || rows > MAX_SIDE || cols > MAX_SIDE {
+        return Err(format!("A field needs 1 to {} rows and columns", MAX_SIDE));
+    }
+    if !(rows * cols).is_multiple_of(2) {
+        return Err(format!("A field of {}x{} has an odd number of cards", rows, cols));
+    }
+    if max_players == 0 {
+        return Err("A room needs at least one seat".to_string());
+    }
+    settings.specials.check(rows * cols)
+}
+
+/// All rooms of a server
+#[derive(Default)]
+struct Lobby {
+    rooms : Vec<Room>,
+    next_client : usize,
+}
+
+impl Lobby {
+    fn new_client(&mut self) -> usize {
+        self.next_client += 1;
+        self.next_client
+    }
+
+    fn rooms(&self) -> ServerMessage {
+        ServerMessage::Rooms { rooms : self.rooms.iter().map(|r| r.info()).collect() }
+    }
+
+    fn room(&mut self, name_or_code : &str) -> Result<&mut Room, String> {
+        self.rooms.iter_mut().find(|r| r.is_called(name_or_code))
+            .ok_or_else(|| format!("There is no room {}", name_or_code))
+    }
+
+    /// A code no other room has
+    fn new_code(&self) -> String {
+        let mut rng = rand::thread_rng();
+        loop {
+            let code : String = (0..CODE_LENGTH)
+                .map(|_| CODE_LETTERS[rng.gen_range(0..CODE_LETTERS.len())] as char)
+                .collect();
+            if !self.rooms.iter().any(|r| r.is_called(&code)) {
+                return code;
+            }
+        }
+    }
+
+    /// Carries out a message of client id. room is the code of the room the client is in and is
+    /// updated when the client enters or leaves one.
+    fn handle(&mut self, id : usize, room : &mut Option<String>, message : ClientMessage, out : &Outbox) -> Result<(), String> {
+        match (message, room.clone()) {
+            (ClientMessage::ListRooms, _) => {
+                out.send(&self.rooms());
+            },
+            (ClientMessage::CreateRoom { players, room : room_name, settings }, None) => {
+                check_settings(&settings)?;
+                if room_name.trim().is_empty() {
+                    return Err("The room needs a name".to_string());
+                }
+                if self.rooms.iter().any(|r| r.is_called(&room_name)) {
+                    return Err(format!("There is already a room called {}", room_name));
+                }
+                let mut new_room = Room::new(room_name, self.new_code(), settings)?;
+                new_room.join(id, players, out.clone())?;
+                println!("Room {} ({}) opened", new_room.name, new_room.code);
+                let code = new_room.code.clone();
+                self.rooms.push(new_room);
+                self.entered(id, &code, room);
+            },
+            (ClientMessage::Join { players, room : wanted }, None) => {
+                let joined = self.room(&wanted)?;
+                joined.join(id, players, out.clone())?;
+                let code = joined.code.clone();
+                self.entered(id, &code, room);
+            },
+            (ClientMessage::Watch { name, room : wanted }, None) => {
+                let watched = self.room(&wanted)?;
+                watched.watch(id, name, out.clone())?;
+                let code = watched.code.clone();
+                self.entered(id, &code, room);
+            },
+            (ClientMessage::Resume { token }, None) => {
+                let resumed = self.rooms.iter_mut().find(|r| r.has_token(&token))
+                    .ok_or("There is no seat to resume, it may have been given up")?;
+                resumed.resume(&token, id, out.clone())?;
+                let code = resumed.code.clone();
+                self.entered(id, &code, room);
+            },
+            (ClientMessage::Leave, Some(code)) => {
+                self.leave(id, &code);
+                *room = None;
+                out.send(&self.rooms());
+            },
+            (message, Some(code)) => {
+                let current = self.room(&code)?;
+                current.handle(id, message)?;
+                current.broadcast();
+            },
+            (_, None) => { return Err("Join a room first".to_string()); },
+        }
+        Ok(())
+    }
+
+    /// Welcomes a client to the room it has just entered and tells everybody in there.
+    fn entered(&mut self, id : usize, code : &str, room : &mut Option<String>) {
+        *room = Some(code.to_string());
+        if let Ok(entered) = self.room(code) {
+            entered.welcome(id);
+            entered.broadcast();
+        }
+    }
+
+    /// Takes a client out of a room for good.
+    fn leave(&mut self, id : usize, code : &str) {
+        if let Ok(room) = self.room(code) {
+            room.leave(id);
+            room.broadcast();
+        }
+        self.close_empty_rooms();
+    }
+
+    /// Called when the connection of a client in a room is lost.
+    fn disconnect(&mut self, id : usize, code : &str) {
+        if let Ok(room) = self.room(code) {
+            room.disconnect(id);
+            room.broadcast();
+        }
+        self.close_empty_rooms();
+    }
+
+    /// Lets players whose reconnect time is over leave their rooms.
+    fn tick(&mut self, now : Instant) {
+        for room in &mut self.rooms {
+            if room.expire(now) {
+                room.broadcast();
+            }
+        }
+        self.close_empty_rooms();
+    }
+
+    fn close_empty_rooms(&mut self) {
+        self.rooms.retain(|room| {
+            if room.is_empty() {
+                println!("Room {} ({}) closed", room.name, room.code);
+            }
+            !room.is_empty()
+        });
+    }
+}
+
+/// A server with a lobby of rooms, each with its own game
+pub struct Server {
+    listener : TcpListener,
+    lobby : Arc<Mutex<Lobby>>,
+}
+
+impl Server {
+    /// Listens on the address. Port 0 picks a free port.
+    pub fn bind<A : ToSocketAddrs>(addr : A) -> Result<Server, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("Can't listen: {}", e))?;
+        Ok(Server {
+            listener,
+            lobby : Arc::new(Mutex::new(Lobby::default())),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr().expect("the listener is bound")
+    }
+
+    /// Accepts connections until the process ends. Every connection is served by its own thread.
+    pub fn run(self) {
+        let lobby = self.lobby.clone();
+        thread::spawn(move || loop {
+            thread::sleep(TICK);
+            lock(&lobby).tick(Instant::now());
+        });
+        for stream in self.listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let lobby = self.lobby.clone();
+                    thread::spawn(move || serve_client(stream, lobby));
+                },
+                Err(e) => { println!("Connection failed: {}", e); },
+            }
+        }
+    }
+}
+
+/// Locks the lobby. The handlers don't panic, but if one did anyway the other rooms go on with
+/// the lobby as it was left rather than all failing with it.
+fn lock(lobby : &Mutex<Lobby>) -> MutexGuard<'_, Lobby> {
+    lobby.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Reads the messages of one client until it disconnects or sends a line longer than MAX_LINE.
+fn serve_client(stream : TcpStream, lobby : Arc<Mutex<Lobby>>) {
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    let Ok(out) = Outbox::new(&stream) else { return };
+    let id = lock(&lobby).new_client();
+    // the code of the room the client is in
+    let mut room = None;
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        match (&mut reader).take(MAX_LINE as u64).read_line(&mut line) {
+            Ok(0) | Err(_) => { break; },
+            Ok(n) if n == MAX_LINE && !line.ends_with('\n') => {
+                out.send_last(&ServerMessage::Error { message : format!("Messages may be at most {} bytes long", MAX_LINE) });
+                break;
+            },
+            Ok(_) => {},
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = serde_json::from_str::<ClientMessage>(&line)
+            .map_err(|e| format!("Can't read \"{}\": {}", line.trim(), e))
+            .and_then(|message| lock(&lobby).handle(id, &mut room, message, &out));
+        if let Err(message) = result {
+            out.send(&ServerMessage::Error { message });
+        }
+    }
+    if let Some(code) = room {
+        lock(&lobby).disconnect(id, &code);
+    }
+}
+
+/// Runs the "serve" command:
+///
+/// memory_game serve [--port=N]
+pub fn run(args : Vec<String>) -> Result<(), String> {
+    let mut port = DEFAULT_PORT;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--port", value)) => { port = value.parse().map_err(|_| format!("Option {} needs a port number", arg))?; },
+            _ => { return Err(format!("Unknown option {}", arg)); },
+        }
+    }
+    let server = Server::bind(("0.0.0.0", port))?;
+    println!("Lobby open on port {}", server.local_addr().port());
+    server.run();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_settings, Lobby, CODE_LENGTH};
+    use crate::model::game_model::Specials;
+    use crate::net::protocol::RoomSettings;
+    use crate::net::room::Room;
+
+    #[test]
+    fn test_room_settings() {
+        assert!(check_settings(&RoomSettings::default()).is_ok());
+        assert!(check_settings(&RoomSettings { rows : 3, cols : 3, ..RoomSettings::default() }).is_err());
+        assert!(check_settings(&RoomSettings { rows : 40, cols : 2, ..RoomSettings::default() }).is_err());
+        assert!(check_settings(&RoomSettings { max_players : 0, ..RoomSettings::default() }).is_err());
+        assert!(check_settings(&RoomSettings { rows : 2, cols : 2, specials : Specials { jokers : 6, shuffles : 0 }, ..RoomSettings::default() }).is_err());
+    }
+
+    #[test]
+    fn test_room_codes() {
+        let mut lobby = Lobby::default();
+        for i in 0..50 {
+            let code = lobby.new_code();
+            assert_eq!(code.len(), CODE_LENGTH);
+            assert!(!lobby.rooms.iter().any(|r| r.is_called(&code)));
+            lobby.rooms.push(Room::new(i.to_string(), code, RoomSettings::default()).unwrap());
+        }
+        let code = lobby.rooms[7].code.to_lowercase();
+        assert_eq!(lobby.room(&code).unwrap().name, "7");
+        assert!(lobby.room("nope").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use memory_game::model::game_model::Specials;
+use memory_game::model::scoring::Rules;
+
+/// How long the result of a turn is shown in auto turn mode if no duration is given.
+const DEFAULT_RESULT_DELAY_MS : u64 = 1500;
+
+/// Settings of the game, taken from the command line.
+///
+/// Options start with "--", everything else is taken as the name of a player:
+///
+/// memory_game [--no-animations] [--auto-turn[=milliseconds]] [--theme=name] [--theme-dir=directory]
+///             [--font-dir=directory ...] [--lang=code] [--lang-dir=directory] [--mute]
+///             [--effects-volume=percent] [--music-volume=percent] [--sound-pack=directory]
+///             [--high-contrast] [--text-scale=factor] [--focus-outline] [--shape-markers]
+///             [--accessible] [--announce[=file]] [--record=file] [--rules=name] [--jokers=N]
+///             [--shuffle-cards=N] [player names ...]
+///
+/// --accessible switches on high contrast, focus outline and shape markers and makes the texts
+/// half as big again. --rules chooses how the turns are scored: classic, mismatch-penalty,
+/// known-card-penalty or streak-bonus. --jokers and --shuffle-cards put that many special cards
+/// into the deck instead of ordinary pairs.
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub player_names : Vec<String>,
+    pub animations : bool,
+    /// If set, turns are checked and passed on automatically after showing the result this long.
+    pub auto_turn_delay : Option<Duration>,
+    /// Name of the theme to start with
+    pub theme : Option<String>,
+    /// Directory with additional "*.theme" files
+    pub theme_dir : PathBuf,
+    /// Directories to search for fonts (before the system font directories)
+    pub font_dirs : Vec<PathBuf>,
+    /// Code of the language to use, e.g. "de". If not given it is taken from the environment.
+    pub language : Option<String>,
+    /// Directory with additional "*.lang" files
+    pub lang_dir : PathBuf,
+    /// Start with the sound switched off
+    pub muted : bool,
+    /// Volume of the sound effects in percent
+    pub effects_volume : u8,
+    /// Volume of the background music in percent
+    pub music_volume : u8,
+    /// Directory with the sound files, the built-in sounds are used if not given
+    pub sound_pack : Option<PathBuf>,
+    pub high_contrast : bool,
+    /// Factor for the size of all texts
+    pub text_scale : f32,
+    pub focus_outline : bool,
+    pub shape_markers : bool,
+    /// Where announcements for screen readers are written to, "-" is stdout
+    pub announce : Option<PathBuf>,
+    /// Where the game is recorded to, for watching it again with "memory_game replay"
+    pub record : Option<PathBuf>,
+    pub rules : Rules,
+    pub specials : Specials,
+}
+
+/// Where theme files are looked for if no directory is given
+const DEFAULT_THEME_DIR : &str = "./themes";
+/// Where language files are looked for if no directory is given
+const DEFAULT_LANG_DIR : &str = "./lang";
+const DEFAULT_EFFECTS_VOLUME : u8 = 80;
+const DEFAULT_MUSIC_VOLUME : u8 = 30;
+/// Text scale of --accessible
+const ACCESSIBLE_TEXT_SCALE : f32 = 1.5;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            player_names : Vec::new(),
+            animations : true,
+            auto_turn_delay : None,
+            theme : None,
+            theme_dir : PathBuf::from(DEFAULT_THEME_DIR),
+            font_dirs : Vec::new(),
+            language : None,
+            lang_dir : PathBuf::from(DEFAULT_LANG_DIR),
+            muted : false,
+            effects_volume : DEFAULT_EFFECTS_VOLUME,
+            music_volume : DEFAULT_MUSIC_VOLUME,
+            sound_pack : None,
+            high_contrast : false,
+            text_scale : 1.0,
+            focus_outline : false,
+            shape_markers : false,
+            announce : None,
+            record : None,
+            rules : Rules::Classic,
+            specials : Specials::default(),
+        }
+    }
+}
+
+/// Splits an option like "--auto-turn=1000" into its name and value.
+fn split_option(arg : &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((name, value)) => { (name, Some(value)) },
+        None => { (arg, None) },
+    }
+}
+
+fn parse_millis(name : &str, value : &str) -> Result<Duration, String> {
+    match value.trim().parse::<u64>() {
+        Ok(ms) => { Ok(Duration::from_millis(ms)) },
+        Err(_) => { Err(format!("Option {} needs a duration in milliseconds, got \"{}\"", name, value)) },
+    }
+}
+
+fn parse_percent(name : &str, value : &str) -> Result<u8, String> {
+    match value.trim().parse::<u8>() {
+        Ok(p) if p <= 100 => { Ok(p) },
+        _ => { Err(format!("Option {} needs a volume between 0 and 100, got \"{}\"", name, value)) },
+    }
+}
+
+fn parse_scale(name : &str, value : &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(f) if (0.5..=4.0).contains(&f) => { Ok(f) },
+        _ => { Err(format!("Option {} needs a factor between 0.5 and 4, got \"{}\"", name, value)) },
+    }
+}
+
+fn parse_count(name : &str, value : &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(n) if n.is_multiple_of(2) => { Ok(n) },
+        _ => { Err(format!("Option {} needs an even number of cards, got \"{}\"", name, value)) },
+    }
+}
+
+impl Settings {
+    /// Parses the command line arguments (without the program name).
+    pub fn from_args<I : IntoIterator<Item = String>>(args : I) -> Result<Settings, String> {
+        let mut settings = Settings::default();
+        for arg in args {
+            if !arg.starts_with("--") {
+                settings.player_names.push(arg);
+                continue;
+            }
+            match split_option(&arg) {
+                ("--no-animations", None) => { settings.animations = false; },
+                ("--auto-turn", None) => {
+                    settings.auto_turn_delay = Some(Duration::from_millis(DEFAULT_RESULT_DELAY_MS));
+                },
+                ("--auto-turn", Some(value)) => {
+                    settings.auto_turn_delay = Some(parse_millis("--auto-turn", value)?);
+                },
+                ("--theme", Some(value)) => { settings.theme = Some(value.to_string()); },
+                ("--theme-dir", Some(value)) => { settings.theme_dir = PathBuf::from(value); },
+                ("--font-dir", Some(value)) => { settings.font_dirs.push(PathBuf::from(value)); },
+                ("--lang", Some(value)) => { settings.language = Some(value.to_string()); },
+                ("--lang-dir", Some(value)) => { settings.lang_dir = PathBuf::from(value); },
+                ("--mute", None) => { settings.muted = true; },
+                ("--effects-volume", Some(value)) => { settings.effects_volume = parse_percent("--effects-volume", value)?; },
+                ("--music-volume", Some(value)) => { settings.music_volume = parse_percent("--music-volume", value)?; },
+                ("--sound-pack", Some(value)) => { settings.sound_pack = Some(PathBuf::from(value)); },
+                ("--high-contrast", None) => { settings.high_contrast = true; },
+                ("--text-scale", Some(value)) => { settings.text_scale = parse_scale("--text-scale", value)?; },
+                ("--focus-outline", None) => { settings.focus_outline = true; },
+                ("--shape-markers", None) => { settings.shape_markers = true; },
+                ("--accessible", None) => {
+                    settings.high_contrast = true;
+                    settings.focus_outline = true;
+                    settings.shape_markers = true;
+                    settings.text_scale = settings.text_scale.max(ACCESSIBLE_TEXT_SCALE);
+                },
+                ("--announce", None) => { settings.announce = Some(PathBuf::from("-")); },
+                ("--announce", Some(value)) => { settings.announce = Some(PathBuf::from(value)); },
+                ("--record", Some(value)) => { settings.record = Some(PathBuf::from(value)); },
+                ("--rules", Some(value)) => { settings.rules = Rules::from_name(value)?; },
+                ("--jokers", Some(value)) => { settings.specials.jokers = parse_count("--jokers", value)?; },
+                ("--shuffle-cards", Some(value)) => { settings.specials.shuffles = parse_count("--shuffle-cards", value)?; },
+                _ => { return Err(format!("Unknown option {}", arg)); },
+            }
+        }
+        if settings.player_names.is_empty() {
+            settings.player_names.push("Hugo".to_string());
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rules, Settings, Specials, DEFAULT_RESULT_DELAY_MS};
+    use std::time::Duration;
+
+    fn args(a : &[&str]) -> Vec<String> {
+        a.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_args() {
+        let settings = Settings::from_args(args(&["Anna", "--no-animations", "Ben"])).unwrap();
+        assert_eq!(settings.player_names, args(&["Anna", "Ben"]));
+        assert!(!settings.animations);
+
+        let settings = Settings::from_args(args(&[])).unwrap();
+        assert_eq!(settings.player_names, args(&["Hugo"]));
+        assert!(settings.animations);
+
+        assert!(Settings::from_args(args(&["--bogus"])).is_err());
+    }
+
+    #[test]
+    fn test_theme_options() {
+        let settings = Settings::from_args(args(&["--theme=dark", "--theme-dir=/tmp/themes"])).unwrap();
+        assert_eq!(settings.theme, Some("dark".to_string()));
+        assert_eq!(settings.theme_dir, std::path::PathBuf::from("/tmp/themes"));
+        assert!(Settings::from_args(args(&["--theme"])).is_err());
+        let settings = Settings::from_args(args(&["--font-dir=a", "--font-dir=b"])).unwrap();
+        assert_eq!(settings.font_dirs, vec![std::path::PathBuf::from("a"), std::path::PathBuf::from("b")]);
+        let settings = Settings::from_args(args(&["--lang=de", "--lang-dir=/tmp/lang"])).unwrap();
+        assert_eq!(settings.language, Some("de".to_string()));
+        assert_eq!(settings.lang_dir, std::path::PathBuf::from("/tmp/lang"));
+    }
+
+    #[test]
+    fn test_auto_turn() {
+        let settings = Settings::from_args(args(&["--auto-turn"])).unwrap();
+        assert_eq!(settings.auto_turn_delay, Some(Duration::from_millis(DEFAULT_RESULT_DELAY_MS)));
+        let settings = Settings::from_args(args(&["--auto-turn=800"])).unwrap();
+        assert_eq!(settings.auto_turn_delay, Some(Duration::from_millis(800)));
+        assert!(Settings::from_args(args(&["--auto-turn=soon"])).is_err());
+        assert_eq!(Settings::from_args(args(&[])).unwrap().auto_turn_delay, None);
+    }
+
+    #[test]
+    fn test_sound_options() {
+        let settings = Settings::from_args(args(&["--mute", "--effects-volume=50", "--music-volume=0", "--sound-pack=packs/retro"])).unwrap();
+        assert!(settings.muted);
+        assert_eq!(settings.effects_volume, 50);
+        assert_eq!(settings.music_volume, 0);
+        assert_eq!(settings.sound_pack, Some(std::path::PathBuf::from("packs/retro")));
+        assert!(Settings::from_args(args(&["--music-volume=101"])).is_err());
+        assert!(Settings::from_args(args(&["--effects-volume=loud"])).is_err());
+        assert!(!Settings::from_args(args(&[])).unwrap().muted);
+    }
+
+    #[test]
+    fn test_accessibility_options() {
+        let settings = Settings::from_args(args(&["--accessible", "--announce"])).unwrap();
+        assert!(settings.high_contrast && settings.focus_outline && settings.shape_markers);
+        assert_eq!(settings.text_scale, 1.5);
+        assert_eq!(settings.announce, Some(std::path::PathBuf::from("-")));
+        let settings = Settings::from_args(args(&["--text-scale=2", "--accessible", "--announce=/tmp/game.txt"])).unwrap();
+        assert_eq!(settings.text_scale, 2.0);
+        assert_eq!(settings.announce, Some(std::path::PathBuf::from("/tmp/game.txt")));
+        assert!(Settings::from_args(args(&["--text-scale=10"])).is_err());
+        assert!(!Settings::from_args(args(&[])).unwrap().high_contrast);
+    }
+
+    #[test]
+    fn test_record_option() {
+        let settings = Settings::from_args(args(&["--record=/tmp/game.json", "Anna"])).unwrap();
+        assert_eq!(settings.record, Some(std::path::PathBuf::from("/tmp/game.json")));
+        assert!(Settings::from_args(args(&["--record"])).is_err());
+        assert_eq!(Settings::from_args(args(&[])).unwrap().record, None);
+    }
+
+    #[test]
+    fn test_rules_option() {
+        let settings = Settings::from_args(args(&["--rules=streak-bonus"])).unwrap();
+        assert_eq!(settings.rules, Rules::StreakBonus);
+        assert_eq!(Settings::from_args(args(&[])).unwrap().rules, Rules::Classic);
+        assert!(Settings::from_args(args(&["--rules=fair"])).is_err());
+    }
+
+    #[test]
+    fn test_special_cards_options() {
+        let settings = Settings::from_args(args(&["--jokers=2", "--shuffle-cards=4"])).unwrap();
+        assert_eq!(settings.specials, Specials { jokers : 2, shuffles : 4 });
+        assert!(Settings::from_args(args(&["--jokers=3"])).is_err());
+        assert!(Settings::from_args(args(&["--shuffle-cards=many"])).is_err());
+    }
+}
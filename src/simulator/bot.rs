@@ -0,0 +1,235 @@
+use std::fmt;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::engine::Engine;
+use crate::model::game_model::{CellView, Coord, ExpectedAction, PublicView};
+
+/// How a bot plays.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Strategy {
+    /// flips random cards and remembers nothing
+    Random,
+    /// remembers every card it has seen
+    Perfect,
+    /// remembers only the given number of cards it has seen last
+    Forgetful(usize),
+    /// an external program that speaks the engine protocol, given as its command line
+    Engine(String),
+}
+
+impl Strategy {
+    /// Parses "random", "perfect", "forgetful:N" or "engine:command".
+    pub fn parse(text : &str) -> Result<Strategy, String> {
+        match text.split_once(':') {
+            Some(("engine", command)) if !command.trim().is_empty() => { Ok(Strategy::Engine(command.trim().to_string())) },
+            None if text == "random" => { Ok(Strategy::Random) },
+            None if text == "perfect" => { Ok(Strategy::Perfect) },
+            Some(("forgetful", n)) => {
+                n.parse::<usize>().map(Strategy::Forgetful)
+                    .map_err(|_| format!("forgetful needs the number of cards it remembers, got \"{}\"", n))
+            },
+            _ => { Err(format!("Unknown strategy \"{}\", expected random, perfect, forgetful:N or engine:command", text)) },
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Random => { write!(f, "random") },
+            Strategy::Perfect => { write!(f, "perfect") },
+            Strategy::Forgetful(n) => { write!(f, "forgetful:{}", n) },
+            Strategy::Engine(command) => { write!(f, "engine:{}", command) },
+        }
+    }
+}
+
+/// A computer player. It only gets the public view of the game, so it plays by the same rules
+/// as a human: it knows the cards it has seen face up, nothing more.
+pub struct Bot {
+    pub strategy : Strategy,
+    /// the cards seen so far as (coordinate, card type), the most recently seen last
+    memory : Vec<(Coord, usize)>,
+    /// the running program of an engine strategy. If it fails the bot plays random moves.
+    engine : Option<Engine>,
+}
+
+impl Bot {
+    /// A bot with a built-in strategy. Engines are started with start(), without that an engine
+    /// strategy plays random moves.
+    pub fn new(strategy : Strategy) -> Self {
+        Bot {
+            strategy,
+            memory : Vec::new(),
+            engine : None,
+        }
+    }
+
+    /// Like new(), but starts the program of an engine strategy, which gets the given time for
+    /// every answer.
+    pub fn start(strategy : Strategy, timeout : Duration) -> Result<Self, String> {
+        let engine = match &strategy {
+            Strategy::Engine(command) => { Some(Engine::start(command, timeout)?) },
+            _ => { None },
+        };
+        Ok(Bot { engine, ..Bot::new(strategy) })
+    }
+
+    /// The engine made a mistake, it is stopped and the bot plays random moves from now on.
+    fn engine_failed(&mut self, error : String) {
+        println!("{}, playing random moves instead", error);
+        self.engine = None;
+    }
+
+    /// Forgets everything of the last game.
+    pub fn new_game(&mut self, seat : usize, view : &PublicView) {
+        self.memory.clear();
+        if let Some(Err(e)) = self.engine.as_mut().map(|engine| engine.new_game(seat, view)) {
+            self.engine_failed(e);
+        }
+    }
+
+    /// Looks at the table: open cards are remembered, taken cards are forgotten. Every bot
+    /// watches every move, not just its own.
+    pub fn observe(&mut self, view : &PublicView) {
+        if let Some(Err(e)) = self.engine.as_mut().map(|engine| engine.update(view)) {
+            self.engine_failed(e);
+        }
+        let capacity = match self.strategy {
+            Strategy::Random | Strategy::Engine(_) => { return; },
+            Strategy::Perfect => { usize::MAX },
+            Strategy::Forgetful(n) => { n },
+        };
+        for (row, cells) in view.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let coord = Coord(row, col);
+                match cell {
+                    CellView::FaceUp { card_type, .. } => {
+                        self.memory.retain(|(c, _)| *c != coord);
+                        self.memory.push((coord, *card_type));
+                    },
+                    CellView::Removed => { self.memory.retain(|(c, _)| *c != coord); },
+                    CellView::FaceDown => {},
+                }
+            }
+        }
+        if self.memory.len() > capacity {
+            self.memory.drain(..self.memory.len() - capacity);
+        }
+    }
+
+    /// A pair of shuffle cards was taken and the cards on the field were rearranged, so the bot
+    /// doesn't know where any card is any more. Engines see the new places in the next view like
+    /// everybody else.
+    pub fn cards_shuffled(&mut self) {
+        self.memory.clear();
+    }
+
+    pub fn game_over(&mut self, view : &PublicView) {
+        if let Some(Err(e)) = self.engine.as_mut().map(|engine| engine.game_over(view)) {
+            self.engine_failed(e);
+        }
+    }
+
+    /// Chooses the card to flip. Returns None if no card can be flipped.
+    pub fn choose(&mut self, view : &PublicView, rng : &mut StdRng) -> Option<Coord> {
+        if let Some(engine) = self.engine.as_mut() {
+            match engine.choose(view) {
+                Ok(coord) => { return Some(coord); },
+                Err(e) => { self.engine_failed(e); },
+            }
+        }
+        if matches!(self.strategy, Strategy::Random | Strategy::Engine(_)) {
+            return view.flippable.choose(rng).cloned();
+        }
+        let known = |coord : &Coord| self.memory.iter().find(|(c, _)| c == coord).map(|(_, t)| *t);
+        let choice = match view.expected_action {
+            ExpectedAction::FlipFirstCard => {
+                // a pair it knows both cards of
+                view.flippable.iter().find(|a| {
+                    known(a).is_some_and(|t| view.flippable.iter().any(|b| b != *a && known(b) == Some(t)))
+                }).cloned()
+            },
+            ExpectedAction::FlipSecondCard => {
+                // the partner of the open card, if it has seen it
+                let open_type = view.cells.iter().flatten().find_map(|cell| match cell {
+                    CellView::FaceUp { card_type, .. } => Some(*card_type),
+                    _ => None,
+                });
+                view.flippable.iter().find(|c| open_type.is_some() && known(c) == open_type).cloned()
+            },
+            _ => { return None; },
+        };
+        // otherwise a card it hasn't seen yet, so it learns something
+        let unknown : Vec<&Coord> = view.flippable.iter().filter(|c| known(c).is_none()).collect();
+        choice
+            .or_else(|| unknown.choose(rng).map(|c| (*c).clone()))
+            .or_else(|| view.flippable.choose(rng).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bot, Strategy};
+    use crate::model::game_model::{Action, Coord, Game};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!(Strategy::parse("random"), Ok(Strategy::Random));
+        assert_eq!(Strategy::parse("forgetful:3"), Ok(Strategy::Forgetful(3)));
+        assert!(Strategy::parse("forgetful:x").is_err());
+        assert!(Strategy::parse("clever").is_err());
+        assert_eq!(Strategy::Forgetful(3).to_string(), "forgetful:3");
+        assert_eq!(Strategy::parse("engine:./bots/mine --fast"), Ok(Strategy::Engine("./bots/mine --fast".to_string())));
+        assert!(Strategy::parse("engine:").is_err());
+    }
+
+    #[test]
+    fn test_perfect_bot_takes_known_pair() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Bot".to_string());
+        game.apply(Action::Reset).unwrap();
+        let mut bot = Bot::new(Strategy::Perfect);
+        // the bot has seen all cards
+        for coord in game.flippable_coords() {
+            bot.memory.push((coord.clone(), game.card_at(coord.0, coord.1).unwrap().card_type));
+        }
+        let mut rng = StdRng::seed_from_u64(1);
+        let first = bot.choose(&game.public_view(), &mut rng).unwrap();
+        game.apply(Action::Flip(first.clone())).unwrap();
+        let second = bot.choose(&game.public_view(), &mut rng).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(game.card_at(second.0, second.1).unwrap().card_type, game.card_at(first.0, first.1).unwrap().card_type);
+    }
+
+    #[test]
+    fn test_forgetful_bot_forgets() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Bot".to_string());
+        game.apply(Action::Reset).unwrap();
+        let mut bot = Bot::new(Strategy::Forgetful(1));
+        game.apply(Action::Flip(Coord(0, 0))).unwrap();
+        game.apply(Action::Flip(Coord(0, 1))).unwrap();
+        bot.observe(&game.public_view());
+        assert_eq!(bot.memory.len(), 1);
+    }
+
+    #[test]
+    fn test_bot_forgets_shuffled_cards() {
+        let mut game = Game::new(2, 2);
+        game.add_player("Bot".to_string());
+        game.apply(Action::Reset).unwrap();
+        let mut bot = Bot::new(Strategy::Perfect);
+        game.apply(Action::Flip(Coord(0, 0))).unwrap();
+        game.apply(Action::Flip(Coord(0, 1))).unwrap();
+        bot.observe(&game.public_view());
+        assert_eq!(bot.memory.len(), 2);
+        bot.cards_shuffled();
+        assert!(bot.memory.is_empty());
+    }
+}
@@ -0,0 +1,511 @@
+pub mod bot;
+pub mod tournament;
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::engine::DEFAULT_TIMEOUT;
+use crate::model::game_model::{Action, ExpectedAction, Game, GameEvent, Specials};
+use bot::{Bot, Strategy};
+
+/// Most turns a game may take per card before it is given up. Games with working rules end long
+/// before, so hitting this means the model got stuck.
+const MAX_TURNS_PER_CARD : usize = 1000;
+
+/// Number of bars of the histograms in the summary
+const HISTOGRAM_BINS : usize = 10;
+
+/// How the results are written
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// a readable report
+    Summary,
+    /// one line per game
+    Csv,
+    /// the report and all games
+    Json,
+}
+
+/// Settings of a simulation, taken from the command line:
+///
+/// memory_game simulate [--games=N] [--rows=N] [--cols=N] [--jokers=N] [--shuffle-cards=N]
+///                      [--players=strategy,...] [--seed=N] [--rotate] [--engine-timeout=milliseconds]
+///                      [--format=summary|csv|json] [--output=file]
+///
+/// The strategies are "random", "perfect", "forgetful:N" and "engine:command", one per player.
+/// With --rotate the players change seats after every game, so every strategy starts equally often.
+/// --jokers and --shuffle-cards put that many special cards into the deck.
+#[derive(Debug, PartialEq)]
+pub struct SimSettings {
+    pub games : usize,
+    pub rows : usize,
+    pub cols : usize,
+    /// the special cards in the deck
+    pub specials : Specials,
+    /// the strategy of each player, in seating order
+    pub strategies : Vec<Strategy>,
+    /// game i is played with seed + i, so any single game can be played again
+    pub seed : u64,
+    pub rotate : bool,
+    /// how long engines may think about a move
+    pub engine_timeout : Duration,
+    pub format : OutputFormat,
+    /// where the results are written, stdout if not given
+    pub output : Option<PathBuf>,
+}
+
+fn parse_number(name : &str, value : &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(n) if n > 0 => { Ok(n) },
+        _ => { Err(format!("Option {} needs a positive number, got \"{}\"", name, value)) },
+    }
+}
+
+/// Reads the number of special cards of --jokers or --shuffle-cards, Specials::check() checks
+/// that it fits.
+fn parse_count(name : &str, value : &str) -> Result<usize, String> {
+    value.trim().parse::<usize>().map_err(|_| format!("Option {} needs a number of cards, got \"{}\"", name, value))
+}
+
+impl SimSettings {
+    /// Parses the arguments after "simulate". Without --seed a random seed is used, the report
+    /// names it so the run can be repeated.
+    pub fn from_args<I : IntoIterator<Item = String>>(args : I) -> Result<SimSettings, String> {
+        let mut settings = SimSettings {
+            games : 1000,
+            rows : 8,
+            cols : 8,
+            specials : Specials::default(),
+            strategies : vec![Strategy::Perfect, Strategy::Random],
+            seed : rand::random(),
+            rotate : false,
+            engine_timeout : DEFAULT_TIMEOUT,
+            format : OutputFormat::Summary,
+            output : None,
+        };
+        for arg in args {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => { (name, Some(value)) },
+                None => { (arg.as_str(), None) },
+            };
+            match (name, value) {
+                ("--games", Some(value)) => { settings.games = parse_number(name, value)?; },
+                ("--rows", Some(value)) => { settings.rows = parse_number(name, value)?; },
+                ("--cols", Some(value)) => { settings.cols = parse_number(name, value)?; },
+                ("--jokers", Some(value)) => { settings.specials.jokers = parse_count(name, value)?; },
+                ("--shuffle-cards", Some(value)) => { settings.specials.shuffles = parse_count(name, value)?; },
+                ("--players", Some(value)) => {
+                    settings.strategies = value.split(',').map(Strategy::parse).collect::<Result<_, _>>()?;
+                },
+                ("--seed", Some(value)) => {
+                    settings.seed = value.trim().parse()
+                        .map_err(|_| format!("Option --seed needs a number, got \"{}\"", value))?;
+                },
+                ("--rotate", None) => { settings.rotate = true; },
+                ("--engine-timeout", Some(value)) => {
+                    settings.engine_timeout = Duration::from_millis(parse_number(name, value)? as u64);
+                },
+                ("--format", Some("summary")) => { settings.format = OutputFormat::Summary; },
+                ("--format", Some("csv")) => { settings.format = OutputFormat::Csv; },
+                ("--format", Some("json")) => { settings.format = OutputFormat::Json; },
+                ("--output", Some(value)) => { settings.output = Some(PathBuf::from(value)); },
+                _ => { return Err(format!("Unknown option {}", arg)); },
+            }
+        }
+        if !(settings.rows * settings.cols).is_multiple_of(2) {
+            return Err(format!("A field of {}x{} has an odd number of cards", settings.rows, settings.cols));
+        }
+        settings.specials.check(settings.rows * settings.cols)?;
+        Ok(settings)
+    }
+}
+
+/// The outcome of one simulated game
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GameResult {
+    pub seed : u64,
+    /// the strategies in seating order, the first one started
+    pub seats : Vec<String>,
+    /// number of times two cards were turned over
+    pub turns : usize,
+    pub rounds : u32,
+    /// pairs found by each seat
+    pub pairs : Vec<usize>,
+    /// the seats with the most pairs, more than one for a draw
+    pub winners : Vec<usize>,
+}
+
+/// Plays one game between the bots, the first bot starts.
+pub fn play_game(rows : usize, cols : usize, specials : Specials, bots : &mut [Bot], seed : u64) -> Result<GameResult, String> {
+    let mut game = Game::new(rows, cols);
+    game.set_specials(specials)?;
+    for (i, bot) in bots.iter().enumerate() {
+        game.add_player(format!("{} {}", i + 1, bot.strategy));
+    }
+    game.set_seed(seed);
+    // the bots get their own generator, so their choices don't change the shuffle
+    let mut rng = StdRng::seed_from_u64(seed ^ 0x5eed_b075);
+    game.apply(Action::Reset).map_err(|e| e.to_string())?;
+    let view = game.public_view();
+    for (seat, bot) in bots.iter_mut().enumerate() {
+        bot.new_game(seat, &view);
+    }
+    let max_turns = MAX_TURNS_PER_CARD * rows * cols;
+    let mut turns = 0;
+    loop {
+        let view = game.public_view();
+        let action = match view.expected_action {
+            ExpectedAction::FlipFirstCard | ExpectedAction::FlipSecondCard => {
+                match bots[view.current_player_id].choose(&view, &mut rng) {
+                    Some(coord) => { Action::Flip(coord) },
+                    None => { return Err(format!("Game {}: no card to flip in state {:?}", seed, view.state)); },
+                }
+            },
+            ExpectedAction::CheckResult | ExpectedAction::PassTurn => { Action::Confirm },
+            ExpectedAction::NewGame => { break; },
+        };
+        let events = game.apply(action).map_err(|e| format!("Game {}: {}", seed, e))?;
+        for event in events {
+            match event {
+                GameEvent::CardFlipped { .. } => {
+                    let view = game.public_view();
+                    for bot in bots.iter_mut() {
+                        bot.observe(&view);
+                    }
+                },
+                GameEvent::CardsShuffled => {
+                    for bot in bots.iter_mut() {
+                        bot.cards_shuffled();
+                    }
+                },
+                GameEvent::PairFound { .. } | GameEvent::NoPair { .. } => {
+                    turns += 1;
+                    if turns > max_turns {
+                        return Err(format!("Game {} didn't end after {} turns", seed, max_turns));
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+    let view = game.public_view();
+    for bot in bots.iter_mut() {
+        bot.game_over(&view);
+    }
+    let pairs : Vec<usize> = view.players.iter().map(|p| p.pairs.len()).collect();
+    let best = pairs.iter().copied().max().unwrap_or(0);
+    Ok(GameResult {
+        seed,
+        seats : bots.iter().map(|b| b.strategy.to_string()).collect(),
+        turns,
+        rounds : view.round,
+        winners : (0..pairs.len()).filter(|i| pairs[*i] == best).collect(),
+        pairs,
+    })
+}
+
+/// Plays all games of the simulation. Engines are started once and play all games.
+pub fn simulate(settings : &SimSettings) -> Result<Vec<GameResult>, String> {
+    let mut bots = settings.strategies.iter()
+        .map(|s| Bot::start(s.clone(), settings.engine_timeout))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut results = Vec::with_capacity(settings.games);
+    for i in 0..settings.games {
+        results.push(play_game(settings.rows, settings.cols, settings.specials, &mut bots, settings.seed.wrapping_add(i as u64))?);
+        if settings.rotate {
+            bots.rotate_left(1);
+        }
+    }
+    Ok(results)
+}
+
+/// One bar of a histogram: the number of values from `from` to `to` (inclusive)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Bin {
+    pub from : usize,
+    pub to : usize,
+    pub count : usize,
+}
+
+/// How a number is distributed over the games
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Distribution {
+    pub min : usize,
+    pub max : usize,
+    pub mean : f64,
+    pub median : usize,
+    /// 90% of the games have this value or less
+    pub p90 : usize,
+    pub histogram : Vec<Bin>,
+}
+
+impl Distribution {
+    pub fn of(values : &[usize]) -> Distribution {
+        let mut sorted = values.to_vec();
+        sorted.sort();
+        if sorted.is_empty() {
+            return Distribution { min : 0, max : 0, mean : 0.0, median : 0, p90 : 0, histogram : Vec::new() };
+        }
+        let min = sorted[0];
+        let max = sorted[sorted.len() - 1];
+        let width = (max - min) / HISTOGRAM_BINS + 1;
+        let mut histogram : Vec<Bin> = (0..=(max - min) / width)
+            .map(|i| Bin { from : min + i * width, to : min + (i + 1) * width - 1, count : 0 })
+            .collect();
+        for v in &sorted {
+            histogram[(v - min) / width].count += 1;
+        }
+        Distribution {
+            min,
+            max,
+            mean : sorted.iter().sum::<usize>() as f64 / sorted.len() as f64,
+            median : sorted[sorted.len() / 2],
+            p90 : sorted[(sorted.len() * 9 / 10).min(sorted.len() - 1)],
+            histogram,
+        }
+    }
+}
+
+/// Wins of a strategy over all seats it played
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StrategyStats {
+    pub strategy : String,
+    /// number of games it played (twice per game if two players use it)
+    pub games : usize,
+    pub win_rate : f64,
+}
+
+/// What the simulation found out. Draws count as a share of a win for each winner, so the win
+/// rates of all seats add up to 1.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub games : usize,
+    pub rows : usize,
+    pub cols : usize,
+    pub specials : Specials,
+    pub seed : u64,
+    pub rotate : bool,
+    pub turns : Distribution,
+    pub rounds : Distribution,
+    /// win rate of each seat, the first seat starts the game
+    pub seat_win_rates : Vec<f64>,
+    /// win rate of the first seat minus a fair share (1 / number of players)
+    pub first_player_advantage : f64,
+    pub draw_rate : f64,
+    pub strategies : Vec<StrategyStats>,
+}
+
+impl Report {
+    pub fn new(settings : &SimSettings, results : &[GameResult]) -> Report {
+        let seats = settings.strategies.len();
+        let games = results.len().max(1) as f64;
+        let mut seat_wins = vec![0.0; seats];
+        let mut strategies : Vec<(String, usize, f64)> = Vec::new();
+        for result in results {
+            let share = 1.0 / result.winners.len() as f64;
+            for (seat, name) in result.seats.iter().enumerate() {
+                let won = if result.winners.contains(&seat) { share } else { 0.0 };
+                seat_wins[seat] += won;
+                match strategies.iter_mut().find(|(s, _, _)| s == name) {
+                    Some(entry) => { entry.1 += 1; entry.2 += won; },
+                    None => { strategies.push((name.clone(), 1, won)); },
+                }
+            }
+        }
+        let seat_win_rates : Vec<f64> = seat_wins.iter().map(|w| w / games).collect();
+        Report {
+            games : results.len(),
+            rows : settings.rows,
+            cols : settings.cols,
+            specials : settings.specials,
+            seed : settings.seed,
+            rotate : settings.rotate,
+            turns : Distribution::of(&results.iter().map(|r| r.turns).collect::<Vec<_>>()),
+            rounds : Distribution::of(&results.iter().map(|r| r.rounds as usize).collect::<Vec<_>>()),
+            first_player_advantage : seat_win_rates.first().copied().unwrap_or(0.0) - 1.0 / seats as f64,
+            seat_win_rates,
+            draw_rate : results.iter().filter(|r| r.winners.len() > 1).count() as f64 / games,
+            strategies : strategies.into_iter()
+                .map(|(strategy, games, wins)| StrategyStats { strategy, games, win_rate : wins / games as f64 })
+                .collect(),
+        }
+    }
+}
+
+/// Writes one line per game. The strategy and pair columns repeat for each seat.
+pub fn write_csv(out : &mut dyn Write, results : &[GameResult]) -> io::Result<()> {
+    let seats = results.first().map(|r| r.seats.len()).unwrap_or(0);
+    let mut header = vec!["game".to_string(), "seed".to_string(), "turns".to_string(), "rounds".to_string(), "winners".to_string()];
+    header.extend((1..=seats).map(|i| format!("strategy_{}", i)));
+    header.extend((1..=seats).map(|i| format!("pairs_{}", i)));
+    writeln!(out, "{}", header.join(","))?;
+    for (i, r) in results.iter().enumerate() {
+        let winners : Vec<String> = r.winners.iter().map(|w| (w + 1).to_string()).collect();
+        let pairs : Vec<String> = r.pairs.iter().map(|p| p.to_string()).collect();
+        writeln!(out, "{},{},{},{},{},{},{}", i + 1, r.seed, r.turns, r.rounds, winners.join(" "), r.seats.join(","), pairs.join(","))?;
+    }
+    Ok(())
+}
+
+/// Writes the report and all games as one JSON object.
+pub fn write_json(out : &mut dyn Write, report : &Report, results : &[GameResult]) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct Output<'a> {
+        report : &'a Report,
+        games : &'a [GameResult],
+    }
+    serde_json::to_writer_pretty(&mut *out, &Output { report, games : results })?;
+    writeln!(out)
+}
+
+fn write_distribution(out : &mut dyn Write, name : &str, d : &Distribution) -> io::Result<()> {
+    writeln!(out, "{}: mean {:.1}, min {}, median {}, 90% {}, max {}", name, d.mean, d.min, d.median, d.p90, d.max)?;
+    let most = d.histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    for bin in &d.histogram {
+        writeln!(out, "  {:>5}-{:<5} {:>6} {}", bin.from, bin.to, bin.count, "#".repeat(bin.count * 40 / most))?;
+    }
+    Ok(())
+}
+
+/// The deck for the first line of a report, nothing for a standard deck
+fn deck_text(specials : &Specials) -> String {
+    if *specials == Specials::default() { String::new() } else { format!(" with a deck of {}", specials) }
+}
+
+/// Writes the report for reading.
+pub fn write_summary(out : &mut dyn Write, report : &Report) -> io::Result<()> {
+    writeln!(out, "{} games on a {}x{} field{}, seed {}{}", report.games, report.rows, report.cols, deck_text(&report.specials),
+        report.seed, if report.rotate { ", rotating seats" } else { "" })?;
+    write_distribution(out, "Turns", &report.turns)?;
+    write_distribution(out, "Rounds", &report.rounds)?;
+    for (seat, rate) in report.seat_win_rates.iter().enumerate() {
+        writeln!(out, "Seat {} wins {:.1}%", seat + 1, rate * 100.0)?;
+    }
+    writeln!(out, "First player advantage: {:+.1} percentage points", report.first_player_advantage * 100.0)?;
+    writeln!(out, "Draws: {:.1}%", report.draw_rate * 100.0)?;
+    for s in &report.strategies {
+        writeln!(out, "Strategy {} wins {:.1}% of {} games", s.strategy, s.win_rate * 100.0, s.games)?;
+    }
+    Ok(())
+}
+
+/// Opens the file the results are written to, or stdout.
+fn open_output(output : &Option<PathBuf>) -> Result<Box<dyn Write>, String> {
+    match output {
+        Some(path) => {
+            Ok(Box::new(File::create(path).map_err(|e| format!("Can't write results to {}: {}", path.display(), e))?))
+        },
+        None => { Ok(Box::new(io::stdout())) },
+    }
+}
+
+/// Runs the "simulate" command: plays the games and writes the results. Doesn't need a display.
+pub fn run(args : Vec<String>) -> Result<(), String> {
+    let settings = SimSettings::from_args(args)?;
+    let results = simulate(&settings)?;
+    let report = Report::new(&settings, &results);
+    let mut out = open_output(&settings.output)?;
+    match settings.format {
+        OutputFormat::Summary => { write_summary(&mut out, &report) },
+        OutputFormat::Csv => { write_csv(&mut out, &results) },
+        OutputFormat::Json => { write_json(&mut out, &report, &results) },
+    }.map_err(|e| format!("Can't write results: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bot::{Bot, Strategy};
+    use super::{play_game, simulate, write_csv, write_json, write_summary, Distribution, OutputFormat, Report, SimSettings};
+    use crate::model::game_model::Specials;
+
+    fn settings(args : &[&str]) -> SimSettings {
+        SimSettings::from_args(args.iter().map(|s| s.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_sim_settings() {
+        let s = settings(&["--games=10", "--rows=4", "--cols=5", "--players=random,forgetful:4", "--seed=7", "--format=csv"]);
+        assert_eq!((s.games, s.rows, s.cols, s.seed), (10, 4, 5, 7));
+        assert_eq!(s.strategies, vec![Strategy::Random, Strategy::Forgetful(4)]);
+        assert_eq!(s.format, OutputFormat::Csv);
+        assert!(SimSettings::from_args(vec!["--rows=3".to_string(), "--cols=3".to_string()]).is_err());
+        assert!(SimSettings::from_args(vec!["--players=genius".to_string()]).is_err());
+        assert!(SimSettings::from_args(vec!["--format=xml".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_games_are_complete_and_repeatable() {
+        let strategies = [Strategy::Random, Strategy::Perfect, Strategy::Forgetful(2)];
+        let mut bots : Vec<Bot> = strategies.iter().map(|s| Bot::new(s.clone())).collect();
+        let result = play_game(4, 4, Specials::default(), &mut bots, 42).unwrap();
+        assert_eq!(result.pairs.iter().sum::<usize>(), 8);
+        assert!(result.turns >= 8);
+        assert!(!result.winners.is_empty());
+        assert_eq!(play_game(4, 4, Specials::default(), &mut bots, 42).unwrap(), result);
+    }
+
+    #[test]
+    fn test_special_cards() {
+        let s = settings(&["--games=20", "--rows=4", "--cols=4", "--jokers=2", "--shuffle-cards=2", "--players=perfect,forgetful:3", "--seed=4"]);
+        assert_eq!(s.specials, Specials { jokers : 2, shuffles : 2 });
+        let results = simulate(&s).unwrap();
+        assert!(results.iter().all(|r| !r.winners.is_empty()));
+        let mut summary = Vec::new();
+        write_summary(&mut summary, &Report::new(&s, &results)).unwrap();
+        assert!(String::from_utf8(summary).unwrap().starts_with("20 games on a 4x4 field with a deck of standard + 2 jokers + 2 shuffles, seed 4"));
+        assert!(SimSettings::from_args(vec!["--jokers=3".to_string()]).is_err());
+        assert!(SimSettings::from_args(vec!["--rows=2".to_string(), "--cols=2".to_string(), "--shuffle-cards=6".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_perfect_memory_beats_random() {
+        let s = settings(&["--games=200", "--rows=4", "--cols=4", "--players=perfect,random", "--seed=1", "--rotate"]);
+        let results = simulate(&s).unwrap();
+        let report = Report::new(&s, &results);
+        assert_eq!(report.games, 200);
+        let total : f64 = report.seat_win_rates.iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        let perfect = report.strategies.iter().find(|s| s.strategy == "perfect").unwrap();
+        assert!(perfect.win_rate > 0.6, "perfect won only {}", perfect.win_rate);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_engine_plays_games() {
+        let s = settings(&["--games=5", "--rows=2", "--cols=4", "--players=engine:sh tests/engines/first_card.sh,random", "--seed=3"]);
+        let results = simulate(&s).unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].seats[0], "engine:sh tests/engines/first_card.sh");
+        assert!(results.iter().all(|r| r.pairs.iter().sum::<usize>() == 4));
+    }
+
+    #[test]
+    fn test_distribution() {
+        let d = Distribution::of(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 30]);
+        assert_eq!((d.min, d.max, d.median), (1, 30, 6));
+        assert_eq!(d.histogram.iter().map(|b| b.count).sum::<usize>(), 11);
+        assert_eq!(d.histogram.last().unwrap().count, 1);
+    }
+
+    #[test]
+    fn test_output() {
+        let s = settings(&["--games=3", "--rows=2", "--cols=4", "--players=random,random", "--seed=5"]);
+        let results = simulate(&s).unwrap();
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &results).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.starts_with("game,seed,turns,rounds,winners,strategy_1,strategy_2,pairs_1,pairs_2\n"));
+        let mut json = Vec::new();
+        write_json(&mut json, &Report::new(&s, &results), &results).unwrap();
+        let value : serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["games"].as_array().unwrap().len(), 3);
+        assert_eq!(value["report"]["seed"], 5);
+    }
+}
@@ -0,0 +1,310 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::bot::{Bot, Strategy};
+use super::{deck_text, open_output, parse_count, parse_number, play_game, write_csv, GameResult, OutputFormat};
+use crate::engine::DEFAULT_TIMEOUT;
+use crate::model::game_model::Specials;
+
+/// z value of a 95% confidence interval
+const Z_95 : f64 = 1.96;
+
+/// Settings of a tournament, taken from the command line:
+///
+/// memory_game tournament [--bots=strategy,...] [--boards=N] [--rows=N] [--cols=N] [--jokers=N]
+///                        [--shuffle-cards=N] [--seed=N] [--engine-timeout=milliseconds]
+///                        [--format=summary|csv|json] [--output=file]
+///
+/// Every bot plays every other bot on the same boards. Each board is played twice, once with each
+/// bot starting, so neither profits from the first move.
+#[derive(Debug, PartialEq)]
+pub struct TournamentSettings {
+    pub bots : Vec<Strategy>,
+    /// number of boards per match, each is played twice
+    pub boards : usize,
+    pub rows : usize,
+    pub cols : usize,
+    /// the special cards in the deck
+    pub specials : Specials,
+    /// board i is shuffled with seed + i
+    pub seed : u64,
+    pub engine_timeout : Duration,
+    pub format : OutputFormat,
+    pub output : Option<PathBuf>,
+}
+
+impl TournamentSettings {
+    /// Parses the arguments after "tournament".
+    pub fn from_args<I : IntoIterator<Item = String>>(args : I) -> Result<TournamentSettings, String> {
+        let mut settings = TournamentSettings {
+            bots : vec![Strategy::Perfect, Strategy::Forgetful(8), Strategy::Forgetful(2), Strategy::Random],
+            boards : 100,
+            rows : 6,
+            cols : 6,
+            specials : Specials::default(),
+            seed : rand::random(),
+            engine_timeout : DEFAULT_TIMEOUT,
+            format : OutputFormat::Summary,
+            output : None,
+        };
+        for arg in args {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => { (name, Some(value)) },
+                None => { (arg.as_str(), None) },
+            };
+            match (name, value) {
+                ("--bots", Some(value)) => {
+                    settings.bots = value.split(',').map(Strategy::parse).collect::<Result<_, _>>()?;
+                },
+                ("--boards", Some(value)) => { settings.boards = parse_number(name, value)?; },
+                ("--rows", Some(value)) => { settings.rows = parse_number(name, value)?; },
+                ("--cols", Some(value)) => { settings.cols = parse_number(name, value)?; },
+                ("--jokers", Some(value)) => { settings.specials.jokers = parse_count(name, value)?; },
+                ("--shuffle-cards", Some(value)) => { settings.specials.shuffles = parse_count(name, value)?; },
+                ("--seed", Some(value)) => {
+                    settings.seed = value.trim().parse()
+                        .map_err(|_| format!("Option --seed needs a number, got \"{}\"", value))?;
+                },
+                ("--engine-timeout", Some(value)) => {
+                    settings.engine_timeout = Duration::from_millis(parse_number(name, value)? as u64);
+                },
+                ("--format", Some("summary")) => { settings.format = OutputFormat::Summary; },
+                ("--format", Some("csv")) => { settings.format = OutputFormat::Csv; },
+                ("--format", Some("json")) => { settings.format = OutputFormat::Json; },
+                ("--output", Some(value)) => { settings.output = Some(PathBuf::from(value)); },
+                _ => { return Err(format!("Unknown option {}", arg)); },
+            }
+        }
+        if settings.bots.len() < 2 {
+            return Err("A tournament needs at least two bots".to_string());
+        }
+        if !(settings.rows * settings.cols).is_multiple_of(2) {
+            return Err(format!("A field of {}x{} has an odd number of cards", settings.rows, settings.cols));
+        }
+        settings.specials.check(settings.rows * settings.cols)?;
+        Ok(settings)
+    }
+}
+
+/// A game of the tournament: the bots (as index into the bot list) in seating order and the result
+pub struct MatchGame {
+    pub bots : [usize; 2],
+    pub result : GameResult,
+}
+
+/// Plays the tournament. Engines are started once and play all their games.
+pub fn play_tournament(settings : &TournamentSettings) -> Result<Vec<MatchGame>, String> {
+    let mut bots = settings.bots.iter()
+        .map(|s| Bot::start(s.clone(), settings.engine_timeout).map(Some))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut games = Vec::new();
+    for a in 0..bots.len() {
+        for b in a + 1..bots.len() {
+            for board in 0..settings.boards {
+                let seed = settings.seed.wrapping_add(board as u64);
+                for seats in [[a, b], [b, a]] {
+                    // the two bots are taken out of the list for the game
+                    let mut players = [bots[seats[0]].take().unwrap(), bots[seats[1]].take().unwrap()];
+                    let result = play_game(settings.rows, settings.cols, settings.specials, &mut players, seed);
+                    let [first, second] = players;
+                    bots[seats[0]] = Some(first);
+                    bots[seats[1]] = Some(second);
+                    games.push(MatchGame { bots : seats, result : result? });
+                }
+            }
+        }
+    }
+    Ok(games)
+}
+
+/// A line of the league table
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Standing {
+    pub bot : String,
+    pub games : usize,
+    pub wins : usize,
+    pub draws : usize,
+    pub losses : usize,
+    /// one for a win, a half for a draw
+    pub points : f64,
+    /// points per game
+    pub score : f64,
+    /// the true score is within score +- this with 95% confidence
+    pub score_margin : f64,
+    pub average_pairs : f64,
+}
+
+/// The league table, best bot first
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct League {
+    pub boards : usize,
+    pub rows : usize,
+    pub cols : usize,
+    pub specials : Specials,
+    pub seed : u64,
+    pub table : Vec<Standing>,
+}
+
+impl League {
+    pub fn new(settings : &TournamentSettings, games : &[MatchGame]) -> League {
+        let mut names : Vec<String> = Vec::new();
+        for (i, bot) in settings.bots.iter().enumerate() {
+            let name = bot.to_string();
+            // the same strategy can play more than once
+            let count = settings.bots[..i].iter().filter(|b| *b == bot).count();
+            names.push(if count == 0 { name } else { format!("{} #{}", name, count + 1) });
+        }
+        let mut table : Vec<Standing> = names.into_iter().enumerate().map(|(i, bot)| {
+            // the points of each game, for the confidence interval
+            let mut points = Vec::new();
+            let mut pairs = 0;
+            let (mut wins, mut draws, mut losses) = (0, 0, 0);
+            for game in games {
+                let Some(seat) = game.bots.iter().position(|b| *b == i) else { continue };
+                pairs += game.result.pairs[seat];
+                if !game.result.winners.contains(&seat) {
+                    losses += 1;
+                    points.push(0.0);
+                } else if game.result.winners.len() > 1 {
+                    draws += 1;
+                    points.push(0.5);
+                } else {
+                    wins += 1;
+                    points.push(1.0);
+                }
+            }
+            let n = points.len().max(1) as f64;
+            let total : f64 = points.iter().sum();
+            let score = total / n;
+            let variance = if points.len() > 1 {
+                points.iter().map(|p| (p - score).powi(2)).sum::<f64>() / (n - 1.0)
+            } else {
+                0.0
+            };
+            Standing {
+                bot,
+                games : points.len(),
+                wins,
+                draws,
+                losses,
+                points : total,
+                score,
+                score_margin : Z_95 * (variance / n).sqrt(),
+                average_pairs : pairs as f64 / n,
+            }
+        }).collect();
+        table.sort_by(|a, b| b.points.total_cmp(&a.points).then(b.average_pairs.total_cmp(&a.average_pairs)));
+        League {
+            boards : settings.boards,
+            rows : settings.rows,
+            cols : settings.cols,
+            specials : settings.specials,
+            seed : settings.seed,
+            table,
+        }
+    }
+}
+
+/// Writes the league table for reading.
+pub fn write_table(out : &mut dyn Write, league : &League) -> io::Result<()> {
+    writeln!(out, "{} boards per match on a {}x{} field{}, seed {}, every board played with both bots starting",
+        league.boards, league.rows, league.cols, deck_text(&league.specials), league.seed)?;
+    let width = league.table.iter().map(|s| s.bot.len()).max().unwrap_or(0).max(3);
+    writeln!(out, "{:>4}  {:<width$}  {:>5}  {:>5}  {:>5}  {:>5}  {:>7}  {:>15}  {:>9}",
+        "#", "Bot", "Games", "Won", "Drawn", "Lost", "Points", "Score (95%)", "Avg pairs")?;
+    for (rank, s) in league.table.iter().enumerate() {
+        let score = format!("{:.1}% ± {:.1}", s.score * 100.0, s.score_margin * 100.0);
+        writeln!(out, "{:>4}  {:<width$}  {:>5}  {:>5}  {:>5}  {:>5}  {:>7.1}  {:>15}  {:>9.2}",
+            rank + 1, s.bot, s.games, s.wins, s.draws, s.losses, s.points, score, s.average_pairs)?;
+    }
+    Ok(())
+}
+
+/// Writes the league table and all games as one JSON object.
+pub fn write_json(out : &mut dyn Write, league : &League, games : &[MatchGame]) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct Output<'a> {
+        league : &'a League,
+        games : Vec<&'a GameResult>,
+    }
+    serde_json::to_writer_pretty(&mut *out, &Output { league, games : games.iter().map(|g| &g.result).collect() })?;
+    writeln!(out)
+}
+
+/// Runs the "tournament" command.
+pub fn run(args : Vec<String>) -> Result<(), String> {
+    let settings = TournamentSettings::from_args(args)?;
+    let games = play_tournament(&settings)?;
+    let league = League::new(&settings, &games);
+    let mut out = open_output(&settings.output)?;
+    match settings.format {
+        OutputFormat::Summary => { write_table(&mut out, &league) },
+        OutputFormat::Csv => {
+            let results : Vec<GameResult> = games.into_iter().map(|g| g.result).collect();
+            write_csv(&mut out, &results)
+        },
+        OutputFormat::Json => { write_json(&mut out, &league, &games) },
+    }.map_err(|e| format!("Can't write results: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_tournament, write_table, League, MatchGame, TournamentSettings};
+    use crate::simulator::GameResult;
+
+    fn settings(args : &[&str]) -> TournamentSettings {
+        TournamentSettings::from_args(args.iter().map(|s| s.to_string())).unwrap()
+    }
+
+    fn game(bots : [usize; 2], pairs : [usize; 2]) -> MatchGame {
+        let best = pairs[0].max(pairs[1]);
+        MatchGame {
+            bots,
+            result : GameResult {
+                seed : 0,
+                seats : Vec::new(),
+                turns : 0,
+                rounds : 0,
+                pairs : pairs.to_vec(),
+                winners : (0..2).filter(|i| pairs[*i] == best).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_league_table() {
+        let s = settings(&["--bots=random,perfect,perfect", "--boards=1"]);
+        // perfect beats random twice, the perfect ones draw and win one each
+        let games = [game([0, 1], [1, 3]), game([1, 0], [4, 0]), game([1, 2], [2, 2]), game([2, 1], [3, 1])];
+        let league = League::new(&s, &games);
+        let names : Vec<&str> = league.table.iter().map(|s| s.bot.as_str()).collect();
+        assert_eq!(names, vec!["perfect", "perfect #2", "random"]);
+        let first = &league.table[0];
+        assert_eq!((first.games, first.wins, first.draws, first.losses), (4, 2, 1, 1));
+        assert_eq!(first.points, 2.5);
+        assert_eq!(first.average_pairs, 2.5);
+        assert!(first.score_margin > 0.0);
+        assert_eq!(league.table[2].score_margin, 0.0);
+        let mut out = Vec::new();
+        write_table(&mut out, &league).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 5);
+    }
+
+    #[test]
+    fn test_tournament() {
+        let s = settings(&["--bots=random,perfect,forgetful:2", "--boards=10", "--rows=4", "--cols=4", "--seed=9"]);
+        let games = play_tournament(&s).unwrap();
+        // three matches, each board with both bots starting
+        assert_eq!(games.len(), 3 * 10 * 2);
+        assert_eq!(games[0].result.seed, games[1].result.seed);
+        assert_eq!(games[0].bots, [0, 1]);
+        assert_eq!(games[1].bots, [1, 0]);
+        let league = League::new(&s, &games);
+        assert_eq!(league.table[0].bot, "perfect");
+        assert!(league.table.iter().all(|s| s.games == 40));
+        assert!(TournamentSettings::from_args(vec!["--bots=perfect".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+/// Options for players who are colorblind or have low vision.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accessibility {
+    /// black, white and yellow instead of the colors of the theme, and thick card borders
+    pub high_contrast : bool,
+    /// all texts are scaled by this factor
+    pub text_scale : f32,
+    /// the card under the cursor always has a thick outline, also when playing with the mouse
+    pub focus_outline : bool,
+    /// open cards show a shape marker that is different for each kind of card, so pairs can be
+    /// told apart without reading the title or seeing colors
+    pub shape_markers : bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Accessibility {
+            high_contrast : false,
+            text_scale : 1.0,
+            focus_outline : false,
+            shape_markers : false,
+        }
+    }
+}
+
+impl Accessibility {
+    /// Scales a font size by the text scale.
+    pub fn scale(&self, size : u32) -> u32 {
+        ((size as f32 * self.text_scale).round() as u32).max(1)
+    }
+}
+
+/// The shapes of the markers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
+    Cross,
+    Plus,
+    HorizontalBar,
+    VerticalBar,
+}
+
+const SHAPES : [Shape; 8] = [Shape::Circle, Shape::Square, Shape::Triangle, Shape::Diamond,
+                             Shape::Cross, Shape::Plus, Shape::HorizontalBar, Shape::VerticalBar];
+
+/// The marker of a kind of card: a shape drawn count times. The eight shapes times one to four
+/// copies give 32 different markers, one for each pair of the 8x8 field. Bigger fields repeat them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Marker {
+    pub shape : Shape,
+    pub count : usize,
+}
+
+/// Most copies of a shape in one marker
+const MAX_MARKER_COUNT : usize = 4;
+
+pub fn marker_for(card_type : usize) -> Marker {
+    let idx = card_type % (SHAPES.len() * MAX_MARKER_COUNT);
+    Marker {
+        shape : SHAPES[idx % SHAPES.len()],
+        count : idx / SHAPES.len() + 1,
+    }
+}
+
+/// Returns the outline of a shape as a closed polygon of points, centered at (0, 0) with the
+/// given radius. Filled shapes (square, bars) are returned as their outline as well.
+pub fn shape_outline(shape : Shape, radius : i32) -> Vec<(i32, i32)> {
+    let r = radius;
+    let t = (radius / 3).max(1);
+    let points = match shape {
+        Shape::Circle => {
+            (0..16).map(|i| {
+                let a = i as f32 * std::f32::consts::TAU / 16.0;
+                ((r as f32 * a.cos()).round() as i32, (r as f32 * a.sin()).round() as i32)
+            }).collect()
+        },
+        Shape::Square => { vec![(-r, -r), (r, -r), (r, r), (-r, r)] },
+        Shape::Triangle => { vec![(0, -r), (r, r), (-r, r)] },
+        Shape::Diamond => { vec![(0, -r), (r, 0), (0, r), (-r, 0)] },
+        Shape::Cross => {
+            vec![(-r, -r + t), (-r + t, -r), (0, -t), (r - t, -r), (r, -r + t), (t, 0),
+                 (r, r - t), (r - t, r), (0, t), (-r + t, r), (-r, r - t), (-t, 0)]
+        },
+        Shape::Plus => {
+            vec![(-t, -r), (t, -r), (t, -t), (r, -t), (r, t), (t, t),
+                 (t, r), (-t, r), (-t, t), (-r, t), (-r, -t), (-t, -t)]
+        },
+        Shape::HorizontalBar => { vec![(-r, -t), (r, -t), (r, t), (-r, t)] },
+        Shape::VerticalBar => { vec![(-t, -r), (t, -r), (t, r), (-t, r)] },
+    };
+    let mut points : Vec<(i32, i32)> = points;
+    points.push(points[0]);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{marker_for, shape_outline, Accessibility, Shape};
+
+    #[test]
+    fn test_markers_are_distinct() {
+        let markers : Vec<_> = (0..32).map(marker_for).collect();
+        for (i, m) in markers.iter().enumerate() {
+            assert!(m.count >= 1 && m.count <= 4);
+            assert!(!markers[i + 1..].contains(m), "card type {} has the same marker as another one", i);
+        }
+        assert_eq!(marker_for(32), marker_for(0));
+    }
+
+    #[test]
+    fn test_shape_outline_is_closed() {
+        for shape in [Shape::Circle, Shape::Triangle, Shape::Plus] {
+            let points = shape_outline(shape, 9);
+            assert_eq!(points.first(), points.last());
+            assert!(points.iter().all(|(x, y)| x.abs() <= 9 && y.abs() <= 9));
+        }
+    }
+
+    #[test]
+    fn test_text_scale() {
+        let a = Accessibility { text_scale : 1.5, ..Accessibility::default() };
+        assert_eq!(a.scale(24), 36);
+        assert_eq!(Accessibility::default().scale(24), 24);
+    }
+}
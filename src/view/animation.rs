@@ -0,0 +1,197 @@
+use std::time::{Duration, Instant};
+
+use crate::model::game_model::Coord;
+
+const FLIP_DURATION : Duration = Duration::from_millis(300);
+const SHAKE_DURATION : Duration = Duration::from_millis(500);
+const SLIDE_DURATION : Duration = Duration::from_millis(700);
+
+/// A time based tween. It maps the time since its start to a progress value between 0.0 and 1.0.
+#[derive(Clone, Copy)]
+pub struct Tween {
+    pub start : Instant,
+    pub duration : Duration,
+}
+
+impl Tween {
+    pub fn new(start : Instant, duration : Duration) -> Self {
+        Tween {
+            start,
+            duration,
+        }
+    }
+
+    /// Linear progress, 0.0 at the start and 1.0 when the tween is done.
+    pub fn progress(&self, now : Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f32();
+        let total = self.duration.as_secs_f32();
+        if total <= 0.0 {
+            return 1.0;
+        }
+        (elapsed / total).min(1.0)
+    }
+
+    /// Progress that starts fast and slows down towards the end.
+    pub fn ease_out(&self, now : Instant) -> f32 {
+        let p = self.progress(now);
+        1.0 - (1.0 - p) * (1.0 - p)
+    }
+
+    pub fn finished(&self, now : Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+/// The different kinds of animations.
+pub enum AnimationKind {
+    /// A card is turned face up.
+    Flip { coord : Coord },
+    /// The cards were no pair and shake their heads.
+    Shake { coords : Vec<Coord> },
+    /// A card of a found pair moves from its slot on the field to the area of the player who found it.
+    Slide { coord : Coord, title : String, player_id : usize },
+}
+
+pub struct Animation {
+    pub kind : AnimationKind,
+    pub tween : Tween,
+}
+
+/// Holds all running animations. The controller starts animations when the game changes, the
+/// Renderer asks for the state of the animations when drawing the cards.
+/// If animations are disabled nothing gets started and the game is drawn as before.
+pub struct Animator {
+    pub enabled : bool,
+    pub animations : Vec<Animation>,
+}
+
+impl Animator {
+    pub fn new(enabled : bool) -> Self {
+        Animator {
+            enabled,
+            animations : Vec::new(),
+        }
+    }
+
+    fn start(&mut self, kind : AnimationKind, duration : Duration) {
+        if !self.enabled {
+            return;
+        }
+        self.animations.push(Animation {
+            kind,
+            tween : Tween::new(Instant::now(), duration),
+        });
+    }
+
+    pub fn start_flip(&mut self, coord : Coord) {
+        self.start(AnimationKind::Flip { coord }, FLIP_DURATION);
+    }
+
+    pub fn start_shake(&mut self, coords : Vec<Coord>) {
+        self.start(AnimationKind::Shake { coords }, SHAKE_DURATION);
+    }
+
+    pub fn start_slide(&mut self, coord : Coord, title : String, player_id : usize) {
+        self.start(AnimationKind::Slide { coord, title, player_id }, SLIDE_DURATION);
+    }
+
+    /// True as long as an animation is running. The controller holds back input meanwhile.
+    pub fn is_busy(&self, now : Instant) -> bool {
+        self.animations.iter().any(|a| !a.tween.finished(now))
+    }
+
+    /// Drops all finished animations.
+    pub fn remove_finished(&mut self, now : Instant) {
+        self.animations.retain(|a| !a.tween.finished(now));
+    }
+
+    pub fn clear(&mut self) {
+        self.animations.clear();
+    }
+
+    /// Returns the progress of the flip animation of the card at the given coordinate, if there is one.
+    pub fn flip_progress(&self, coord : &Coord, now : Instant) -> Option<f32> {
+        self.animations.iter().find_map(|a| match &a.kind {
+            AnimationKind::Flip { coord: c } if c == coord && !a.tween.finished(now) => Some(a.tween.progress(now)),
+            _ => None,
+        })
+    }
+
+    /// Returns the horizontal offset in pixels of a shaking card. The amplitude decays over time.
+    pub fn shake_offset(&self, coord : &Coord, now : Instant) -> i32 {
+        for a in &self.animations {
+            if let AnimationKind::Shake { coords } = &a.kind {
+                if coords.contains(coord) && !a.tween.finished(now) {
+                    let p = a.tween.progress(now);
+                    let amplitude = 10.0 * (1.0 - p);
+                    return (amplitude * (p * 4.0 * std::f32::consts::TAU).sin()) as i32;
+                }
+            }
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Animator, Tween, FLIP_DURATION, SHAKE_DURATION};
+    use crate::model::game_model::Coord;
+
+    #[test]
+    fn test_tween_progress() {
+        let start = Instant::now();
+        let tween = Tween::new(start, Duration::from_millis(400));
+        assert_eq!(tween.progress(start), 0.0);
+        assert_eq!(tween.progress(start + Duration::from_millis(100)), 0.25);
+        assert_eq!(tween.progress(start + Duration::from_secs(5)), 1.0);
+        // before the start there is no progress yet
+        assert_eq!(Tween::new(start + Duration::from_secs(1), Duration::from_millis(400)).progress(start), 0.0);
+        assert_eq!(tween.ease_out(start), 0.0);
+        assert_eq!(tween.ease_out(start + Duration::from_millis(400)), 1.0);
+        assert!(tween.ease_out(start + Duration::from_millis(100)) > 0.25);
+        assert!(!tween.finished(start + Duration::from_millis(399)));
+        assert!(tween.finished(start + Duration::from_millis(400)));
+        assert!(Tween::new(start, Duration::ZERO).finished(start));
+    }
+
+    #[test]
+    fn test_animator() {
+        let mut animator = Animator::new(true);
+        animator.start_flip(Coord(0, 1));
+        let start = animator.animations[0].tween.start;
+        assert!(animator.is_busy(start));
+        assert_eq!(animator.flip_progress(&Coord(0, 1), start), Some(0.0));
+        assert_eq!(animator.flip_progress(&Coord(1, 0), start), None);
+        let done = start + FLIP_DURATION;
+        assert!(!animator.is_busy(done));
+        assert_eq!(animator.flip_progress(&Coord(0, 1), done), None);
+        animator.remove_finished(done);
+        assert!(animator.animations.is_empty());
+    }
+
+    #[test]
+    fn test_shake_comes_to_rest() {
+        let mut animator = Animator::new(true);
+        animator.start_shake(vec![Coord(0, 0), Coord(1, 1)]);
+        let start = animator.animations[0].tween.start;
+        let offsets : Vec<i32> = (0..50).map(|ms| animator.shake_offset(&Coord(1, 1), start + Duration::from_millis(ms * 10))).collect();
+        assert!(offsets.iter().any(|o| *o != 0));
+        assert!(offsets.iter().all(|o| o.abs() <= 10));
+        assert_eq!(animator.shake_offset(&Coord(1, 1), start + SHAKE_DURATION), 0);
+        assert_eq!(animator.shake_offset(&Coord(0, 1), start + Duration::from_millis(50)), 0);
+    }
+
+    #[test]
+    fn test_disabled_animator() {
+        let mut animator = Animator::new(false);
+        animator.start_flip(Coord(0, 0));
+        animator.start_shake(vec![Coord(0, 0)]);
+        animator.start_slide(Coord(0, 0), "7".to_string(), 0);
+        let now = Instant::now();
+        assert!(!animator.is_busy(now));
+        assert_eq!(animator.flip_progress(&Coord(0, 0), now), None);
+        assert_eq!(animator.shake_offset(&Coord(0, 0), now), 0);
+    }
+}
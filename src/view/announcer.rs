@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Writes what happens in the game as plain sentences, one per line, e.g.
+/// "Player Anna opened card at row 3 column 5: Cat". Screen readers can read the stream from
+/// stdout or follow the file. If no output is set announcements are dropped.
+pub struct Announcer {
+    out : Option<Box<dyn Write>>,
+}
+
+impl Announcer {
+    /// An announcer that drops everything
+    pub fn none() -> Self {
+        Announcer { out : None }
+    }
+
+    pub fn stdout() -> Self {
+        Announcer::to_writer(Box::new(io::stdout()))
+    }
+
+    /// Writes the announcements into the given file, an existing file is overwritten.
+    pub fn to_file(path : &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Can't write announcements to {}: {}", path.display(), e))?;
+        Ok(Announcer::to_writer(Box::new(file)))
+    }
+
+    pub fn to_writer(out : Box<dyn Write>) -> Self {
+        Announcer { out : Some(out) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Writes one announcement. If writing fails the announcer is switched off, so a closed
+    /// pipe doesn't stop the game.
+    pub fn announce(&mut self, text : &str) {
+        if let Some(out) = self.out.as_mut() {
+            if let Err(e) = writeln!(out, "{}", text).and_then(|_| out.flush()) {
+                println!("Announcements switched off: {}", e);
+                self.out = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Announcer;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer that keeps everything in a shared buffer
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf : &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_announce() {
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let mut announcer = Announcer::to_writer(Box::new(Buffer(data.clone())));
+        announcer.announce("Player Anna opened card at row 3 column 5: Cat");
+        announcer.announce("No pair");
+        assert_eq!(String::from_utf8(data.lock().unwrap().clone()).unwrap(),
+            "Player Anna opened card at row 3 column 5: Cat\nNo pair\n");
+
+        let mut silent = Announcer::none();
+        assert!(!silent.is_enabled());
+        silent.announce("nobody hears this");
+    }
+}
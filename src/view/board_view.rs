@@ -1,4 +1,4 @@
-use sdl2::rect::Rect;
+use sdl2::rect::{Point, Rect};
 use sdl2::pixels::Color;
 use sdl2::render::Canvas;
 use sdl2::render::TextureQuery;
@@ -6,8 +6,20 @@ use sdl2::render::Texture;
 use sdl2::surface::Surface;
 use sdl2::video::Window;
 
+use std::path::PathBuf;
+use std::time::Instant;
+
+use sdl2::ttf::Sdl2TtfContext;
+
 use crate::model::game_model::Coord;
-use crate::model::game_model::{Game, Card, GameState};
+use crate::model::game_model::{CardKind, CellView, Game, GameState, PlayerView, PublicView};
+use crate::view::accessibility::{marker_for, shape_outline, Accessibility, Shape};
+use crate::view::ui_state::{Menu, MenuItem, UiState};
+use crate::view::animation::AnimationKind;
+use crate::view::layout::Layout;
+use crate::view::theme::Theme;
+use crate::view::fonts::{resolve_font, FontSource};
+use crate::i18n::Catalog;
 
 // -----------------------------------------------------------------------------------------------
 /// 
@@ -15,13 +27,21 @@ use crate::model::game_model::{Game, Card, GameState};
 /// 
 // -----------------------------------------------------------------------------------------------
 pub struct Renderer {
-    pub window_height : u32,
-    pub window_width : u32,
-    pub statusbar_area : Rect,
-    pub screen_area : Rect,
-    pub clear_color : Color,
+    pub layout : Layout,
+    pub theme : Theme,
+    /// the texts in the selected language
+    pub texts : Catalog,
+    pub accessibility : Accessibility,
+    /// the font of the theme as it was found on this computer
+    font : FontSource,
+    /// additional directories to search for fonts
+    font_dirs : Vec<PathBuf>,
+    ttf_context : Sdl2TtfContext,
 }
 
+/// Height of the line of a player in the players_area
+const PLAYER_LINE_HEIGHT : u32 = 50;
+
 /// handle the annoying Rect i32 casting need
 macro_rules! rect(
     ($x:expr, $y:expr, $w:expr, $h:expr) => (
@@ -66,8 +86,9 @@ fn get_centered_rect(rect_width: u32, rect_height: u32, cons_width: u32, cons_he
 
 struct TextRenderData<'a> {
     text : &'a str,
-    font_path : &'a str,
     font_style : sdl2::ttf::FontStyle,
+    font_size : u16,
+    color : Color,
 }
 
 // -----------------------------------------------------------------------------------------------
@@ -76,40 +97,89 @@ struct TextRenderData<'a> {
 /// state of the game.
 // -----------------------------------------------------------------------------------------------
 impl Renderer {
+    /// Creates the Renderer. Fails if no usable font can be found for the theme (see resolve_font()).
+    pub fn new(window_width : u32, window_height : u32, rows : usize, cols : usize, theme : Theme, texts : Catalog, font_dirs : Vec<PathBuf>) -> Result<Self, String> {
+        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+        let font = resolve_font(&ttf_context, &theme.font_path, &font_dirs)?;
+        Ok(Renderer {
+            layout : Layout::new(window_width, window_height, rows, cols),
+            theme,
+            texts,
+            accessibility : Accessibility::default(),
+            font,
+            font_dirs,
+            ttf_context,
+        })
+    }
+
+    /// Switches to the given theme (if it isn't the current one already). In high contrast mode
+    /// only the fonts of the theme are used.
+    pub fn set_theme(&mut self, theme : &Theme) -> Result<(), String> {
+        let theme = if self.accessibility.high_contrast { theme.high_contrast() } else { theme.clone() };
+        if self.theme != theme {
+            if self.theme.font_path != theme.font_path {
+                self.font = resolve_font(&self.ttf_context, &theme.font_path, &self.font_dirs)?;
+            }
+            self.theme = theme;
+        }
+        Ok(())
+    }
+
+    /// Switches to the given language (if it isn't the current one already).
+    pub fn set_language(&mut self, texts : &Catalog) {
+        if self.texts.code != texts.code {
+            self.texts = texts.clone();
+        }
+    }
+
+    /// Text data for the general texts (status bar, player list, messages, menu) in the current theme
+    fn text_data<'a>(&'a self, text : &'a str, color : Color) -> TextRenderData<'a> {
+        TextRenderData {
+            text,
+            font_style : sdl2::ttf::FontStyle::BOLD,
+            font_size : self.accessibility.scale(self.theme.font_size as u32) as u16,
+            color,
+        }
+    }
+
+    /// Recalculates the layout for a new window size.
+    pub fn resize(&mut self, window_width : u32, window_height : u32) {
+        self.layout = Layout::new(window_width, window_height, self.layout.rows, self.layout.cols);
+    }
+
     /// Creates a SDL2 Surface from a given text which can be used to create a Texture.
     fn surface_from_text(&self, text_render_data : &TextRenderData) -> Surface {
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-
-        // Load a font
-        let mut font = ttf_context.load_font(text_render_data.font_path, 24).unwrap();
+        // Load the font, it has been checked to be loadable when it was resolved
+        let mut font = self.font.load(&self.ttf_context, text_render_data.font_size).unwrap();
         font.set_style(text_render_data.font_style);
         //let txt = (row*8+col+1).to_string();
         let txt = text_render_data.text;
         // render a surface, and convert it to a texture bound to the canvas
         let surface = font
             .render(&txt)
-            .blended(Color::RGBA(255, 0, 0, 255))
+            .blended(text_render_data.color)
             .map_err(|e| e.to_string()).unwrap();
         surface
     }
 
-    /// Return a string representation of the given GameState
+    /// Return a string representation of the given GameState in the selected language
     fn format_status(& self, state : & GameState) -> &str {
-        match state {
-            GameState::GameSetup => { "Enter player name" },
-            GameState::StartGame => { "select first card" },
-            GameState::StartSelectCards => { "select first card" },
-            GameState::FirstCard => { "select second card" },
-            GameState::SecondCard => { "SecondCard" },
+        let key = match state {
+            GameState::GameSetup => { "state.game_setup" },
+            GameState::StartGame => { "state.start_game" },
+            GameState::StartSelectCards => { "state.start_select_cards" },
+            GameState::FirstCard => { "state.first_card" },
+            GameState::SecondCard => { "state.second_card" },
             // GameState::ViewResult=> { "ViewResult" },
-            GameState::NextUser => { "NextUser" },
-            GameState::GameOver => { "Game Over!" },
-        }
+            GameState::NextUser => { "state.next_user" },
+            GameState::GameOver => { "state.game_over" },
+        };
+        self.texts.text(key)
     }
 
     fn render_text(& self, canvas : &mut Canvas<Window>, rect : &Rect, text_render_data : &TextRenderData, clear_box : bool) {
         if clear_box {
-            canvas.set_draw_color(self.clear_color);
+            canvas.set_draw_color(self.theme.background);
             canvas.fill_rect(*rect).ok().unwrap_or_default();
         }
 
@@ -141,108 +211,337 @@ impl Renderer {
     /// Function renders the status bar. The status bar shows the points of the top five players. It also shows the current player,
     /// and the round number.
     /// 
-    fn render_status_box(&self, canvas : &mut Canvas<Window>, game : &Game) {
-        let text = format!("Round: {} - Current Player: {} has {} cards - {}", 
-            game.round(), game.current_player().name, game.current_player().collected_cards.len(), 
-            self.format_status(&game.game_state()));
-        let font_path = "./python/fonts/OpenSans-Bold.ttf";
-        let font_style = sdl2::ttf::FontStyle::BOLD;
-
-        let text_render_data = TextRenderData {
-            text : text.as_str(),
-            font_path,
-            font_style,
-        };
-
-        let rect = rect!(
-            self.statusbar_area.left(), 
-            self.statusbar_area.top(), 
-            self.statusbar_area.width(), 
-            self.statusbar_area.height());
+    fn render_status_box(&self, canvas : &mut Canvas<Window>, view : &PublicView) {
+        let Some(player) = view.players.get(view.current_player_id) else { return };
+        let mut ranking : Vec<&PlayerView> = view.players.iter().collect();
+        ranking.sort_by_key(|p| std::cmp::Reverse(p.score));
+        let scores : Vec<String> = ranking.iter().take(5).map(|p| format!("{} {}", p.name, p.score)).collect();
+        let text = self.texts.format("status.line", &[
+            ("round", &view.round),
+            ("player", &player.name),
+            ("cards", &self.texts.plural("cards", player.pairs.len() * 2)),
+            ("scores", &scores.join(", ")),
+            ("state", &self.format_status(&view.state)),
+        ]);
+        let text_render_data = self.text_data(text.as_str(), self.theme.status_text);
+
+        let rect = self.layout.statusbar_area;
         self.render_text(canvas, &rect, &text_render_data, true);
     }
     
     /// Function renders a single card at given position and dimension on the Canvas
-    fn render_card(&self, canvas : &mut Canvas<Window>, card : &Card, y : i32, x : i32, card_height : u32 , card_width : u32) {
-        let text = card.title.as_str();
-        let font_path = "./python/fonts/OpenSans-Bold.ttf";
-        let font_style = sdl2::ttf::FontStyle::BOLD;
+    fn render_card(&self, canvas : &mut Canvas<Window>, title : &str, y : i32, x : i32, card_height : u32 , card_width : u32) {
+        let rect = rect!(x, y, card_width, card_height);
+        self.render_card_text(canvas, title, &rect);
+    }
 
+    /// Function renders the title of a card centered into the given rectangle
+    fn render_card_text(&self, canvas : &mut Canvas<Window>, text : &str, rect : &Rect) {
         let text_render_data = TextRenderData {
             text,
-            font_path,
-            font_style,
+            font_style : sdl2::ttf::FontStyle::BOLD,
+            font_size : self.accessibility.scale(self.theme.card_font_size as u32) as u16,
+            color : self.theme.card_text,
         };
 
-        let rect = rect!(x, y, card_width, card_height);
-        self.render_text(canvas, &rect, &text_render_data, false);
+        self.render_text(canvas, rect, &text_render_data, false);
+    }
+
+    /// Function renders the list of players with the number of cards they collected.
+    /// The current player is marked with an arrow, in network games the players at this computer
+    /// are marked as well.
+    fn render_players_box(&self, canvas : &mut Canvas<Window>, view : &PublicView, local : Option<&[usize]>) {
+        for (i, player) in view.players.iter().enumerate() {
+            let marker = if i == view.current_player_id { "> " } else { "" };
+            let mut line = self.texts.format("players.line", &[
+                ("player", &player.name),
+                ("cards", &self.texts.plural("cards", player.pairs.len() * 2)),
+            ]);
+            if local.is_some_and(|l| l.contains(&i)) {
+                line = self.texts.format("players.here", &[("line", &line)]);
+            }
+            let text = format!("{}{}", marker, line);
+            let text_render_data = self.text_data(text.as_str(), self.theme.status_text);
+            self.render_text(canvas, &self.player_line_rect(i), &text_render_data, false);
+        }
     }
 
+    /// Returns the rectangle of the line of the given player in the players_area
+    fn player_line_rect(&self, player_id : usize) -> Rect {
+        rect!(
+            self.layout.players_area.left(),
+            self.layout.players_area.top() + (player_id as u32 * self.accessibility.scale(PLAYER_LINE_HEIGHT)) as i32,
+            self.layout.players_area.width(),
+            self.accessibility.scale(PLAYER_LINE_HEIGHT))
+    }
+
+    /// Function renders the rectangle of a card: filled with the given color and with a border.
+    /// The border is thicker in high contrast mode.
+    fn render_card_frame(&self, canvas : &mut Canvas<Window>, rect : &Rect, fill : Color) {
+        canvas.set_draw_color(fill);
+        canvas.fill_rect(*rect).ok().unwrap_or_default();
+        canvas.set_draw_color(self.theme.card_border);
+        let thickness = if self.accessibility.high_contrast { 3 } else { 1 };
+        for i in 0..thickness {
+            let frame = rect!(rect.x() + i, rect.y() + i, rect.width().saturating_sub(2 * i as u32), rect.height().saturating_sub(2 * i as u32));
+            canvas.draw_rect(frame).expect("Error on Drawing Rectangle on canvas");
+        }
+    }
+
+    /// Function renders the shape marker of the card type in the top left corner of an open card.
+    fn render_marker(&self, canvas : &mut Canvas<Window>, card_type : usize, rect : &Rect) {
+        let marker = marker_for(card_type);
+        let radius = (rect.width().min(rect.height()) / 10).max(2) as i32;
+        canvas.set_draw_color(self.theme.card_text);
+        for i in 0..marker.count as i32 {
+            let cx = rect.x() + radius * 2 + i * radius * 3;
+            let cy = rect.y() + radius * 2;
+            if cx + radius > rect.right() {
+                break;
+            }
+            let points : Vec<Point> = shape_outline(marker.shape, radius).iter()
+                .map(|(x, y)| Point::new(cx + x, cy + y))
+                .collect();
+            canvas.draw_lines(points.as_slice()).expect("Error on Drawing Lines on canvas");
+            // square and bars are drawn filled, so they differ from the outlined shapes
+            if matches!(marker.shape, Shape::Square | Shape::HorizontalBar | Shape::VerticalBar) {
+                let xs = points.iter().map(|p| p.x());
+                let ys = points.iter().map(|p| p.y());
+                let (x0, x1) = (xs.clone().min().unwrap_or(cx), xs.max().unwrap_or(cx));
+                let (y0, y1) = (ys.clone().min().unwrap_or(cy), ys.max().unwrap_or(cy));
+                canvas.fill_rect(rect!(x0, y0, x1 - x0, y1 - y0)).ok().unwrap_or_default();
+            }
+        }
+    }
+
+    /// Function renders the cards of found pairs on their way from the field to the player who found them.
+    fn render_slides(&self, canvas : &mut Canvas<Window>, ui : &UiState, now : Instant) {
+        let (card_width, card_height) = (self.layout.card_width, self.layout.card_height);
+        for animation in &ui.animations.animations {
+            if let AnimationKind::Slide { coord, title, player_id } = &animation.kind {
+                if animation.tween.finished(now) {
+                    continue;
+                }
+                let p = animation.tween.ease_out(now);
+                let from = self.layout.card_rect(coord);
+                let line = self.player_line_rect(*player_id);
+                let to = rect!(
+                    line.right() - (card_width / 2) as i32,
+                    line.top() + (line.height() as i32 - (card_height / 2) as i32) / 2,
+                    card_width / 2,
+                    card_height / 2);
+                let lerp = |a : i32, b : i32| a + ((b - a) as f32 * p) as i32;
+                let r = rect!(
+                    lerp(from.x(), to.x()),
+                    lerp(from.y(), to.y()),
+                    lerp(from.width() as i32, to.width() as i32),
+                    lerp(from.height() as i32, to.height() as i32));
+                self.render_card_frame(canvas, &r, self.theme.card_front);
+                self.render_card_text(canvas, title, &r);
+            }
+        }
+    }
 
     /// Function renders a text on the canvas right across the cards. The text is a
     /// success message if the player opened to matching cards or otherwise a fail 
     /// message.
-    fn render_check_result_box(&self, canvas : &mut Canvas<Window>, game : &Game) {
-        let mut text = "Not a pair, bad luck.";
+    fn render_check_result_box(&self, canvas : &mut Canvas<Window>, view : &PublicView) {
+        let mut text = self.texts.text("result.no_pair");
 
-        if game.last_guess_success() {
-            text = "You found a pair!";
+        if view.last_guess_success {
+            text = self.texts.text("result.pair");
         }
 
-        let font_path = "./python/fonts/OpenSans-Bold.ttf";
-        let font_style = sdl2::ttf::FontStyle::BOLD;
+        let text_render_data = self.text_data(text, self.theme.message_text);
 
-        let text_render_data = TextRenderData {
-            text,
-            font_path,
-            font_style,
-        };
-
-        let rect = rect!(0, 400, (self.statusbar_area.width() * 3) / 5 , self.statusbar_area.height()-10);        
+        let rect = self.layout.message_area;
         self.render_text(canvas, &rect, &text_render_data, false);
     }
 
 
+    /// Function renders the move list of a replay below the players: a header line and as many
+    /// moves as fit, scrolled so the current one is visible and marked with an arrow.
+    pub fn render_move_list(&self, canvas : &mut Canvas<Window>, num_players : usize, header : &str, lines : &[String], current : Option<usize>) {
+        let line_height = self.accessibility.scale(PLAYER_LINE_HEIGHT);
+        let area = self.layout.players_area;
+        // one empty line between the players and the list
+        let top = area.top() + ((num_players + 1) as u32 * line_height) as i32;
+        let visible = (area.bottom() - top).max(0) as usize / line_height as usize;
+        if visible == 0 {
+            return;
+        }
+        let line_rect = |i : usize| rect!(area.left(), top + (i as u32 * line_height) as i32, area.width(), line_height);
+        self.render_text(canvas, &line_rect(0), &self.text_data(header, self.theme.status_text), false);
+        let moves = visible - 1;
+        let first = current.unwrap_or(0).saturating_sub(moves / 2).min(lines.len().saturating_sub(moves));
+        for (i, line) in lines.iter().enumerate().skip(first).take(moves) {
+            let marker = if Some(i) == current { "> " } else { "" };
+            let text = format!("{}{}", marker, line);
+            self.render_text(canvas, &line_rect(i - first + 1), &self.text_data(&text, self.theme.status_text), false);
+        }
+    }
+
+    /// Function renders a message right across the cards, e.g. whose turn it is in a network game.
+    pub fn render_message(&self, canvas : &mut Canvas<Window>, text : &str) {
+        let text_render_data = self.text_data(text, self.theme.message_text);
+        self.render_text(canvas, &self.layout.message_area, &text_render_data, false);
+    }
+
+    /// Function renders whose turn it is in a network game: the players at this computer are
+    /// asked to play, otherwise they are told who they are waiting for.
+    fn render_turn_box(&self, canvas : &mut Canvas<Window>, view : &PublicView, local : &[usize]) {
+        let Some(player) = view.players.get(view.current_player_id) else { return };
+        let key = if local.contains(&view.current_player_id) { "net.your_turn" } else { "net.their_turn" };
+        let text = self.texts.format(key, &[("player", &player.name)]);
+        self.render_message(canvas, &text);
+    }
+
+    /// Function renders the keyboard cursor as a thick frame around the card slot at the cursor position.
+    /// The frame is also drawn if the slot is empty, so the user always sees where the cursor is.
+    /// The focus outline is even thicker and has a dark inner line, so it stands out on any card.
+    /// The cursors of players waiting for their turn are thin frames.
+    fn render_cursor(&self, canvas : &mut Canvas<Window>, cursor : &Coord, waiting : bool) {
+        let r = self.layout.card_rect(cursor);
+
+        canvas.set_draw_color(self.theme.cursor);
+        if waiting {
+            canvas.draw_rect(rect!(r.x() - 2, r.y() - 2, r.width() + 4, r.height() + 4))
+                .expect("Error on Drawing Rectangle on canvas");
+            return;
+        }
+        let thickness = if self.accessibility.focus_outline { 7 } else { 4 };
+        for i in 1..thickness {
+            let frame = rect!(r.x() - i, r.y() - i, r.width() + 2 * i as u32, r.height() + 2 * i as u32);
+            canvas.draw_rect(frame).expect("Error on Drawing Rectangle on canvas");
+        }
+        if self.accessibility.focus_outline {
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            canvas.draw_rect(rect!(r.x() - thickness, r.y() - thickness, r.width() + 2 * thickness as u32, r.height() + 2 * thickness as u32))
+                .expect("Error on Drawing Rectangle on canvas");
+        }
+    }
+
+    /// Function renders the menu as a box in the middle of the screen_area. The selected item is
+    /// marked with arrows.
+    fn render_menu(&self, canvas : &mut Canvas<Window>, menu : &Menu, sound_on : bool) {
+        let item_height : u32 = self.accessibility.scale(60);
+        let screen_area = self.layout.screen_area;
+        let box_width : u32 = screen_area.width() * 2 / 3;
+        let box_height : u32 = item_height * menu.items.len() as u32 + 40;
+        let box_x = screen_area.left() + (screen_area.width().saturating_sub(box_width) / 2) as i32;
+        let box_y = screen_area.top() + (screen_area.height() as i32 - box_height as i32) / 2;
+
+        canvas.set_draw_color(self.theme.background);
+        canvas.fill_rect(rect!(box_x, box_y, box_width, box_height)).ok().unwrap_or_default();
+        canvas.set_draw_color(self.theme.card_border);
+        canvas.draw_rect(rect!(box_x, box_y, box_width, box_height)).expect("Error on Drawing Rectangle on canvas");
+
+        for (i, item) in menu.items.iter().enumerate() {
+            let label = match item {
+                MenuItem::Theme => { self.texts.format(item.text_key(), &[("theme", &self.theme.name)]) },
+                MenuItem::Language => { self.texts.format(item.text_key(), &[("language", &self.texts.name())]) },
+                MenuItem::Sound => {
+                    let state = self.texts.text(if sound_on { "menu.on" } else { "menu.off" });
+                    self.texts.format(item.text_key(), &[("state", &state)])
+                },
+                _ => { self.texts.text(item.text_key()).to_string() },
+            };
+            let text = if i == menu.selected {
+                format!("> {} <", label)
+            } else {
+                label
+            };
+            let text_render_data = self.text_data(text.as_str(), self.theme.status_text);
+            let rect = rect!(box_x, box_y + 20 + (i as u32 * item_height) as i32, box_width, item_height);
+            self.render_text(canvas, &rect, &text_render_data, false);
+            if i == menu.selected && self.accessibility.focus_outline {
+                canvas.set_draw_color(self.theme.cursor);
+                for j in 0..3 {
+                    let width = rect.width().saturating_sub(20 + 2 * j as u32);
+                    let height = rect.height().saturating_sub(2 * j as u32);
+                    // a tiny window has no room for the outline
+                    if width == 0 || height == 0 {
+                        break;
+                    }
+                    canvas.draw_rect(rect!(rect.x() + 10 + j, rect.y() + j, width, height))
+                        .expect("Error on Drawing Rectangle on canvas");
+                }
+            }
+        }
+    }
+
     /// renders the cardboard to the screen.
-    /// it iterates over each row and col.
+    /// it iterates over each row and col of the public view of the game, so the Renderer only
+    /// knows about the cards everybody can see.
     /// If a coordinate is empty (card already taken) it shows an empty area (no rectangle drawn).
     /// If a coordinate is not empty and not opened it shows a rectangle
     /// If a coordinate is not empty and opened it shows the rectangle and the title of the card.
-    pub fn render(&self, canvas : &mut Canvas<Window>, game : &Game) {
-        canvas.set_draw_color(self.clear_color);
-        canvas.fill_rect(self.screen_area).ok().unwrap_or_default();
-
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
-        let padding: u32  = 5;
-        let card_width: u32 = (((self.screen_area.width()) / 8) - (padding*8/6)).try_into().unwrap();
-        let card_height: u32= (((self.screen_area.height()) / 8) - (padding*8/6)).try_into().unwrap();
-        //let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-        //let font_path = "./python/fonts/OpenSans-Bold.ttf";
-
-        for row in 0..8 {
-            for col in 0..8 {
-
-                let card = game.card_at(row.try_into().unwrap(), col.try_into().unwrap());
-                match card {
-                    Some(c) => {
-                        let x_offset:u32 = self.screen_area.left().try_into().unwrap();
-                        let y_offset:u32 = self.screen_area.top().try_into().unwrap();
-                        let x: i32 = (x_offset+padding+col*(card_width + padding)).try_into().unwrap();
-                        let y: i32= (y_offset+padding+row*(card_height+padding)).try_into().unwrap();
-                        let r = Rect::new(x, y, card_width, card_height);
-                        canvas.draw_rect(r).expect("Error on Drawing Rectangle on canvas");
-                        let coord = Coord(row.try_into().unwrap(), col.try_into().unwrap());
-                        if game.is_clicked(&coord) {
-                            self.render_card(canvas, c, y, x, card_height, card_width);
+    /// Running animations change how the cards are drawn: a flipping card gets narrower and
+    /// wider again, a shaking card is moved sideways and the cards of a found pair are drawn on
+    /// their way to the player.
+    /// On top of that it draws the keyboard cursor and the menu according to the UiState.
+    pub fn render(&self, canvas : &mut Canvas<Window>, game : &Game, ui : &UiState) {
+        self.render_view(canvas, &game.public_view(), ui, None);
+    }
+
+    /// Like render(), but draws from the public view only, as network clients get it. local are
+    /// the players at this computer, None if everybody plays here. In a network game the cursor
+    /// is only drawn on turns of the local players and a message tells whose turn it is.
+    pub fn render_view(&self, canvas : &mut Canvas<Window>, view : &PublicView, ui : &UiState, local : Option<&[usize]>) {
+        // the whole window is cleared since animated cards move outside of the screen_area
+        canvas.set_draw_color(self.theme.background);
+        canvas.clear();
+
+        let now = Instant::now();
+
+        for row in 0..view.height {
+            for col in 0..view.width {
+                let coord = Coord(row, col);
+                let cell = view.cell(&coord).unwrap_or(&CellView::Removed);
+                if *cell != CellView::Removed {
+                    let mut r = self.layout.card_rect(&coord);
+                    r.x += ui.animations.shake_offset(&coord, now);
+                    let mut face_up = matches!(cell, CellView::FaceUp { .. });
+                    if let Some(p) = ui.animations.flip_progress(&coord, now) {
+                        // the card shows its back during the first half of the flip
+                        let scale = (1.0 - 2.0 * p).abs();
+                        let w = ((r.width() as f32 * scale) as u32).max(1);
+                        r = rect!(r.x() + ((r.width() - w) / 2) as i32, r.y(), w, r.height());
+                        face_up = face_up && p >= 0.5;
+                    }
+                    let fill = match (face_up, cell) {
+                        (true, CellView::FaceUp { kind : CardKind::Joker | CardKind::Shuffle, .. }) => { self.theme.card_special },
+                        (true, _) => { self.theme.card_front },
+                        (false, _) => { self.theme.card_back },
+                    };
+                    self.render_card_frame(canvas, &r, fill);
+                    if let (true, CellView::FaceUp { card_type, title, .. }) = (face_up, cell) {
+                        self.render_card(canvas, title, r.y(), r.x(), r.height(), r.width());
+                        if self.accessibility.shape_markers {
+                            self.render_marker(canvas, *card_type, &r);
                         }
-                    },
-                    None => {},
-                } // match
+                    }
+                }
             } // for col
         } // for row
-        self.render_status_box(canvas, game);
-        if game.game_state() == GameState::NextUser {
-            self.render_check_result_box(canvas, game);
+        self.render_slides(canvas, ui, now);
+        for player_id in ui.waiting.iter().filter(|p| **p != view.current_player_id) {
+            self.render_cursor(canvas, &ui.cursor(*player_id), true);
+        }
+        let local_turn = local.is_none_or(|l| l.contains(&view.current_player_id));
+        if local_turn && (ui.show_cursor || self.accessibility.focus_outline) {
+            let cursor = ui.cursor(view.current_player_id);
+            self.render_cursor(canvas, &cursor, false);
+        }
+        self.render_players_box(canvas, view, local);
+        self.render_status_box(canvas, view);
+        match (&view.state, local) {
+            (GameState::NextUser, _) => { self.render_check_result_box(canvas, view); },
+            (GameState::GameSetup | GameState::GameOver, _) | (_, None) => {},
+            (_, Some(local)) => { self.render_turn_box(canvas, view, local); },
+        }
+        if let Some(menu) = &ui.menu {
+            self.render_menu(canvas, menu, !ui.volumes.muted);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+
+/// The font that is built into the binary. It is used when the font of the theme can't be found.
+pub static EMBEDDED_FONT : &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// How deep the system font directories are searched
+const MAX_SEARCH_DEPTH : usize = 4;
+
+/// Where a font comes from: a font file or the font built into the binary.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontSource {
+    File(PathBuf),
+    Embedded,
+}
+
+impl FontSource {
+    /// Loads the font in the given size.
+    pub fn load<'ttf>(&self, ttf_context : &'ttf Sdl2TtfContext, point_size : u16) -> Result<Font<'ttf, 'static>, String> {
+        match self {
+            FontSource::File(path) => { ttf_context.load_font(path, point_size) },
+            FontSource::Embedded => {
+                let rwops = RWops::from_bytes(EMBEDDED_FONT)?;
+                ttf_context.load_font_from_rwops(rwops, point_size)
+            },
+        }
+    }
+}
+
+/// The directories fonts are searched in, after the font path itself and the configured
+/// directories: the directory of the executable and the usual system font directories.
+fn default_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(|d| d.to_path_buf())) {
+        dirs.push(exe_dir.join("fonts"));
+        dirs.push(exe_dir.join("python").join("fonts"));
+        dirs.push(exe_dir);
+    }
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+        dirs.push(home.join("Library/Fonts"));
+    }
+    for dir in ["/usr/share/fonts", "/usr/local/share/fonts", "/Library/Fonts", "/System/Library/Fonts"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(windir) = std::env::var_os("WINDIR").map(PathBuf::from) {
+        dirs.push(windir.join("Fonts"));
+    }
+    dirs
+}
+
+/// Searches the directory (and its sub directories up to the given depth) for a file with the given name.
+fn find_in_dir(dir : &Path, file_name : &str, depth : usize) -> Option<PathBuf> {
+    let candidate = dir.join(file_name);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut sub_dirs : Vec<PathBuf> = fs::read_dir(dir).ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    sub_dirs.sort();
+    sub_dirs.iter().find_map(|sub_dir| find_in_dir(sub_dir, file_name, depth - 1))
+}
+
+/// Returns all existing font files for the requested font, best match first:
+/// the path as given, then the file name in the configured directories, the directory of the
+/// executable and the system font directories.
+pub fn font_candidates(requested : &str, configured_dirs : &[PathBuf]) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    let requested_path = Path::new(requested);
+    if requested_path.is_file() {
+        candidates.push(requested_path.to_path_buf());
+    }
+    let file_name = match requested_path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n,
+        None => return candidates,
+    };
+    let dirs = configured_dirs.iter().cloned().chain(default_font_dirs());
+    for dir in dirs {
+        if let Some(found) = find_in_dir(&dir, file_name, MAX_SEARCH_DEPTH) {
+            if !candidates.contains(&found) {
+                candidates.push(found);
+            }
+        }
+    }
+    candidates
+}
+
+/// Finds a usable font for the requested font path. Every candidate file is test-loaded, the
+/// first one that works is used. If none works the embedded font is used. Returns an error if
+/// not even the embedded font can be loaded.
+pub fn resolve_font(ttf_context : &Sdl2TtfContext, requested : &str, configured_dirs : &[PathBuf]) -> Result<FontSource, String> {
+    for path in font_candidates(requested, configured_dirs) {
+        let source = FontSource::File(path);
+        match source.load(ttf_context, 12) {
+            Ok(_) => { return Ok(source); },
+            Err(e) => { println!("Can't use font {:?}: {}", source, e); },
+        }
+    }
+    println!("Font {} not found, using the built-in font", requested);
+    match FontSource::Embedded.load(ttf_context, 12) {
+        Ok(_) => { Ok(FontSource::Embedded) },
+        Err(e) => {
+            Err(format!("No usable font found. Looked for {} in the current directory, the font directories \
+                         and the system font directories, and the built-in font failed to load: {}", requested, e))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{font_candidates, EMBEDDED_FONT};
+    use std::fs;
+
+    #[test]
+    fn test_embedded_font_is_a_truetype_font() {
+        assert!(EMBEDDED_FONT.len() > 1000);
+        assert_eq!(&EMBEDDED_FONT[0..4], &[0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_font_candidates() {
+        let dir = std::env::temp_dir().join(format!("memory_game_fonts_{}", std::process::id()));
+        let sub_dir = dir.join("some").join("family");
+        fs::create_dir_all(&sub_dir).unwrap();
+        fs::write(sub_dir.join("Test-Font.ttf"), EMBEDDED_FONT).unwrap();
+
+        let font_dirs = vec![dir.clone()];
+        let candidates = font_candidates("./not/here/Test-Font.ttf", &font_dirs);
+        assert_eq!(candidates.first(), Some(&sub_dir.join("Test-Font.ttf")));
+        assert!(font_candidates("./not/here/No-Such-Font-4711.ttf", &font_dirs).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
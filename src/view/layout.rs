@@ -0,0 +1,186 @@
+use sdl2::rect::Rect;
+
+use crate::model::game_model::Coord;
+
+/// Space between the cards in pixels
+pub const PADDING : u32 = 5;
+/// Space around the areas in pixels
+const MARGIN : u32 = 20;
+/// Height of the status bar in pixels
+const STATUSBAR_HEIGHT : u32 = 40;
+/// Height of the box with the result message in pixels
+const MESSAGE_HEIGHT : u32 = 60;
+/// The players area takes this share of the window width (within the limits below)
+const PLAYERS_AREA_SHARE : f32 = 0.25;
+const PLAYERS_AREA_MIN_WIDTH : u32 = 150;
+const PLAYERS_AREA_MAX_WIDTH : u32 = 400;
+/// Cards may be at most this much wider than high (or higher than wide)
+const MAX_CARD_ASPECT : f32 = 4.0 / 3.0;
+
+/// The areas of the window and the size of the cards. The layout is calculated from the window size
+/// and the size of the field, and recalculated whenever the window is resized. It is used for
+/// drawing the cards as well as for finding the card under the mouse, so both always agree.
+///
+/// The window is split like this:
+///
+/// ```text
+/// +------------------------------+---------+
+/// |                              |         |
+/// |         screen_area          | players |
+/// |        (the cards)           |  area   |
+/// |                              |         |
+/// +------------------------------+---------+
+/// |             statusbar_area             |
+/// +----------------------------------------+
+/// ```
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub rows : usize,
+    pub cols : usize,
+    pub screen_area : Rect,
+    pub players_area : Rect,
+    pub statusbar_area : Rect,
+    pub message_area : Rect,
+    pub card_width : u32,
+    pub card_height : u32,
+}
+
+impl Layout {
+    pub fn new(window_width : u32, window_height : u32, rows : usize, cols : usize) -> Self {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+
+        let statusbar_area = Rect::new(0, window_height.saturating_sub(STATUSBAR_HEIGHT) as i32, window_width.max(1), STATUSBAR_HEIGHT);
+
+        let players_width = ((window_width as f32 * PLAYERS_AREA_SHARE) as u32)
+            .clamp(PLAYERS_AREA_MIN_WIDTH, PLAYERS_AREA_MAX_WIDTH);
+        let avail_height = window_height.saturating_sub(STATUSBAR_HEIGHT + 2 * MARGIN).max(1);
+        let avail_width = window_width.saturating_sub(players_width + 3 * MARGIN).max(1);
+
+        // each card gets a cell of the board (card plus padding), the cards are kept square-ish
+        let mut card_width = (avail_width / cols as u32).saturating_sub(PADDING).max(1);
+        let mut card_height = (avail_height / rows as u32).saturating_sub(PADDING).max(1);
+        card_width = card_width.min((card_height as f32 * MAX_CARD_ASPECT) as u32).max(1);
+        card_height = card_height.min((card_width as f32 * MAX_CARD_ASPECT) as u32).max(1);
+        let cell_width = card_width + PADDING;
+        let cell_height = card_height + PADDING;
+
+        let board_width = cell_width * cols as u32 + PADDING;
+        let board_height = cell_height * rows as u32 + PADDING;
+        let board_x = MARGIN + avail_width.saturating_sub(board_width) / 2;
+        let board_y = MARGIN + avail_height.saturating_sub(board_height) / 2;
+        let screen_area = Rect::new(board_x as i32, board_y as i32, board_width, board_height);
+
+        let players_area = Rect::new(
+            (window_width.saturating_sub(players_width + MARGIN)) as i32,
+            MARGIN as i32,
+            players_width,
+            avail_height);
+
+        let message_area = Rect::new(
+            screen_area.left(),
+            screen_area.top() + (board_height.saturating_sub(MESSAGE_HEIGHT) / 2) as i32,
+            board_width,
+            MESSAGE_HEIGHT);
+
+        Layout {
+            rows,
+            cols,
+            screen_area,
+            players_area,
+            statusbar_area,
+            message_area,
+            card_width,
+            card_height,
+        }
+    }
+
+    /// Returns the rectangle of the card slot at the given coordinate
+    pub fn card_rect(&self, coord : &Coord) -> Rect {
+        let x = self.screen_area.left() + (PADDING + coord.1 as u32 * (self.card_width + PADDING)) as i32;
+        let y = self.screen_area.top() + (PADDING + coord.0 as u32 * (self.card_height + PADDING)) as i32;
+        Rect::new(x, y, self.card_width, self.card_height)
+    }
+
+    /// Returns the coordinate of the card slot at the given pixel position, or None if the position
+    /// is not on a card (outside of the board or on the padding between the cards).
+    pub fn coord_at(&self, y : i32, x : i32) -> Option<Coord> {
+        let rel_x = x - self.screen_area.left() - PADDING as i32;
+        let rel_y = y - self.screen_area.top() - PADDING as i32;
+        if rel_x < 0 || rel_y < 0 {
+            return None;
+        }
+        let cell_width = (self.card_width + PADDING) as i32;
+        let cell_height = (self.card_height + PADDING) as i32;
+        let col = (rel_x / cell_width) as usize;
+        let row = (rel_y / cell_height) as usize;
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        if rel_x % cell_width >= self.card_width as i32 || rel_y % cell_height >= self.card_height as i32 {
+            return None;
+        }
+        Some(Coord(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, MAX_CARD_ASPECT, PADDING};
+    use crate::model::game_model::Coord;
+
+    /// Window sizes from wide to tall down to tiny, and field sizes from square to long
+    const WINDOWS : [(u32, u32); 7] = [(1600, 1000), (800, 600), (2000, 300), (500, 1400), (1024, 1024), (320, 240), (40, 30)];
+    const FIELDS : [(usize, usize); 5] = [(6, 6), (2, 8), (8, 2), (4, 5), (1, 2)];
+
+    fn layouts() -> impl Iterator<Item = Layout> {
+        WINDOWS.iter().flat_map(|(w, h)| FIELDS.iter().map(move |(rows, cols)| Layout::new(*w, *h, *rows, *cols)))
+    }
+
+    #[test]
+    fn test_card_under_its_center() {
+        for layout in layouts() {
+            for row in 0..layout.rows {
+                for col in 0..layout.cols {
+                    let c = Coord(row, col);
+                    let center = layout.card_rect(&c).center();
+                    assert_eq!(layout.coord_at(center.y(), center.x()), Some(c.clone()), "{:?}", layout);
+                    let rect = layout.card_rect(&c);
+                    assert!(layout.screen_area.contains_rect(rect), "{:?} is off the board {:?}", c, layout);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_card_between_and_around_cards() {
+        for layout in layouts() {
+            let board = layout.screen_area;
+            let first = layout.card_rect(&Coord(0, 0));
+            let last = layout.card_rect(&Coord(layout.rows - 1, layout.cols - 1));
+            // the padding at the edges of the board and the space around it
+            assert_eq!(layout.coord_at(first.center().y(), board.left()), None);
+            assert_eq!(layout.coord_at(board.top(), first.center().x()), None);
+            assert_eq!(layout.coord_at(last.center().y(), last.right()), None);
+            assert_eq!(layout.coord_at(last.bottom(), last.center().x()), None);
+            assert_eq!(layout.coord_at(first.center().y(), board.left() - 1), None);
+            assert_eq!(layout.coord_at(board.bottom() + 10, first.center().x()), None);
+            assert_eq!(layout.coord_at(-5, -5), None);
+            // the padding between two cards
+            if layout.cols > 1 {
+                assert_eq!(layout.coord_at(first.center().y(), first.right() + PADDING as i32 / 2), None);
+            }
+            if layout.rows > 1 {
+                assert_eq!(layout.coord_at(first.bottom() + PADDING as i32 / 2, first.center().x()), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_card_aspect() {
+        for layout in layouts() {
+            let (w, h) = (layout.card_width as f32, layout.card_height as f32);
+            assert!(w <= h * MAX_CARD_ASPECT && h <= w * MAX_CARD_ASPECT, "cards of {}x{} in {:?}", w, h, layout);
+        }
+    }
+}
@@ -1 +1,9 @@
+pub mod accessibility;
+pub mod animation;
+pub mod announcer;
 pub mod board_view;
+pub mod fonts;
+pub mod layout;
+pub mod sound;
+pub mod theme;
+pub mod ui_state;
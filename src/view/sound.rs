@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::path::Path;
+use std::sync::Arc;
+
+use sdl2::audio::{AudioCVT, AudioCallback, AudioDevice, AudioFormat, AudioSpecDesired, AudioSpecWAV};
+use sdl2::Sdl;
+
+/// All sounds are converted to mono 32 bit float samples at this rate
+pub const SAMPLE_RATE : i32 = 44100;
+
+/// The things that happen in the game that make a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEvent {
+    /// a card is turned face up
+    Flip,
+    /// the two open cards are a pair
+    Match,
+    /// the two open cards are no pair
+    Mismatch,
+    /// the next player gets the turn
+    TurnChange,
+    GameOver,
+}
+
+impl SoundEvent {
+    pub const ALL : [SoundEvent; 5] = [SoundEvent::Flip, SoundEvent::Match, SoundEvent::Mismatch, SoundEvent::TurnChange, SoundEvent::GameOver];
+
+    /// The name of the file of this sound in a sound pack
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            SoundEvent::Flip => { "flip.wav" },
+            SoundEvent::Match => { "match.wav" },
+            SoundEvent::Mismatch => { "mismatch.wav" },
+            SoundEvent::TurnChange => { "turn.wav" },
+            SoundEvent::GameOver => { "game_over.wav" },
+        }
+    }
+}
+
+/// The name of the background music file in a sound pack
+const MUSIC_FILE_NAME : &str = "music.wav";
+
+/// The volumes (0.0 to 1.0) of the sound effects and of the background music.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Volumes {
+    pub effects : f32,
+    pub music : f32,
+    /// if set nothing is played, the volumes are kept for unmuting
+    pub muted : bool,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Volumes {
+            effects : 0.8,
+            music : 0.3,
+            muted : false,
+        }
+    }
+}
+
+impl Volumes {
+    pub fn effects_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.effects }
+    }
+
+    pub fn music_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.music }
+    }
+}
+
+/// Creates a sequence of sine tones, given as (frequency in Hz, duration in seconds). Every tone
+/// fades in quickly and fades out until its end, so there are no clicks between the tones.
+fn tones(notes : &[(f32, f32)], amplitude : f32) -> Vec<f32> {
+    let mut samples = Vec::new();
+    for (freq, secs) in notes {
+        let len = (secs * SAMPLE_RATE as f32) as usize;
+        let attack = (0.005 * SAMPLE_RATE as f32) as usize;
+        for i in 0..len {
+            let envelope = if i < attack { i as f32 / attack as f32 } else { 1.0 - (i - attack) as f32 / (len - attack) as f32 };
+            samples.push(amplitude * envelope * (TAU * freq * i as f32 / SAMPLE_RATE as f32).sin());
+        }
+    }
+    samples
+}
+
+/// Loads a WAV file and converts it to mono float samples at SAMPLE_RATE.
+fn load_wav(path : &Path) -> Result<Vec<f32>, String> {
+    let wav = AudioSpecWAV::load_wav(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let cvt = AudioCVT::new(wav.format, wav.channels, wav.freq, AudioFormat::f32_sys(), 1, SAMPLE_RATE)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let bytes = cvt.convert(wav.buffer().to_vec());
+    Ok(bytes.chunks_exact(4).map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]])).collect())
+}
+
+/// The sounds of the game: one sound per SoundEvent and the background music.
+///
+/// A sound pack is a directory with WAV files named after the events (see
+/// SoundEvent::file_name()) and "music.wav". Files that are missing in the pack are replaced by
+/// the synthesized sounds, so a pack can also replace just some of the sounds.
+pub struct SoundPack {
+    effects : HashMap<SoundEvent, Arc<Vec<f32>>>,
+    pub music : Option<Arc<Vec<f32>>>,
+}
+
+impl SoundPack {
+    /// Simple tones generated at start up, so the game has sound without any sound files.
+    pub fn synthesized() -> Self {
+        let mut effects = HashMap::new();
+        effects.insert(SoundEvent::Flip, Arc::new(tones(&[(880.0, 0.05)], 0.4)));
+        effects.insert(SoundEvent::Match, Arc::new(tones(&[(660.0, 0.08), (880.0, 0.08), (1320.0, 0.12)], 0.4)));
+        effects.insert(SoundEvent::Mismatch, Arc::new(tones(&[(300.0, 0.15), (220.0, 0.25)], 0.4)));
+        effects.insert(SoundEvent::TurnChange, Arc::new(tones(&[(520.0, 0.1)], 0.3)));
+        effects.insert(SoundEvent::GameOver, Arc::new(tones(&[(523.0, 0.15), (659.0, 0.15), (784.0, 0.15), (1047.0, 0.4)], 0.4)));
+        let melody = [262.0, 330.0, 392.0, 330.0, 294.0, 349.0, 440.0, 349.0];
+        let notes : Vec<(f32, f32)> = melody.iter().map(|f| (*f, 0.4)).collect();
+        SoundPack {
+            effects,
+            music : Some(Arc::new(tones(&notes, 0.2))),
+        }
+    }
+
+    /// Loads the sound pack in the given directory. Fails if the directory doesn't exist, files
+    /// that can't be loaded are reported and replaced by the synthesized sounds.
+    pub fn load(dir : &Path) -> Result<SoundPack, String> {
+        if !dir.is_dir() {
+            return Err(format!("Sound pack {} not found", dir.display()));
+        }
+        let mut pack = SoundPack::synthesized();
+        for event in SoundEvent::ALL {
+            let path = dir.join(event.file_name());
+            if path.is_file() {
+                match load_wav(&path) {
+                    Ok(samples) => { pack.effects.insert(event, Arc::new(samples)); },
+                    Err(e) => { println!("Can't load sound {}", e); },
+                }
+            }
+        }
+        let path = dir.join(MUSIC_FILE_NAME);
+        if path.is_file() {
+            match load_wav(&path) {
+                Ok(samples) => { pack.music = Some(Arc::new(samples)); },
+                Err(e) => { println!("Can't load music {}", e); },
+            }
+        }
+        Ok(pack)
+    }
+
+    pub fn effect(&self, event : SoundEvent) -> Option<Arc<Vec<f32>>> {
+        self.effects.get(&event).cloned()
+    }
+}
+
+/// A sound that is being played
+struct Voice {
+    samples : Arc<Vec<f32>>,
+    pos : usize,
+}
+
+/// Mixes the playing sound effects and the looping background music. SDL calls it from the
+/// audio thread whenever the device needs more samples.
+pub struct Mixer {
+    voices : Vec<Voice>,
+    music : Option<Voice>,
+    pub volumes : Volumes,
+}
+
+impl Mixer {
+    pub fn new(music : Option<Arc<Vec<f32>>>, volumes : Volumes) -> Self {
+        Mixer {
+            voices : Vec::new(),
+            music : music.filter(|m| !m.is_empty()).map(|samples| Voice { samples, pos : 0 }),
+            volumes,
+        }
+    }
+
+    pub fn play(&mut self, samples : Arc<Vec<f32>>) {
+        self.voices.push(Voice { samples, pos : 0 });
+    }
+
+    /// Fills the buffer with the mix of all voices and the music. Finished voices are dropped.
+    pub fn mix(&mut self, out : &mut [f32]) {
+        let effects_gain = self.volumes.effects_gain();
+        let music_gain = self.volumes.music_gain();
+        for sample in out.iter_mut() {
+            let mut value = 0.0;
+            for voice in self.voices.iter_mut() {
+                if let Some(s) = voice.samples.get(voice.pos) {
+                    value += s * effects_gain;
+                    voice.pos += 1;
+                }
+            }
+            if let Some(music) = self.music.as_mut() {
+                value += music.samples[music.pos] * music_gain;
+                music.pos = (music.pos + 1) % music.samples.len();
+            }
+            *sample = value.clamp(-1.0, 1.0);
+        }
+        self.voices.retain(|v| v.pos < v.samples.len());
+    }
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    fn callback(&mut self, out : &mut [f32]) {
+        self.mix(out);
+    }
+}
+
+/// Plays the sounds of the game. If there is no audio device (e.g. on a server or with the SDL
+/// dummy driver in CI) the player is disabled and playing does nothing.
+pub struct SoundPlayer {
+    device : Option<AudioDevice<Mixer>>,
+    pack : SoundPack,
+    volumes : Volumes,
+}
+
+impl SoundPlayer {
+    pub fn new(sdl_context : &Sdl, pack : SoundPack, volumes : Volumes) -> Self {
+        let desired = AudioSpecDesired {
+            freq : Some(SAMPLE_RATE),
+            channels : Some(1),
+            samples : None,
+        };
+        let music = pack.music.clone();
+        let device = sdl_context.audio()
+            .and_then(|audio| audio.open_playback(None, &desired, |_spec| Mixer::new(music, volumes)));
+        let device = match device {
+            Ok(device) => {
+                device.resume();
+                Some(device)
+            },
+            Err(e) => {
+                println!("Sound disabled, no audio device: {}", e);
+                None
+            },
+        };
+        SoundPlayer {
+            device,
+            pack,
+            volumes,
+        }
+    }
+
+    pub fn play(&mut self, event : SoundEvent) {
+        if let (Some(device), Some(samples)) = (self.device.as_mut(), self.pack.effect(event)) {
+            device.lock().play(samples);
+        }
+    }
+
+    /// Changes the volumes (if they changed).
+    pub fn set_volumes(&mut self, volumes : Volumes) {
+        if self.volumes == volumes {
+            return;
+        }
+        self.volumes = volumes;
+        if let Some(device) = self.device.as_mut() {
+            device.lock().volumes = volumes;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mixer, SoundEvent, SoundPack, Volumes};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_mixer() {
+        let volumes = Volumes { effects : 0.5, music : 0.0, muted : false };
+        let mut mixer = Mixer::new(None, volumes);
+        mixer.play(Arc::new(vec![1.0; 4]));
+        mixer.play(Arc::new(vec![0.5; 2]));
+        let mut out = [9.0; 6];
+        mixer.mix(&mut out);
+        assert_eq!(out, [0.75, 0.75, 0.5, 0.5, 0.0, 0.0]);
+        assert!(mixer.voices.is_empty());
+
+        mixer.volumes.muted = true;
+        mixer.play(Arc::new(vec![1.0; 4]));
+        mixer.mix(&mut out);
+        assert_eq!(out, [0.0; 6]);
+    }
+
+    #[test]
+    fn test_music_loops() {
+        let volumes = Volumes { effects : 1.0, music : 1.0, muted : false };
+        let mut mixer = Mixer::new(Some(Arc::new(vec![0.1, 0.2])), volumes);
+        mixer.play(Arc::new(vec![1.0]));
+        let mut out = [0.0; 5];
+        mixer.mix(&mut out);
+        assert_eq!(out, [1.0, 0.2, 0.1, 0.2, 0.1]);
+    }
+
+    #[test]
+    fn test_synthesized_pack() {
+        let pack = SoundPack::synthesized();
+        for event in SoundEvent::ALL {
+            let samples = pack.effect(event).unwrap();
+            assert!(!samples.is_empty());
+            assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        }
+        assert!(pack.music.is_some());
+        assert!(SoundPack::load(std::path::Path::new("./no/such/pack")).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::Path;
+
+use sdl2::pixels::Color;
+
+/// The font all built-in themes use
+pub const DEFAULT_FONT_PATH : &str = "./python/fonts/OpenSans-Bold.ttf";
+
+/// The look of the game: colors, fonts and font sizes.
+///
+/// Themes can be loaded from files with one "key = value" pair per line. Colors are given as
+/// "#rrggbb" or "#rrggbbaa", lines starting with '#' are comments. Keys that are missing in the
+/// file keep the value of the classic theme. Example:
+///
+/// ```text
+/// name = forest
+/// background = #2e4a2e
+/// card_back = #5b3a29
+/// card_text = #ffffff
+/// font_size = 20
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name : String,
+    pub background : Color,
+    /// fill color of cards showing their back
+    pub card_back : Color,
+    /// fill color of open cards
+    pub card_front : Color,
+    /// fill color of open jokers and shuffle cards
+    pub card_special : Color,
+    pub card_border : Color,
+    pub card_text : Color,
+    pub status_text : Color,
+    pub message_text : Color,
+    pub cursor : Color,
+    pub font_path : String,
+    /// font size for status bar, player list, messages and menu
+    pub font_size : u16,
+    /// font size for the titles of the cards
+    pub card_font_size : u16,
+}
+
+impl Theme {
+    /// The original look of the game: light blue background, black card outlines and red text.
+    pub fn classic() -> Self {
+        Theme {
+            name : "classic".to_string(),
+            background : Color::RGB(64, 192, 255),
+            card_back : Color::RGB(64, 192, 255),
+            card_front : Color::RGB(64, 192, 255),
+            card_special : Color::RGB(255, 215, 0),
+            card_border : Color::RGB(0, 0, 0),
+            card_text : Color::RGBA(255, 0, 0, 255),
+            status_text : Color::RGBA(255, 0, 0, 255),
+            message_text : Color::RGBA(255, 0, 0, 255),
+            cursor : Color::RGB(255, 215, 0),
+            font_path : DEFAULT_FONT_PATH.to_string(),
+            font_size : 24,
+            card_font_size : 24,
+        }
+    }
+
+    /// A dark theme which is easier on the eyes in the evening.
+    pub fn dark() -> Self {
+        Theme {
+            name : "dark".to_string(),
+            background : Color::RGB(30, 30, 36),
+            card_back : Color::RGB(60, 64, 80),
+            card_front : Color::RGB(220, 220, 210),
+            card_special : Color::RGB(210, 170, 70),
+            card_border : Color::RGB(120, 124, 140),
+            card_text : Color::RGB(20, 20, 30),
+            status_text : Color::RGB(230, 230, 230),
+            message_text : Color::RGB(255, 200, 80),
+            cursor : Color::RGB(255, 170, 0),
+            font_path : DEFAULT_FONT_PATH.to_string(),
+            font_size : 24,
+            card_font_size : 24,
+        }
+    }
+
+    /// The theme with black, white and yellow colors instead of its own. Name and fonts stay.
+    pub fn high_contrast(&self) -> Self {
+        Theme {
+            background : Color::RGB(0, 0, 0),
+            card_back : Color::RGB(255, 255, 255),
+            card_front : Color::RGB(0, 0, 0),
+            card_special : Color::RGB(0, 0, 160),
+            card_border : Color::RGB(255, 255, 0),
+            card_text : Color::RGB(255, 255, 255),
+            status_text : Color::RGB(255, 255, 255),
+            message_text : Color::RGB(255, 255, 0),
+            cursor : Color::RGB(0, 255, 255),
+            ..self.clone()
+        }
+    }
+
+    pub fn built_in() -> Vec<Theme> {
+        vec![Theme::classic(), Theme::dark()]
+    }
+
+    /// Parses a theme from the content of a theme file. Errors name the line number.
+    pub fn parse(text : &str) -> Result<Theme, String> {
+        let mut theme = Theme::classic();
+        theme.name = String::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => { (k.trim(), v.trim()) },
+                None => { return Err(format!("line {}: expected \"key = value\"", i + 1)); },
+            };
+            let color = || parse_color(value).map_err(|e| format!("line {}: {}", i + 1, e));
+            let size = || match value.parse::<u16>() {
+                Ok(s) if s > 0 => Ok(s),
+                _ => Err(format!("line {}: invalid font size \"{}\"", i + 1, value)),
+            };
+            match key {
+                "name" => { theme.name = value.to_string(); },
+                "background" => { theme.background = color()?; },
+                "card_back" => { theme.card_back = color()?; },
+                "card_front" => { theme.card_front = color()?; },
+                "card_special" => { theme.card_special = color()?; },
+                "card_border" => { theme.card_border = color()?; },
+                "card_text" => { theme.card_text = color()?; },
+                "status_text" => { theme.status_text = color()?; },
+                "message_text" => { theme.message_text = color()?; },
+                "cursor" => { theme.cursor = color()?; },
+                "font" => { theme.font_path = value.to_string(); },
+                "font_size" => { theme.font_size = size()?; },
+                "card_font_size" => { theme.card_font_size = size()?; },
+                _ => { return Err(format!("line {}: unknown key \"{}\"", i + 1, key)); },
+            }
+        }
+        if theme.name.is_empty() {
+            return Err("the theme has no name".to_string());
+        }
+        Ok(theme)
+    }
+
+    pub fn from_file(path : &Path) -> Result<Theme, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Theme::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Parses a color given as "#rrggbb" or "#rrggbbaa".
+fn parse_color(value : &str) -> Result<Color, String> {
+    let hex = value.strip_prefix('#').unwrap_or("");
+    if (hex.len() != 6 && hex.len() != 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid color \"{}\", expected #rrggbb or #rrggbbaa", value));
+    }
+    let component = |i : usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    let alpha = if hex.len() == 8 { component(6) } else { 255 };
+    Ok(Color::RGBA(component(0), component(2), component(4), alpha))
+}
+
+/// The available themes and which one is in use.
+pub struct ThemeSet {
+    pub themes : Vec<Theme>,
+    pub current : usize,
+}
+
+impl ThemeSet {
+    pub fn new(themes : Vec<Theme>) -> Self {
+        ThemeSet {
+            themes,
+            current : 0,
+        }
+    }
+
+    /// The built-in themes plus all "*.theme" files found in the given directory. Files that
+    /// can't be read are reported and skipped. A theme from a file replaces a built-in theme
+    /// with the same name.
+    pub fn load(dir : Option<&Path>) -> Self {
+        let mut themes = Theme::built_in();
+        if let Some(dir) = dir {
+            let mut paths : Vec<_> = match fs::read_dir(dir) {
+                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+                Err(e) => {
+                    println!("Can't read theme directory {}: {}", dir.display(), e);
+                    Vec::new()
+                },
+            };
+            paths.sort();
+            for path in paths.iter().filter(|p| p.extension().is_some_and(|ext| ext == "theme")) {
+                match Theme::from_file(path) {
+                    Ok(theme) => {
+                        themes.retain(|t| t.name != theme.name);
+                        themes.push(theme);
+                    },
+                    Err(e) => { println!("Skipping theme {}", e); },
+                }
+            }
+        }
+        ThemeSet::new(themes)
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+
+    /// Switches to the next theme.
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.themes.len();
+    }
+
+    /// Switches to the theme with the given name. Returns false if there is no such theme.
+    pub fn select(&mut self, name : &str) -> bool {
+        match self.themes.iter().position(|t| t.name == name) {
+            Some(idx) => { self.current = idx; true },
+            None => { false },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Theme, ThemeSet};
+    use sdl2::pixels::Color;
+
+    #[test]
+    fn test_parse_theme() {
+        let text = "# a comment\nname = forest\nbackground = #2e4a2e\ncard_text = #ffffff80\nfont_size = 20\n";
+        let theme = Theme::parse(text).unwrap();
+        assert_eq!(theme.name, "forest");
+        assert_eq!(theme.background, Color::RGB(0x2e, 0x4a, 0x2e));
+        assert_eq!(theme.card_text, Color::RGBA(255, 255, 255, 0x80));
+        assert_eq!(theme.font_size, 20);
+        // not given in the file -> classic
+        assert_eq!(theme.card_border, Theme::classic().card_border);
+    }
+
+    #[test]
+    fn test_parse_theme_errors() {
+        assert_eq!(Theme::parse("name = x\nbackground = blue").unwrap_err(),
+            "line 2: invalid color \"blue\", expected #rrggbb or #rrggbbaa");
+        assert_eq!(Theme::parse("name = x\nsparkle = #ffffff").unwrap_err(), "line 2: unknown key \"sparkle\"");
+        assert_eq!(Theme::parse("name = x\nfont_size = 0").unwrap_err(), "line 2: invalid font size \"0\"");
+        assert!(Theme::parse("background = #000000").is_err());
+    }
+
+    #[test]
+    fn test_theme_set() {
+        let mut themes = ThemeSet::load(None);
+        assert_eq!(themes.current().name, "classic");
+        themes.next();
+        assert_eq!(themes.current().name, "dark");
+        themes.next();
+        assert_eq!(themes.current().name, "classic");
+        assert!(themes.select("dark"));
+        assert!(!themes.select("neon"));
+        assert_eq!(themes.current().name, "dark");
+    }
+
+    #[test]
+    fn test_shipped_themes_load() {
+        let themes = ThemeSet::load(Some(std::path::Path::new("themes")));
+        assert!(themes.themes.iter().any(|t| t.name == "forest"));
+    }
+}
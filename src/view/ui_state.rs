@@ -0,0 +1,119 @@
+use crate::model::game_model::Coord;
+use crate::view::animation::Animator;
+use crate::view::sound::Volumes;
+
+/// The entries of the in-game menu (opened with Escape).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuItem {
+    Resume,
+    NewGame,
+    /// switches to the next theme
+    Theme,
+    /// switches to the next language
+    Language,
+    /// switches the sound on and off
+    Sound,
+    Quit,
+}
+
+impl MenuItem {
+    /// The key of the label in the language files
+    pub fn text_key(&self) -> &'static str {
+        match self {
+            MenuItem::Resume => { "menu.resume" },
+            MenuItem::NewGame => { "menu.new_game" },
+            MenuItem::Theme => { "menu.theme" },
+            MenuItem::Language => { "menu.language" },
+            MenuItem::Sound => { "menu.sound" },
+            MenuItem::Quit => { "menu.quit" },
+        }
+    }
+}
+
+/// The in-game menu. It is a simple list of items with one of them being selected.
+pub struct Menu {
+    pub items : Vec<MenuItem>,
+    pub selected : usize,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Menu {
+            items : vec![MenuItem::Resume, MenuItem::NewGame, MenuItem::Theme, MenuItem::Language, MenuItem::Sound, MenuItem::Quit],
+            selected : 0,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn selected_item(&self) -> MenuItem {
+        self.items[self.selected]
+    }
+}
+
+impl Default for Menu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// State of the user interface that is not part of the game itself, i.e. the positions of the
+/// cursors and whether the menu is open. It is owned by the controller and read by the Renderer.
+/// Every player has an own cursor (so each player can steer it with an own game controller), the
+/// cursor of the current player is the one that is shown. Players who move their cursor while
+/// waiting for their turn get it shown as well, thinner. The running animations and the sound
+/// volumes are kept here as well.
+pub struct UiState {
+    pub cursors : Vec<Coord>,
+    pub show_cursor : bool,
+    /// the players who have moved their cursor while it isn't their turn
+    pub waiting : Vec<usize>,
+    pub menu : Option<Menu>,
+    pub animations : Animator,
+    pub volumes : Volumes,
+}
+
+impl UiState {
+    pub fn new() -> Self {
+        UiState {
+            cursors : Vec::new(),
+            show_cursor : false,
+            waiting : Vec::new(),
+            menu : None,
+            animations : Animator::new(true),
+            volumes : Volumes::default(),
+        }
+    }
+
+    /// Returns the cursor position of the given player.
+    pub fn cursor(&self, player_id : usize) -> Coord {
+        match self.cursors.get(player_id) {
+            Some(c) => { c.clone() },
+            None => { Coord(0, 0) },
+        }
+    }
+
+    pub fn set_cursor(&mut self, player_id : usize, coord : Coord) {
+        if self.cursors.len() <= player_id {
+            self.cursors.resize(player_id + 1, Coord(0, 0));
+        }
+        self.cursors[player_id] = coord;
+    }
+
+    pub fn reset_cursors(&mut self) {
+        self.cursors.clear();
+        self.waiting.clear();
+    }
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,103 @@
+/* Plays a game through the C interface, see tests/ffi.rs. Exits with 1 on the first failed check. */
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "memory_game.h"
+
+#define CHECK(cond) do { if (!(cond)) { fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); return 1; } } while (0)
+
+#define ROWS 2
+#define COLS 4
+
+/* flips the card and remembers its type in known */
+static int flip(MgGame *game, size_t row, size_t col, int known[ROWS][COLS]) {
+    MgCell cell;
+    if (mg_game_flip(game, row, col) != MG_ERROR_OK) return 0;
+    if (mg_game_cell(game, row, col, &cell) != MG_ERROR_OK || cell.state != MG_CELL_STATE_FACE_UP) return 0;
+    known[row][col] = cell.card_type;
+    return 1;
+}
+
+int main(void) {
+    int known[ROWS][COLS];
+    size_t r, c, pairs, total = 0;
+    int32_t score;
+    MgCell cell;
+    MgGame *game;
+
+    CHECK(mg_game_new(3, 3) == NULL);
+    CHECK(mg_game_new(SIZE_MAX, 2) == NULL);
+    CHECK(mg_game_new(65, 2) == NULL);
+    game = mg_game_new(ROWS, COLS);
+    CHECK(game != NULL);
+    CHECK(mg_game_rows(game) == ROWS && mg_game_cols(game) == COLS);
+    CHECK(mg_game_reset(game, 42) == MG_ERROR_NO_PLAYERS);
+    CHECK(mg_game_add_player(game, "Anna") == MG_ERROR_OK);
+    CHECK(mg_game_add_player(game, "Ben") == MG_ERROR_OK);
+    CHECK(mg_game_add_player(game, NULL) == MG_ERROR_NULL_POINTER);
+    CHECK(mg_game_player_count(game) == 2);
+    CHECK(mg_game_reset(game, 42) == MG_ERROR_OK);
+    CHECK(mg_game_confirm(game) == MG_ERROR_ILLEGAL_ACTION);
+    CHECK(mg_game_flip(game, ROWS, 0) == MG_ERROR_OUT_OF_BOUNDS);
+    CHECK(strcmp(mg_error_message(MG_ERROR_OUT_OF_BOUNDS), "the coordinate is outside of the field") == 0);
+    CHECK(mg_game_cell(game, 0, 0, &cell) == MG_ERROR_OK && cell.state == MG_CELL_STATE_FACE_DOWN && cell.card_type == -1);
+
+    /* play with perfect memory until the game is over */
+    memset(known, -1, sizeof(known));
+    while (mg_game_expected_action(game) != MG_EXPECTED_NEW_GAME) {
+        size_t r1 = 0, c1 = 0, r2 = 0, c2 = 0;
+        int found = 0;
+        CHECK(mg_game_expected_action(game) == MG_EXPECTED_FLIP_FIRST_CARD);
+        /* a card face down that hasn't been seen yet, otherwise any card face down */
+        for (r = 0; r < ROWS && !found; r++)
+            for (c = 0; c < COLS && !found; c++)
+                if (mg_game_cell(game, r, c, &cell) == MG_ERROR_OK && cell.state == MG_CELL_STATE_FACE_DOWN
+                        && known[r][c] == -1) {
+                    r1 = r; c1 = c; found = 1;
+                }
+        for (r = 0; r < ROWS && !found; r++)
+            for (c = 0; c < COLS && !found; c++)
+                if (mg_game_cell(game, r, c, &cell) == MG_ERROR_OK && cell.state == MG_CELL_STATE_FACE_DOWN) {
+                    r1 = r; c1 = c; found = 1;
+                }
+        CHECK(found);
+        CHECK(flip(game, r1, c1, known));
+        CHECK(mg_game_flip(game, r1, c1) == MG_ERROR_ALREADY_OPEN);
+        /* its partner if it has been seen, otherwise the next card face down */
+        found = 0;
+        for (r = 0; r < ROWS && !found; r++)
+            for (c = 0; c < COLS && !found; c++)
+                if ((r != r1 || c != c1) && mg_game_cell(game, r, c, &cell) == MG_ERROR_OK
+                        && cell.state == MG_CELL_STATE_FACE_DOWN && known[r][c] == known[r1][c1]) {
+                    r2 = r; c2 = c; found = 1;
+                }
+        for (r = 0; r < ROWS && !found; r++)
+            for (c = 0; c < COLS && !found; c++)
+                if ((r != r1 || c != c1) && mg_game_cell(game, r, c, &cell) == MG_ERROR_OK
+                        && cell.state == MG_CELL_STATE_FACE_DOWN) {
+                    r2 = r; c2 = c; found = 1;
+                }
+        CHECK(found);
+        CHECK(flip(game, r2, c2, known));
+        CHECK(mg_game_expected_action(game) == MG_EXPECTED_CHECK_RESULT);
+        CHECK(mg_game_confirm(game) == MG_ERROR_OK);
+        CHECK(mg_game_last_guess_success(game) == (known[r1][c1] == known[r2][c2]));
+        CHECK(mg_game_expected_action(game) == MG_EXPECTED_PASS_TURN);
+        CHECK(mg_game_confirm(game) == MG_ERROR_OK);
+    }
+
+    for (r = 0; r < mg_game_player_count(game); r++) {
+        CHECK(mg_game_pairs(game, r, &pairs) == MG_ERROR_OK);
+        /* with the classic rules the score is the number of pairs */
+        CHECK(mg_game_score(game, r, &score) == MG_ERROR_OK);
+        CHECK(score == (int32_t)pairs);
+        total += pairs;
+    }
+    CHECK(total == ROWS * COLS / 2);
+    CHECK(mg_game_score(game, 2, &score) == MG_ERROR_INVALID_ARGUMENT);
+    CHECK(mg_game_pairs(game, 2, &pairs) == MG_ERROR_INVALID_ARGUMENT);
+    mg_game_free(game);
+    printf("ffi test passed\n");
+    return 0;
+}
@@ -0,0 +1,4 @@
+#!/bin/sh
+# An engine that says it is ready and then stops reading its input, so the pipe to it fills up.
+echo '{"type":"ready","name":"deaf"}'
+exec sleep 30
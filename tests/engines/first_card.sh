@@ -0,0 +1,14 @@
+#!/bin/sh
+# A minimal engine for the bot protocol (see src/engine.rs): it always flips the first card it
+# may flip. It reads the coordinates from "flippable":[[row,col],...] with sed, a real engine
+# should use a JSON parser.
+while read -r line; do
+    case "$line" in
+        *'"type":"hello"'*)
+            echo '{"type":"ready","name":"first card"}' ;;
+        *'"type":"move"'*)
+            echo "$line" | sed 's/.*"flippable":\[\[\([0-9]*\),\([0-9]*\)\].*/{"type":"flip","row":\1,"col":\2}/' ;;
+        *'"type":"quit"'*)
+            exit 0 ;;
+    esac
+done
@@ -0,0 +1,55 @@
+//! Builds the C program in tests/c against the shared library and runs it, and checks that the
+//! C header is up to date.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_c_program() {
+    // `cargo test` doesn't put the cdylib where the test could find it, so the library is built
+    // into a target directory of the test, without SDL
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(&cargo)
+        .args(["build", "--lib", "--no-default-features", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir").arg(&target_dir)
+        .status()
+        .expect("can't run cargo");
+    assert!(status.success(), "building the library failed");
+    let lib_dir = target_dir.join("debug");
+
+    let program = target_dir.join("ffi_test");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&cc)
+        .arg(root.join("tests/c/ffi_test.c"))
+        .arg("-I").arg(root.join("include"))
+        .arg("-L").arg(&lib_dir)
+        .arg("-lmemory_game")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-o").arg(&program)
+        .status()
+        .expect("can't run the C compiler, set CC");
+    assert!(status.success(), "compiling tests/c/ffi_test.c failed");
+
+    // cargo test puts its own target directory on the library path, which may hold an older build
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+/// include/memory_game.h is checked in so C programs can be built without cbindgen. With the
+/// c-header feature the build generates the header from src/ffi.rs, and it has to be the same.
+#[cfg(feature = "c-header")]
+#[test]
+fn test_header_is_current() {
+    let generated = PathBuf::from(env!("OUT_DIR")).join("memory_game.h");
+    let checked_in = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("include/memory_game.h");
+    let read = |path : &PathBuf| std::fs::read_to_string(path).unwrap_or_else(|e| panic!("can't read {}: {}", path.display(), e));
+    assert!(read(&generated) == read(&checked_in),
+        "include/memory_game.h is out of date, copy {} over it", generated.display());
+}
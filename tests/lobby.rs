@@ -0,0 +1,399 @@
+//! Plays network games through the lobby of a server on the loopback interface.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use memory_game::net::client::{self, Connection};
+use memory_game::net::protocol::{Absence, ClientMessage, RoomInfo, RoomSettings, ServerMessage, MAX_LINE};
+use memory_game::net::server::Server;
+use memory_game::{CellView, Coord, ExpectedAction, PublicView};
+
+/// Starts a server on a free port and returns its address.
+fn start_server() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr();
+    std::thread::spawn(move || server.run());
+    addr
+}
+
+fn connect(addr : SocketAddr) -> Connection {
+    let mut connection = Connection::connect(addr).unwrap();
+    connection.set_timeout(Some(Duration::from_secs(5))).unwrap();
+    connection
+}
+
+fn create(addr : SocketAddr, name : &str, room : &str, settings : RoomSettings) -> Connection {
+    let mut connection = connect(addr);
+    connection.send(&ClientMessage::CreateRoom { players : vec![name.to_string()], room : room.to_string(), settings }).unwrap();
+    connection
+}
+
+fn join(addr : SocketAddr, name : &str, room : &str) -> Connection {
+    let mut connection = connect(addr);
+    connection.send(&ClientMessage::Join { players : vec![name.to_string()], room : room.to_string() }).unwrap();
+    connection
+}
+
+fn list_rooms(addr : SocketAddr) -> Vec<RoomInfo> {
+    let mut connection = connect(addr);
+    connection.send(&ClientMessage::ListRooms).unwrap();
+    match connection.receive().unwrap() {
+        ServerMessage::Rooms { rooms } => rooms,
+        other => panic!("expected rooms, got {:?}", other),
+    }
+}
+
+/// Skips messages until the next state.
+fn next_state(connection : &mut Connection) -> (RoomInfo, PublicView, Vec<bool>, Vec<String>) {
+    loop {
+        match connection.receive().unwrap() {
+            ServerMessage::State { room, view, ready, spectators, .. } => { return (room, view, ready, spectators); },
+            ServerMessage::Error { message } => { panic!("unexpected error {}", message); },
+            _ => {},
+        }
+    }
+}
+
+/// Skips states until one fits the condition.
+fn state_until<F : Fn(&RoomInfo, &PublicView, &[bool]) -> bool>(connection : &mut Connection, condition : F) -> (RoomInfo, PublicView, Vec<bool>, Vec<String>) {
+    loop {
+        let state = next_state(connection);
+        if condition(&state.0, &state.1, &state.2) {
+            return state;
+        }
+    }
+}
+
+fn next_error(connection : &mut Connection) -> String {
+    loop {
+        if let ServerMessage::Error { message } = connection.receive().unwrap() {
+            return message;
+        }
+    }
+}
+
+/// Skips messages until the list of rooms, which comes after leaving a room.
+fn next_rooms(connection : &mut Connection) -> Vec<RoomInfo> {
+    loop {
+        if let ServerMessage::Rooms { rooms } = connection.receive().unwrap() {
+            return rooms;
+        }
+    }
+}
+
+fn welcome(connection : &mut Connection) -> (String, String, Vec<usize>, Option<String>) {
+    match connection.receive().unwrap() {
+        ServerMessage::Welcome { room, code, seats, token } => (room, code, seats, token),
+        other => panic!("expected welcome, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_rooms() {
+    let addr = start_server();
+    assert!(list_rooms(addr).is_empty());
+    let settings = RoomSettings { rows : 2, cols : 4, max_players : 2, ..RoomSettings::default() };
+    let mut anna = create(addr, "Anna", "Friday", settings.clone());
+    let (room, code, seats, _) = welcome(&mut anna);
+    assert_eq!((room.as_str(), seats), ("Friday", vec![0]));
+    next_state(&mut anna);
+    let mut carl = create(addr, "Carl", "Other", RoomSettings::default());
+    welcome(&mut carl);
+    next_state(&mut carl);
+
+    let rooms = list_rooms(addr);
+    assert_eq!(rooms.len(), 2);
+    let friday = rooms.iter().find(|r| r.name == "Friday").unwrap();
+    assert_eq!(friday.code, code);
+    assert_eq!(friday.settings, settings);
+    assert_eq!(friday.players, vec!["Anna".to_string()]);
+    assert_eq!(friday.host.as_deref(), Some("Anna"));
+
+    // join by code, in any case
+    let mut ben = join(addr, "Ben", &code.to_lowercase());
+    assert_eq!(welcome(&mut ben).2, vec![1]);
+    let (info, view, ready, _) = next_state(&mut ben);
+    assert_eq!(info.players, vec!["Anna".to_string(), "Ben".to_string()]);
+    assert_eq!((view.cells.len(), view.cells[0].len()), (2, 4));
+    assert_eq!(ready, vec![false, false]);
+
+    // the room is full
+    let mut dora = join(addr, "Dora", "Friday");
+    assert!(next_error(&mut dora).contains("full"));
+    let mut eve = create(addr, "Eve", "Friday", RoomSettings::default());
+    assert!(next_error(&mut eve).contains("already a room"));
+    let mut odd = create(addr, "Eve", "Odd", RoomSettings { rows : 3, cols : 3, ..RoomSettings::default() });
+    assert!(next_error(&mut odd).contains("odd"));
+
+    // only the host starts, once everybody is ready
+    next_state(&mut anna);
+    ben.send(&ClientMessage::Start).unwrap();
+    assert_eq!(next_error(&mut ben), "Only the host can start the game");
+    anna.send(&ClientMessage::Start).unwrap();
+    assert_eq!(next_error(&mut anna), "Waiting for Ben to be ready");
+    ben.send(&ClientMessage::Ready { ready : true }).unwrap();
+    state_until(&mut anna, |_, _, ready| ready == [false, true]);
+    anna.send(&ClientMessage::Start).unwrap();
+    let (info, view, ready, _) = next_state(&mut anna);
+    assert!(info.started);
+    assert_eq!(ready, vec![false, false]);
+    assert_eq!(view.flippable.len(), 8);
+    let mut late = join(addr, "Late", "Friday");
+    assert!(next_error(&mut late).contains("already started"));
+
+    // the host passes on, empty rooms are closed. Leaving a running game gives the seat up.
+    anna.send(&ClientMessage::Leave).unwrap();
+    assert_eq!(next_rooms(&mut anna).len(), 2);
+    let (_, view, _, _) = state_until(&mut ben, |info, _, _| info.host.as_deref() == Some("Ben"));
+    // Anna's seat stays for the running game
+    assert_eq!(view.players.len(), 2);
+    assert_eq!(view.current_player_id, 1);
+    ben.send(&ClientMessage::Leave).unwrap();
+    drop(carl);
+    for _ in 0..100 {
+        if list_rooms(addr).is_empty() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("the empty rooms weren't closed: {:?}", list_rooms(addr));
+}
+
+#[test]
+fn test_leaving_before_start_frees_the_seat() {
+    let addr = start_server();
+    let mut anna = create(addr, "Anna", "Tea", RoomSettings::default());
+    welcome(&mut anna);
+    let mut ben = join(addr, "Ben", "Tea");
+    welcome(&mut ben);
+    let mut carl = join(addr, "Carl", "Tea");
+    welcome(&mut carl);
+    ben.send(&ClientMessage::Leave).unwrap();
+    next_rooms(&mut ben);
+    let (info, view, _, _) = state_until(&mut carl, |info, _, _| info.players.len() == 2);
+    assert_eq!(info.players, vec!["Anna".to_string(), "Carl".to_string()]);
+    assert_eq!(view.players.len(), 2);
+    // Carl moved up to seat 1 and can still get ready
+    carl.send(&ClientMessage::Ready { ready : true }).unwrap();
+    state_until(&mut carl, |_, _, ready| ready == [false, true]);
+}
+
+#[test]
+fn test_spectator_joins_running_game() {
+    let addr = start_server();
+    let mut anna = create(addr, "Anna", "Match", RoomSettings { rows : 2, cols : 4, ..RoomSettings::default() });
+    welcome(&mut anna);
+    let mut ben = join(addr, "Ben", "Match");
+    welcome(&mut ben);
+    ben.send(&ClientMessage::Ready { ready : true }).unwrap();
+    state_until(&mut ben, |_, _, ready| ready == [false, true]);
+    anna.send(&ClientMessage::Start).unwrap();
+    state_until(&mut anna, |info, _, _| info.started);
+    anna.send(&ClientMessage::Flip { row : 0, col : 0 }).unwrap();
+    next_state(&mut anna);
+
+    // the spectator gets the running game first
+    let mut sam = connect(addr);
+    sam.send(&ClientMessage::Watch { name : "Sam".to_string(), room : "Match".to_string() }).unwrap();
+    assert!(welcome(&mut sam).2.is_empty());
+    let (info, view, _, spectators) = next_state(&mut sam);
+    assert!(info.started);
+    assert_eq!(spectators, vec!["Sam".to_string()]);
+    assert!(matches!(view.cell(&Coord(0, 0)), Some(CellView::FaceUp { .. })));
+    // only the open card shows its type
+    assert_eq!(serde_json::to_string(&view).unwrap().matches("card_type").count(), 1);
+
+    // players see who is watching
+    loop {
+        if next_state(&mut anna).3 == ["Sam".to_string()] {
+            break;
+        }
+    }
+
+    // spectators and players out of turn can't play
+    sam.send(&ClientMessage::Flip { row : 0, col : 1 }).unwrap();
+    assert_eq!(next_error(&mut sam), "Spectators can't play");
+    ben.send(&ClientMessage::Flip { row : 0, col : 1 }).unwrap();
+    assert_eq!(next_error(&mut ben), "It's not your turn");
+
+    // the spectator follows the game
+    anna.send(&ClientMessage::Flip { row : 0, col : 1 }).unwrap();
+    state_until(&mut sam, |_, view, _| matches!(view.cell(&Coord(0, 1)), Some(CellView::FaceUp { .. })));
+
+    let mut private = create(addr, "Carl", "Private", RoomSettings { spectators : false, ..RoomSettings::default() });
+    welcome(&mut private);
+    let mut peeker = connect(addr);
+    peeker.send(&ClientMessage::Watch { name : "Peeker".to_string(), room : "Private".to_string() }).unwrap();
+    assert!(next_error(&mut peeker).contains("doesn't allow spectators"));
+}
+
+/// Creates a room for Anna and Ben and starts the game. Returns their connections and Anna's token.
+fn start_game(addr : SocketAddr, room : &str, settings : RoomSettings) -> (Connection, Connection, String) {
+    let mut anna = create(addr, "Anna", room, settings);
+    let token = welcome(&mut anna).3.unwrap();
+    let mut ben = join(addr, "Ben", room);
+    welcome(&mut ben);
+    ben.send(&ClientMessage::Ready { ready : true }).unwrap();
+    state_until(&mut anna, |_, _, ready| ready == [false, true]);
+    anna.send(&ClientMessage::Start).unwrap();
+    state_until(&mut anna, |info, _, _| info.started);
+    state_until(&mut ben, |info, _, _| info.started);
+    (anna, ben, token)
+}
+
+fn next_away(connection : &mut Connection) -> (PublicView, Vec<bool>) {
+    loop {
+        if let ServerMessage::State { view, away, .. } = connection.receive().unwrap() {
+            return (view, away);
+        }
+    }
+}
+
+#[test]
+fn test_resume_after_dropped_connection() {
+    let addr = start_server();
+    let (anna, mut ben, token) = start_game(addr, "Pause", RoomSettings { rows : 2, cols : 4, ..RoomSettings::default() });
+    drop(anna);
+    // the game waits for Anna
+    let (view, away) = next_away(&mut ben);
+    assert_eq!(away, vec![true, false]);
+    assert_eq!(view.current_player_id, 0);
+    ben.send(&ClientMessage::Flip { row : 0, col : 0 }).unwrap();
+    assert_eq!(next_error(&mut ben), "It's not your turn");
+
+    let mut stranger = connect(addr);
+    stranger.send(&ClientMessage::Resume { token : "nonsense".to_string() }).unwrap();
+    assert!(next_error(&mut stranger).contains("no seat"));
+
+    let mut anna = connect(addr);
+    anna.send(&ClientMessage::Resume { token : token.clone() }).unwrap();
+    let (room, _, seats, new_token) = welcome(&mut anna);
+    assert_eq!((room.as_str(), seats, new_token), ("Pause", vec![0], Some(token)));
+    let (info, view, _, _) = next_state(&mut anna);
+    assert!(info.started);
+    assert_eq!(view.flippable.len(), 8);
+    assert_eq!(next_away(&mut ben).1, vec![false, false]);
+    anna.send(&ClientMessage::Flip { row : 0, col : 0 }).unwrap();
+    let (view, _) = next_away(&mut ben);
+    assert!(matches!(view.cell(&Coord(0, 0)), Some(CellView::FaceUp { .. })));
+}
+
+#[test]
+fn test_client_resumes() {
+    let addr = start_server();
+    let (anna, mut ben, token) = start_game(addr, "Again", RoomSettings { rows : 2, cols : 4, ..RoomSettings::default() });
+    drop(anna);
+    assert_eq!(next_away(&mut ben).1, vec![true, false]);
+    let (mut anna, seats) = client::resume(&addr.to_string(), &token, Duration::from_secs(5)).unwrap();
+    assert_eq!(seats, vec![0]);
+    assert!(next_state(&mut anna).0.started);
+
+    // a refused token isn't tried again
+    let error = client::resume(&addr.to_string(), "nonsense", Duration::from_secs(60)).err().unwrap();
+    assert!(error.contains("no seat"));
+    // nobody listens on the port of a closed server
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let error = client::resume(&closed.to_string(), &token, Duration::ZERO).err().unwrap();
+    assert!(error.contains("giving up"));
+}
+
+#[test]
+fn test_absent_players_are_skipped() {
+    let addr = start_server();
+    let settings = RoomSettings { rows : 2, cols : 4, reconnect_seconds : 1, absence : Absence::Skip, ..RoomSettings::default() };
+    let (anna, mut ben, token) = start_game(addr, "Skip", settings);
+    drop(anna);
+    // Anna's turn passes to Ben right away, and comes back to him after each of his turns
+    let (view, away) = next_away(&mut ben);
+    assert_eq!((view.current_player_id, away), (1, vec![true, false]));
+    for col in 0..2 {
+        ben.send(&ClientMessage::Flip { row : 0, col }).unwrap();
+    }
+    ben.send(&ClientMessage::Confirm).unwrap();
+    ben.send(&ClientMessage::Confirm).unwrap();
+    let (_, view, _, _) = state_until(&mut ben, |_, view, _| view.round == 2 && view.expected_action == ExpectedAction::FlipFirstCard);
+    assert_eq!(view.current_player_id, 1);
+
+    // after the reconnect time the seat is given up
+    let (info, _, _, _) = state_until(&mut ben, |info, _, _| info.players.len() == 1);
+    assert_eq!(info.players, vec!["Ben".to_string()]);
+    let mut anna = connect(addr);
+    anna.send(&ClientMessage::Resume { token }).unwrap();
+    assert!(next_error(&mut anna).contains("no seat"));
+}
+
+#[test]
+fn test_one_client_plays_for_several_players() {
+    let addr = start_server();
+    let settings = RoomSettings { rows : 2, cols : 4, max_players : 3, ..RoomSettings::default() };
+    let mut home = connect(addr);
+    let players = vec!["Anna".to_string(), "Ben".to_string()];
+    home.send(&ClientMessage::CreateRoom { players, room : "Sofa".to_string(), settings }).unwrap();
+    let (_, _, seats, token) = welcome(&mut home);
+    assert_eq!(seats, vec![0, 1]);
+    assert!(token.is_some());
+
+    // names are unique in a room, and there must be a free seat for every player
+    let mut twice = connect(addr);
+    twice.send(&ClientMessage::Join { players : vec!["Dora".to_string(), "Dora".to_string()], room : "Sofa".to_string() }).unwrap();
+    assert!(next_error(&mut twice).contains("already somebody called Dora"));
+    let mut taken = join(addr, "Ben", "Sofa");
+    assert!(next_error(&mut taken).contains("already somebody called Ben"));
+    let mut crowd = connect(addr);
+    crowd.send(&ClientMessage::Join { players : vec!["Carl".to_string(), "Dora".to_string()], room : "Sofa".to_string() }).unwrap();
+    assert!(next_error(&mut crowd).contains("full"));
+    let mut nobody = connect(addr);
+    nobody.send(&ClientMessage::Join { players : Vec::new(), room : "Sofa".to_string() }).unwrap();
+    assert!(next_error(&mut nobody).contains("at least one player"));
+
+    let mut carl = join(addr, "Carl", "Sofa");
+    assert_eq!(welcome(&mut carl).2, vec![2]);
+    let (info, _, _, _) = next_state(&mut carl);
+    assert_eq!(info.players, vec!["Anna".to_string(), "Ben".to_string(), "Carl".to_string()]);
+    assert_eq!(info.host.as_deref(), Some("Anna"));
+    carl.send(&ClientMessage::Ready { ready : true }).unwrap();
+    state_until(&mut home, |_, _, ready| ready == [false, false, true]);
+    home.send(&ClientMessage::Start).unwrap();
+    let (_, mut view, _, _) = state_until(&mut home, |info, _, _| info.started);
+
+    // the client plays the turns of both its players, but not Carl's
+    for current in 0..2 {
+        let Coord(row, col) = view.flippable[0];
+        carl.send(&ClientMessage::Flip { row, col }).unwrap();
+        assert_eq!(next_error(&mut carl), "It's not your turn");
+        for coord in &view.flippable[..2] {
+            home.send(&ClientMessage::Flip { row : coord.0, col : coord.1 }).unwrap();
+        }
+        home.send(&ClientMessage::Confirm).unwrap();
+        home.send(&ClientMessage::Confirm).unwrap();
+        view = state_until(&mut home, |_, view, _| view.expected_action == ExpectedAction::FlipFirstCard && view.current_player_id != current).1;
+        assert_eq!(view.current_player_id, current + 1);
+    }
+    let Coord(row, col) = view.flippable[0];
+    home.send(&ClientMessage::Flip { row, col }).unwrap();
+    assert_eq!(next_error(&mut home), "It's not your turn");
+    carl.send(&ClientMessage::Flip { row, col }).unwrap();
+    state_until(&mut carl, |_, view, _| view.expected_action == ExpectedAction::FlipSecondCard);
+}
+
+#[test]
+fn test_overlong_line_ends_the_connection() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let addr = start_server();
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    // a line that never ends
+    stream.write_all(&vec![b'x'; MAX_LINE]).unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    match serde_json::from_str(&line).unwrap() {
+        ServerMessage::Error { message } => { assert!(message.contains("at most")); },
+        other => { panic!("expected an error, got {:?}", other); },
+    }
+    line.clear();
+    assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+    // the server goes on serving the others
+    assert!(list_rooms(addr).is_empty());
+}